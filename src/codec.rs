@@ -0,0 +1,615 @@
+//! Compact binary encoding for component, resource and event data.
+//!
+//! [`Writer`] appends values to a [`Bytes`] buffer and [`Reader`] reads them
+//! back, returning a [`CodecError`] instead of panicking on truncated or
+//! malformed input. Values carry no type tags, so data must be read back in the
+//! order it was written.
+//!
+//! # Wire format
+//!
+//! | Value | Encoding |
+//! |-------|----------|
+//! | `u8` | one byte |
+//! | `bool` | one byte, `0` or `1` |
+//! | packed `bool`s | eight per byte, first one in the lowest bit, unused bits zero |
+//! | `u32`, `u64` | LEB128 varint: seven bits per byte, lowest first, high bit set on every byte but the last |
+//! | `i32`, `i64` | zigzag (`0, -1, 1, -2, …` to `0, 1, 2, 3, …`), then a varint |
+//! | [`Bytes`], [`String`] | varint length, then the raw bytes (UTF-8 for strings) |
+//! | [`Symbol`] | varint length, then its characters |
+//! | [`Address`] | `0` then the 32-byte Ed25519 key of an account, or `1` then the 32-byte hash of a contract |
+//! | [`EntityId`] | varint id, then varint generation |
+//!
+//! Small numbers take one byte, so typical components shrink to a fraction of
+//! their `#[contracttype]` XDR size, where every integer is a 4 or 8 byte
+//! field behind a 4 byte type tag and every struct field is keyed by name. A
+//! `Position` of `(3, -4)` is 2 bytes here against 52 bytes of XDR for a
+//! `#[contracttype]` struct with `x: i32` and `y: i32`.
+
+use crate::entity::EntityId;
+use alloc::vec::Vec as StdVec;
+use soroban_sdk::xdr::{FromXdr, ToXdr};
+use soroban_sdk::{Address, Bytes, Env, String, Symbol};
+
+/// XDR discriminant of `ScVal::Address`
+const SCV_ADDRESS: [u8; 4] = [0, 0, 0, 18];
+/// Longest symbol the host accepts
+const MAX_SYMBOL_LEN: u32 = 32;
+
+const ADDRESS_ACCOUNT: u8 = 0;
+const ADDRESS_CONTRACT: u8 = 1;
+
+/// Errors returned when encoding or decoding data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodecError {
+    /// The data ended in the middle of a value
+    UnexpectedEnd,
+    /// A varint does not fit in the integer type being read
+    VarintOverflow,
+    /// A bool byte is neither 0 nor 1
+    InvalidBool(u8),
+    /// The unused bits of packed bools are not zero
+    NonZeroPadding,
+    /// A string is not valid UTF-8
+    InvalidUtf8,
+    /// A symbol is too long or contains characters outside `[a-zA-Z0-9_]`
+    InvalidSymbol,
+    /// An address is neither an account nor a contract address
+    UnsupportedAddress,
+    /// Bytes are left over after the last value was read
+    TrailingBytes,
+}
+
+/// Map a zigzag-encoded value back to a signed integer
+pub fn zigzag_decode(value: u64) -> i64 {
+    ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+/// Map a signed integer to an unsigned one, keeping small magnitudes small
+pub fn zigzag_encode(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+/// Get the number of bytes `value` takes as a varint
+pub fn varint_len(value: u64) -> u32 {
+    let bits = 64 - value.leading_zeros();
+    bits.max(1).saturating_add(6) / 7
+}
+
+/// Appends encoded values to a byte buffer
+#[derive(Debug, Clone)]
+pub struct Writer {
+    bytes: Bytes,
+}
+
+impl Writer {
+    /// Create a writer with an empty buffer
+    pub fn new(env: &Env) -> Self {
+        Self {
+            bytes: Bytes::new(env),
+        }
+    }
+
+    /// Get the number of bytes written
+    pub fn len(&self) -> u32 {
+        self.bytes.len()
+    }
+
+    /// Check whether nothing has been written
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Get the written bytes
+    pub fn finish(self) -> Bytes {
+        self.bytes
+    }
+
+    pub fn write_u8(&mut self, value: u8) -> &mut Self {
+        self.bytes.push_back(value);
+        self
+    }
+
+    pub fn write_bool(&mut self, value: bool) -> &mut Self {
+        self.write_u8(value as u8)
+    }
+
+    /// Write `values` packed eight to a byte
+    pub fn write_bools(&mut self, values: &[bool]) -> &mut Self {
+        for chunk in values.chunks(8) {
+            let byte = chunk
+                .iter()
+                .enumerate()
+                .fold(0u8, |byte, (bit, value)| byte | ((*value as u8) << bit));
+            self.write_u8(byte);
+        }
+        self
+    }
+
+    pub fn write_u32(&mut self, value: u32) -> &mut Self {
+        self.write_u64(value as u64)
+    }
+
+    pub fn write_u64(&mut self, mut value: u64) -> &mut Self {
+        let mut buf = [0u8; 10];
+        let mut len = 0;
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                buf[len] = byte;
+                len += 1;
+                break;
+            }
+            buf[len] = byte | 0x80;
+            len += 1;
+        }
+        self.bytes.extend_from_slice(&buf[..len]);
+        self
+    }
+
+    pub fn write_i32(&mut self, value: i32) -> &mut Self {
+        self.write_i64(value as i64)
+    }
+
+    pub fn write_i64(&mut self, value: i64) -> &mut Self {
+        self.write_u64(zigzag_encode(value))
+    }
+
+    /// Write `value` behind its length
+    pub fn write_bytes(&mut self, value: &Bytes) -> &mut Self {
+        self.write_u32(value.len());
+        self.bytes.append(value);
+        self
+    }
+
+    /// Write the UTF-8 bytes of `value` behind their length
+    pub fn write_string(&mut self, value: &String) -> &mut Self {
+        let mut buf = alloc::vec![0u8; value.len() as usize];
+        value.copy_into_slice(&mut buf);
+        self.write_u32(value.len());
+        self.bytes.extend_from_slice(&buf);
+        self
+    }
+
+    /// Write the characters of `value` behind their length
+    pub fn write_symbol(&mut self, value: &Symbol) -> &mut Self {
+        // The characters of a symbol are only reachable through its XDR:
+        // the discriminant, the length and the padded characters
+        let xdr = value.clone().to_xdr(self.bytes.env());
+        let len = read_be_u32(&xdr, 4);
+        self.write_u32(len);
+        self.bytes.append(&xdr.slice(8..8 + len));
+        self
+    }
+
+    /// Write `value` as a type byte and its 32-byte key or hash
+    ///
+    /// Panics on address types other than accounts and contracts, which
+    /// contracts cannot hold.
+    pub fn write_address(&mut self, value: &Address) -> &mut Self {
+        let xdr = value.clone().to_xdr(self.bytes.env());
+        // The ScAddress type follows the ScVal discriminant; account keys are
+        // further preceded by their public key type
+        match read_be_u32(&xdr, 4) {
+            0 => {
+                self.write_u8(ADDRESS_ACCOUNT);
+                self.bytes.append(&xdr.slice(12..44));
+            }
+            1 => {
+                self.write_u8(ADDRESS_CONTRACT);
+                self.bytes.append(&xdr.slice(8..40));
+            }
+            _ => panic!("unsupported address type"),
+        }
+        self
+    }
+
+    pub fn write_entity_id(&mut self, value: EntityId) -> &mut Self {
+        self.write_u64(value.id()).write_u32(value.generation())
+    }
+}
+
+fn read_be_u32(data: &Bytes, offset: u32) -> u32 {
+    let mut buf = [0u8; 4];
+    data.slice(offset..offset + 4).copy_into_slice(&mut buf);
+    u32::from_be_bytes(buf)
+}
+
+/// Reads encoded values from a byte buffer, in the order they were written
+#[derive(Debug, Clone)]
+pub struct Reader<'a> {
+    data: &'a Bytes,
+    offset: u32,
+}
+
+impl<'a> Reader<'a> {
+    /// Create a reader at the start of `data`
+    pub fn new(data: &'a Bytes) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    /// Get the offset of the next byte to read
+    pub fn position(&self) -> u32 {
+        self.offset
+    }
+
+    /// Get the number of bytes left to read
+    pub fn remaining(&self) -> u32 {
+        self.data.len() - self.offset
+    }
+
+    /// Check that every byte was read
+    pub fn finish(self) -> Result<(), CodecError> {
+        if self.remaining() > 0 {
+            return Err(CodecError::TrailingBytes);
+        }
+        Ok(())
+    }
+
+    fn env(&self) -> &Env {
+        self.data.env()
+    }
+
+    /// Take the next `len` bytes
+    fn take(&mut self, len: u32) -> Result<Bytes, CodecError> {
+        if len > self.remaining() {
+            return Err(CodecError::UnexpectedEnd);
+        }
+        let bytes = self.data.slice(self.offset..self.offset + len);
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    /// Take the next `len` bytes into a buffer
+    fn take_vec(&mut self, len: u32) -> Result<StdVec<u8>, CodecError> {
+        let bytes = self.take(len)?;
+        let mut buf = alloc::vec![0u8; len as usize];
+        bytes.copy_into_slice(&mut buf);
+        Ok(buf)
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, CodecError> {
+        let byte = self
+            .data
+            .get(self.offset)
+            .ok_or(CodecError::UnexpectedEnd)?;
+        self.offset += 1;
+        Ok(byte)
+    }
+
+    pub fn read_bool(&mut self) -> Result<bool, CodecError> {
+        match self.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            byte => Err(CodecError::InvalidBool(byte)),
+        }
+    }
+
+    /// Read `N` bools packed eight to a byte
+    pub fn read_bools<const N: usize>(&mut self) -> Result<[bool; N], CodecError> {
+        let mut values = [false; N];
+        for chunk in values.chunks_mut(8) {
+            let byte = self.read_u8()?;
+            if chunk.len() < 8 && byte >> chunk.len() != 0 {
+                return Err(CodecError::NonZeroPadding);
+            }
+            for (bit, value) in chunk.iter_mut().enumerate() {
+                *value = byte & (1 << bit) != 0;
+            }
+        }
+        Ok(values)
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, CodecError> {
+        u32::try_from(self.read_u64()?).map_err(|_| CodecError::VarintOverflow)
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64, CodecError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.read_u8()?;
+            let bits = (byte & 0x7f) as u64;
+            // The tenth byte only has room for the highest bit
+            if shift == 63 && bits > 1 {
+                return Err(CodecError::VarintOverflow);
+            }
+            value |= bits << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(CodecError::VarintOverflow)
+    }
+
+    pub fn read_i32(&mut self) -> Result<i32, CodecError> {
+        i32::try_from(self.read_i64()?).map_err(|_| CodecError::VarintOverflow)
+    }
+
+    pub fn read_i64(&mut self) -> Result<i64, CodecError> {
+        self.read_u64().map(zigzag_decode)
+    }
+
+    pub fn read_bytes(&mut self) -> Result<Bytes, CodecError> {
+        let len = self.read_u32()?;
+        self.take(len)
+    }
+
+    pub fn read_string(&mut self) -> Result<String, CodecError> {
+        let len = self.read_u32()?;
+        let buf = self.take_vec(len)?;
+        core::str::from_utf8(&buf).map_err(|_| CodecError::InvalidUtf8)?;
+        Ok(String::from_bytes(self.env(), &buf))
+    }
+
+    pub fn read_symbol(&mut self) -> Result<Symbol, CodecError> {
+        let len = self.read_u32()?;
+        if len > MAX_SYMBOL_LEN {
+            return Err(CodecError::InvalidSymbol);
+        }
+        let buf = self.take_vec(len)?;
+        if !buf.iter().all(|c| c.is_ascii_alphanumeric() || *c == b'_') {
+            return Err(CodecError::InvalidSymbol);
+        }
+        // Only ASCII characters got through
+        let chars = core::str::from_utf8(&buf).map_err(|_| CodecError::InvalidSymbol)?;
+        Ok(Symbol::new(self.env(), chars))
+    }
+
+    pub fn read_address(&mut self) -> Result<Address, CodecError> {
+        let mut xdr = Bytes::from_array(self.env(), &SCV_ADDRESS);
+        match self.read_u8()? {
+            ADDRESS_ACCOUNT => xdr.extend_from_array(&[0, 0, 0, 0, 0, 0, 0, 0]),
+            ADDRESS_CONTRACT => xdr.extend_from_array(&[0, 0, 0, 1]),
+            _ => return Err(CodecError::UnsupportedAddress),
+        }
+        xdr.append(&self.take(32)?);
+        Address::from_xdr(self.env(), &xdr).map_err(|_| CodecError::UnsupportedAddress)
+    }
+
+    pub fn read_entity_id(&mut self) -> Result<EntityId, CodecError> {
+        let id = self.read_u64()?;
+        Ok(EntityId::new(id, self.read_u32()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use soroban_sdk::testutils::Address as _;
+    use soroban_sdk::{contract, contracttype, symbol_short, IntoVal, Val};
+
+    #[contract]
+    struct CodecTestContract;
+
+    fn roundtrip(env: &Env, write: impl Fn(&mut Writer)) -> Bytes {
+        let mut writer = Writer::new(env);
+        write(&mut writer);
+        writer.finish()
+    }
+
+    #[test]
+    fn test_varints_and_zigzag() {
+        let env = Env::default();
+        let values = [
+            0u64,
+            1,
+            127,
+            128,
+            300,
+            16_383,
+            16_384,
+            u32::MAX as u64,
+            u64::MAX,
+        ];
+        let data = roundtrip(&env, |writer| {
+            for value in values {
+                writer.write_u64(value);
+            }
+        });
+        let expected_len: u32 = values.iter().map(|value| varint_len(*value)).sum();
+        assert_eq!(data.len(), expected_len);
+        assert_eq!(varint_len(127), 1);
+        assert_eq!(varint_len(128), 2);
+        assert_eq!(varint_len(u64::MAX), 10);
+        let mut reader = Reader::new(&data);
+        for value in values {
+            assert_eq!(reader.read_u64(), Ok(value));
+        }
+        assert_eq!(reader.finish(), Ok(()));
+
+        for value in [
+            0i64,
+            -1,
+            1,
+            -2,
+            63,
+            -64,
+            64,
+            i32::MIN as i64,
+            i64::MAX,
+            i64::MIN,
+        ] {
+            assert_eq!(zigzag_decode(zigzag_encode(value)), value);
+        }
+        assert_eq!([0, -1, 1, -2].map(zigzag_encode), [0, 1, 2, 3]);
+        let data = roundtrip(&env, |writer| {
+            writer
+                .write_i32(-64)
+                .write_i32(i32::MIN)
+                .write_i64(i64::MIN);
+        });
+        let mut reader = Reader::new(&data);
+        assert_eq!(reader.read_i32(), Ok(-64));
+        assert_eq!(reader.read_i32(), Ok(i32::MIN));
+        assert_eq!(reader.read_i64(), Ok(i64::MIN));
+        assert_eq!(data.len(), 1 + 5 + 10);
+    }
+
+    #[test]
+    fn test_malformed_input_is_an_error() {
+        let env = Env::default();
+        // A varint whose continuation bit promises more bytes
+        let data = Bytes::from_array(&env, &[0x80]);
+        assert_eq!(
+            Reader::new(&data).read_u64(),
+            Err(CodecError::UnexpectedEnd)
+        );
+        // Eleven bytes, or a tenth byte with more than one bit
+        let data = Bytes::from_array(&env, &[0xff; 11]);
+        assert_eq!(
+            Reader::new(&data).read_u64(),
+            Err(CodecError::VarintOverflow)
+        );
+        let mut bytes = [0xffu8; 10];
+        bytes[9] = 0x02;
+        let data = Bytes::from_array(&env, &bytes);
+        assert_eq!(
+            Reader::new(&data).read_u64(),
+            Err(CodecError::VarintOverflow)
+        );
+        // A u64 read back as a u32
+        let data = roundtrip(&env, |writer| {
+            writer.write_u64(u32::MAX as u64 + 1);
+        });
+        assert_eq!(
+            Reader::new(&data).read_u32(),
+            Err(CodecError::VarintOverflow)
+        );
+
+        let data = Bytes::from_array(&env, &[2]);
+        assert_eq!(
+            Reader::new(&data).read_bool(),
+            Err(CodecError::InvalidBool(2))
+        );
+        // A length longer than the data
+        let data = Bytes::from_array(&env, &[5, 1, 2]);
+        assert_eq!(
+            Reader::new(&data).read_bytes(),
+            Err(CodecError::UnexpectedEnd)
+        );
+        let data = Bytes::from_array(&env, &[2, b'a', b'-']);
+        assert_eq!(
+            Reader::new(&data).read_symbol(),
+            Err(CodecError::InvalidSymbol)
+        );
+        let data = Bytes::from_array(&env, &[2, 0xc3, 0x28]);
+        assert_eq!(
+            Reader::new(&data).read_string(),
+            Err(CodecError::InvalidUtf8)
+        );
+        let data = Bytes::from_array(&env, &[7]);
+        assert_eq!(
+            Reader::new(&data).read_address(),
+            Err(CodecError::UnsupportedAddress)
+        );
+
+        let data = Bytes::from_array(&env, &[1, 2]);
+        let mut reader = Reader::new(&data);
+        assert_eq!(reader.read_u8(), Ok(1));
+        assert_eq!(reader.remaining(), 1);
+        assert_eq!(reader.finish(), Err(CodecError::TrailingBytes));
+    }
+
+    #[test]
+    fn test_packed_bools() {
+        let env = Env::default();
+        let flags = [
+            true, false, true, true, false, false, false, true, true, false,
+        ];
+        let data = roundtrip(&env, |writer| {
+            writer.write_bools(&flags).write_bool(true);
+        });
+        assert_eq!(data.len(), 3);
+        let mut reader = Reader::new(&data);
+        assert_eq!(reader.read_bools::<10>(), Ok(flags));
+        assert_eq!(reader.read_bool(), Ok(true));
+
+        let data = Bytes::from_array(&env, &[0b1000_0101]);
+        assert_eq!(
+            Reader::new(&data).read_bools::<3>(),
+            Err(CodecError::NonZeroPadding)
+        );
+    }
+
+    #[test]
+    fn test_symbols_strings_and_addresses() {
+        let env = Env::default();
+        let short = symbol_short!("fire");
+        let long = Symbol::new(&env, "critical_hit_from_behind_32chars");
+        let text = String::from_str(&env, "héllo");
+        let account = Address::generate(&env);
+        let contract = env.register(CodecTestContract, ());
+        let entity_id = EntityId::new(300, 2);
+        let data = roundtrip(&env, |writer| {
+            writer
+                .write_symbol(&short)
+                .write_symbol(&long)
+                .write_string(&text)
+                .write_bytes(&Bytes::from_array(&env, &[9, 8]))
+                .write_address(&account)
+                .write_address(&contract)
+                .write_entity_id(entity_id);
+        });
+        let mut reader = Reader::new(&data);
+        assert_eq!(reader.read_symbol(), Ok(short));
+        assert_eq!(reader.read_symbol(), Ok(long));
+        assert_eq!(reader.read_string(), Ok(text));
+        assert_eq!(reader.read_bytes(), Ok(Bytes::from_array(&env, &[9, 8])));
+        assert_eq!(reader.read_address(), Ok(account));
+        assert_eq!(reader.read_address(), Ok(contract));
+        assert_eq!(reader.read_entity_id(), Ok(entity_id));
+        assert_eq!(reader.finish(), Ok(()));
+        // 5 + 33 + 7 + 3 + 2 * 33 + 3
+        assert_eq!(data.len(), 117);
+    }
+
+    #[contracttype]
+    #[derive(Clone)]
+    struct XdrPosition {
+        x: i32,
+        y: i32,
+    }
+
+    #[contracttype]
+    #[derive(Clone)]
+    struct XdrUnit {
+        id: u64,
+        hp: i32,
+        kind: Symbol,
+        alive: bool,
+        flying: bool,
+    }
+
+    #[test]
+    fn test_smaller_than_contracttype_xdr() {
+        let env = Env::default();
+        let xdr_len = |value: Val| value.to_xdr(&env).len();
+
+        let position: Val = XdrPosition { x: 3, y: -4 }.into_val(&env);
+        let compact = roundtrip(&env, |writer| {
+            writer.write_i32(3).write_i32(-4);
+        });
+        assert_eq!(compact.len(), 2);
+        assert_eq!(xdr_len(position), 52);
+
+        let kind = symbol_short!("archer");
+        let unit: Val = XdrUnit {
+            id: 42,
+            hp: 100,
+            kind: kind.clone(),
+            alive: true,
+            flying: false,
+        }
+        .into_val(&env);
+        let compact = roundtrip(&env, |writer| {
+            writer
+                .write_u64(42)
+                .write_i32(100)
+                .write_symbol(&kind)
+                .write_bools(&[true, false]);
+        });
+        // 1 + 2 + 7 + 1 bytes against well over a hundred
+        assert_eq!(compact.len(), 11);
+        assert!(xdr_len(unit) > 10 * compact.len());
+    }
+}
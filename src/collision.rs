@@ -0,0 +1,664 @@
+//! Geometric collision detection.
+//!
+//! A [`Collider`] component gives an entity with a [`Position`] a shape:
+//! an axis-aligned box, a circle, a point or a grid cell, all centered on (or,
+//! for cells, containing) the position. Narrow-phase tests use integer math
+//! only, so they are exact and deterministic.
+//!
+//! Colliders sit on a layer and carry a mask of the layers they interact
+//! with; two colliders are only tested if each one's layer is in the other's
+//! mask. [`CollisionSystem`] sends a [`CollisionEvent`] for every pair of
+//! entities that actually overlap, using the world's
+//! [`SpatialHash`](crate::spatial::SpatialHash) as broad phase when enabled.
+
+use crate::component::ComponentTrait;
+use crate::components::Position;
+use crate::entity::EntityId;
+use crate::event::CollisionEvent;
+use crate::system::System;
+use crate::world::World;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec as StdVec;
+use soroban_sdk::{symbol_short, Bytes, Env, Symbol};
+
+/// Geometry of a [`Collider`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shape {
+    /// Box extending `half_width` and `half_height` from the position, edges
+    /// included; negative sizes count as their absolute value
+    Aabb { half_width: i32, half_height: i32 },
+    /// Disc of `radius` around the position, edge included; a negative radius
+    /// counts as its absolute value
+    Circle { radius: i32 },
+    /// The position itself
+    Point,
+    /// The cell of a grid with square cells of `size` that contains the position
+    Cell { size: i32 },
+}
+
+/// Shape placed in the world, in the form the narrow phase works on
+enum Placed {
+    /// Inclusive bounds
+    Rect {
+        min_x: i64,
+        min_y: i64,
+        max_x: i64,
+        max_y: i64,
+    },
+    Circle {
+        x: i64,
+        y: i64,
+        radius: i64,
+    },
+}
+
+impl Shape {
+    fn place(&self, position: &Position) -> Placed {
+        let (x, y) = (position.x as i64, position.y as i64);
+        match *self {
+            Shape::Aabb {
+                half_width,
+                half_height,
+            } => {
+                let (half_width, half_height) = (
+                    half_width.unsigned_abs() as i64,
+                    half_height.unsigned_abs() as i64,
+                );
+                Placed::Rect {
+                    min_x: x - half_width,
+                    min_y: y - half_height,
+                    max_x: x + half_width,
+                    max_y: y + half_height,
+                }
+            }
+            Shape::Circle { radius } => Placed::Circle {
+                x,
+                y,
+                radius: radius.unsigned_abs() as i64,
+            },
+            Shape::Point => Placed::Rect {
+                min_x: x,
+                min_y: y,
+                max_x: x,
+                max_y: y,
+            },
+            Shape::Cell { size } => {
+                let size = (size as i64).max(1);
+                let (cell_x, cell_y) = (x.div_euclid(size) * size, y.div_euclid(size) * size);
+                Placed::Rect {
+                    min_x: cell_x,
+                    min_y: cell_y,
+                    max_x: cell_x + size - 1,
+                    max_y: cell_y + size - 1,
+                }
+            }
+        }
+    }
+
+    /// Smallest box around the shape placed at `position`, edges included
+    pub fn bounding_box(&self, position: &Position) -> (Position, Position) {
+        let (min_x, min_y, max_x, max_y) = match self.place(position) {
+            Placed::Rect {
+                min_x,
+                min_y,
+                max_x,
+                max_y,
+            } => (min_x, min_y, max_x, max_y),
+            Placed::Circle { x, y, radius } => (x - radius, y - radius, x + radius, y + radius),
+        };
+        let clamp = |value: i64| value.clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+        (
+            Position::new(clamp(min_x), clamp(min_y)),
+            Position::new(clamp(max_x), clamp(max_y)),
+        )
+    }
+}
+
+/// Check whether the offset `(dx, dy)` is at most `reach` long
+///
+/// Squared in `i128`, since offsets between `i32` positions overflow `i64`
+/// once squared and summed.
+fn within_reach(dx: i64, dy: i64, reach: i64) -> bool {
+    let (dx, dy, reach) = (dx as i128, dy as i128, reach as i128);
+    dx * dx + dy * dy <= reach * reach
+}
+
+fn rect_circle_overlap(
+    (min_x, min_y, max_x, max_y): (i64, i64, i64, i64),
+    (x, y, radius): (i64, i64, i64),
+) -> bool {
+    let dx = x.clamp(min_x, max_x) - x;
+    let dy = y.clamp(min_y, max_y) - y;
+    within_reach(dx, dy, radius)
+}
+
+/// Test whether `a` placed at `position_a` overlaps `b` placed at `position_b`
+///
+/// Touching edges count as overlapping. Layers are not taken into account.
+pub fn shapes_overlap(a: &Shape, position_a: &Position, b: &Shape, position_b: &Position) -> bool {
+    match (a.place(position_a), b.place(position_b)) {
+        (
+            Placed::Rect {
+                min_x: a_min_x,
+                min_y: a_min_y,
+                max_x: a_max_x,
+                max_y: a_max_y,
+            },
+            Placed::Rect {
+                min_x: b_min_x,
+                min_y: b_min_y,
+                max_x: b_max_x,
+                max_y: b_max_y,
+            },
+        ) => a_min_x <= b_max_x && b_min_x <= a_max_x && a_min_y <= b_max_y && b_min_y <= a_max_y,
+        (
+            Placed::Circle {
+                x: ax,
+                y: ay,
+                radius: ar,
+            },
+            Placed::Circle {
+                x: bx,
+                y: by,
+                radius: br,
+            },
+        ) => within_reach(ax - bx, ay - by, ar + br),
+        (
+            Placed::Rect {
+                min_x,
+                min_y,
+                max_x,
+                max_y,
+            },
+            Placed::Circle { x, y, radius },
+        )
+        | (
+            Placed::Circle { x, y, radius },
+            Placed::Rect {
+                min_x,
+                min_y,
+                max_x,
+                max_y,
+            },
+        ) => rect_circle_overlap((min_x, min_y, max_x, max_y), (x, y, radius)),
+    }
+}
+
+/// Collision shape and filtering of an entity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Collider {
+    pub shape: Shape,
+    /// Layers this collider is on, as a bit set
+    pub layer: u32,
+    /// Layers this collider interacts with, as a bit set
+    pub mask: u32,
+}
+
+impl Collider {
+    /// Default layer of new colliders
+    pub const DEFAULT_LAYER: u32 = 1;
+
+    /// Create a collider on the default layer that interacts with every layer
+    pub fn new(shape: Shape) -> Self {
+        Self {
+            shape,
+            layer: Self::DEFAULT_LAYER,
+            mask: u32::MAX,
+        }
+    }
+
+    /// Create a box collider; negative sizes are made positive, saturating
+    pub fn aabb(half_width: i32, half_height: i32) -> Self {
+        Self::new(Shape::Aabb {
+            half_width: half_width.saturating_abs(),
+            half_height: half_height.saturating_abs(),
+        })
+    }
+
+    /// Create a disc collider; a negative radius is made positive, saturating
+    pub fn circle(radius: i32) -> Self {
+        Self::new(Shape::Circle {
+            radius: radius.saturating_abs(),
+        })
+    }
+
+    pub fn point() -> Self {
+        Self::new(Shape::Point)
+    }
+
+    pub fn cell(size: i32) -> Self {
+        Self::new(Shape::Cell { size })
+    }
+
+    /// Put the collider on `layer`
+    pub fn with_layer(mut self, layer: u32) -> Self {
+        self.layer = layer;
+        self
+    }
+
+    /// Only interact with colliders on a layer in `mask`
+    pub fn with_mask(mut self, mask: u32) -> Self {
+        self.mask = mask;
+        self
+    }
+
+    /// Check whether the layers of both colliders let them collide
+    pub fn interacts_with(&self, other: &Collider) -> bool {
+        self.layer & other.mask != 0 && other.layer & self.mask != 0
+    }
+
+    /// Test this collider at `position` against `other` at `other_position`
+    pub fn collides(
+        &self,
+        position: &Position,
+        other: &Collider,
+        other_position: &Position,
+    ) -> bool {
+        self.interacts_with(other)
+            && shapes_overlap(&self.shape, position, &other.shape, other_position)
+    }
+}
+
+impl ComponentTrait for Collider {
+    fn component_type() -> Symbol {
+        symbol_short!("collider")
+    }
+    fn serialize(&self, env: &Env) -> Bytes {
+        let (tag, a, b) = match self.shape {
+            Shape::Aabb {
+                half_width,
+                half_height,
+            } => (0u8, half_width, half_height),
+            Shape::Circle { radius } => (1, radius, 0),
+            Shape::Point => (2, 0, 0),
+            Shape::Cell { size } => (3, size, 0),
+        };
+        let mut bytes = Bytes::from_array(env, &[tag]);
+        bytes.extend_from_array(&a.to_be_bytes());
+        bytes.extend_from_array(&b.to_be_bytes());
+        bytes.extend_from_array(&self.layer.to_be_bytes());
+        bytes.extend_from_array(&self.mask.to_be_bytes());
+        bytes
+    }
+    fn deserialize(_env: &Env, data: &Bytes) -> Option<Self> {
+        if data.len() != 17 {
+            return None;
+        }
+        let mut buf = [0u8; 17];
+        data.copy_into_slice(&mut buf);
+        let word = |offset: usize| {
+            [
+                buf[offset],
+                buf[offset + 1],
+                buf[offset + 2],
+                buf[offset + 3],
+            ]
+        };
+        let (a, b) = (i32::from_be_bytes(word(1)), i32::from_be_bytes(word(5)));
+        let shape = match buf[0] {
+            0 => Shape::Aabb {
+                half_width: a,
+                half_height: b,
+            },
+            1 => Shape::Circle { radius: a },
+            2 => Shape::Point,
+            3 => Shape::Cell { size: a },
+            _ => return None,
+        };
+        Some(Self {
+            shape,
+            layer: u32::from_be_bytes(word(9)),
+            mask: u32::from_be_bytes(word(13)),
+        })
+    }
+}
+
+/// Find every pair of visible entities whose colliders overlap
+///
+/// Each pair is reported once, with the lower entity id first, and pairs are
+/// ordered by their first entity. When the world's [`SpatialHash`] is enabled
+/// it serves as the broad phase, so each collider is only tested against the
+/// entities near it instead of every other collider.
+///
+/// [`SpatialHash`]: crate::spatial::SpatialHash
+pub fn find_collisions(world: &World) -> StdVec<(EntityId, EntityId)> {
+    let spatial = world.spatial_hash();
+    let mut bodies = BTreeMap::new();
+    for entity_id in world
+        .query_entities(&[Position::component_type(), Collider::component_type()])
+        .iter()
+    {
+        let position = spatial
+            .and_then(|spatial| spatial.position_of(entity_id))
+            .or_else(|| world.get_typed_component::<Position>(entity_id));
+        if let (Some(position), Some(collider)) =
+            (position, world.get_typed_component::<Collider>(entity_id))
+        {
+            bodies.insert(entity_id, (position, collider));
+        }
+    }
+
+    let mut pairs = StdVec::new();
+    let Some(spatial) = spatial else {
+        let bodies: StdVec<_> = bodies.into_iter().collect();
+        for (i, (a, (position_a, collider_a))) in bodies.iter().enumerate() {
+            for (b, (position_b, collider_b)) in bodies[i + 1..].iter() {
+                if collider_a.collides(position_a, collider_b, position_b) {
+                    pairs.push((*a, *b));
+                }
+            }
+        }
+        return pairs;
+    };
+
+    // Any collider overlapping another's bounding box has its position within
+    // the largest reach of that box
+    let reach = bodies
+        .values()
+        .map(|(position, collider)| {
+            let (min, max) = collider.shape.bounding_box(position);
+            (position.x.saturating_sub(min.x))
+                .max(max.x.saturating_sub(position.x))
+                .max(position.y.saturating_sub(min.y))
+                .max(max.y.saturating_sub(position.y))
+        })
+        .max()
+        .unwrap_or(0);
+    for (a, (position_a, collider_a)) in bodies.iter() {
+        let (min, max) = collider_a.shape.bounding_box(position_a);
+        let min = Position::new(min.x.saturating_sub(reach), min.y.saturating_sub(reach));
+        let max = Position::new(max.x.saturating_add(reach), max.y.saturating_add(reach));
+        for b in spatial.entities_in_rect(&min, &max) {
+            if b <= *a {
+                continue;
+            }
+            if let Some((position_b, collider_b)) = bodies.get(&b) {
+                if collider_a.collides(position_a, collider_b, position_b) {
+                    pairs.push((*a, b));
+                }
+            }
+        }
+    }
+    pairs
+}
+
+/// Sends a [`CollisionEvent`] for every pair of overlapping colliders
+#[derive(Debug, Clone)]
+pub struct CollisionSystem {
+    collision_type: Symbol,
+}
+
+impl CollisionSystem {
+    /// Create a collision system that tags its events as `physical`
+    pub fn new() -> Self {
+        Self::with_collision_type(symbol_short!("physical"))
+    }
+
+    /// Create a collision system that tags its events with `collision_type`
+    pub fn with_collision_type(collision_type: Symbol) -> Self {
+        Self { collision_type }
+    }
+}
+
+impl Default for CollisionSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl System for CollisionSystem {
+    type In = ();
+    /// Number of collisions found
+    type Out = u32;
+
+    fn run(&mut self, world: &mut World, _input: Self::In) -> Self::Out {
+        let pairs = find_collisions(world);
+        for (a, b) in pairs.iter() {
+            let event = CollisionEvent::new(a.id(), b.id(), self.collision_type.clone());
+            world.send_typed_event(&event);
+        }
+        pairs.len() as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::EventTrait;
+    use crate::rng::{GameRng, Rng};
+
+    fn spawn_body(world: &mut World, x: i32, y: i32, collider: Collider) -> EntityId {
+        let entity_id = world.spawn_empty().id();
+        world.add_typed_component(entity_id, &Position::new(x, y));
+        world.add_typed_component(entity_id, &collider);
+        entity_id
+    }
+
+    #[test]
+    fn test_narrow_phase() {
+        let origin = Position::new(0, 0);
+        let square = Shape::Aabb {
+            half_width: 2,
+            half_height: 2,
+        };
+        let circle = Shape::Circle { radius: 3 };
+
+        assert!(shapes_overlap(
+            &square,
+            &origin,
+            &square,
+            &Position::new(4, 4)
+        ));
+        assert!(!shapes_overlap(
+            &square,
+            &origin,
+            &square,
+            &Position::new(5, 0)
+        ));
+        assert!(shapes_overlap(
+            &circle,
+            &origin,
+            &circle,
+            &Position::new(6, 0)
+        ));
+        assert!(!shapes_overlap(
+            &circle,
+            &origin,
+            &circle,
+            &Position::new(5, 5)
+        ));
+        // The box corner (2, 2) is sqrt(8) from the circle center (4, 4)
+        assert!(shapes_overlap(
+            &square,
+            &origin,
+            &circle,
+            &Position::new(4, 4)
+        ));
+        assert!(!shapes_overlap(
+            &circle,
+            &Position::new(5, 5),
+            &square,
+            &origin
+        ));
+        assert!(shapes_overlap(
+            &Shape::Point,
+            &origin,
+            &square,
+            &Position::new(2, 0)
+        ));
+        assert!(!shapes_overlap(
+            &Shape::Point,
+            &origin,
+            &Shape::Point,
+            &Position::new(0, 1)
+        ));
+
+        let cell = Shape::Cell { size: 10 };
+        assert!(shapes_overlap(
+            &cell,
+            &Position::new(11, 19),
+            &cell,
+            &Position::new(19, 10)
+        ));
+        assert!(!shapes_overlap(
+            &cell,
+            &Position::new(9, 0),
+            &cell,
+            &Position::new(10, 0)
+        ));
+        assert!(!shapes_overlap(
+            &cell,
+            &Position::new(-1, 0),
+            &cell,
+            &Position::new(0, 0)
+        ));
+        // Offsets across the whole coordinate range do not overflow
+        let (min, max) = (Position::new(i32::MIN, 0), Position::new(i32::MAX, 0));
+        let dot = Shape::Circle { radius: 1 };
+        assert!(!shapes_overlap(&dot, &max, &dot, &min));
+        assert!(!shapes_overlap(
+            &Shape::Circle { radius: i32::MAX },
+            &Position::new(i32::MIN, i32::MIN),
+            &dot,
+            &Position::new(i32::MAX, i32::MAX)
+        ));
+        // Two of the largest circles touch across the range, one unit short
+        let huge = Shape::Circle { radius: i32::MAX };
+        assert!(!shapes_overlap(&huge, &min, &huge, &max));
+        assert!(shapes_overlap(
+            &huge,
+            &min,
+            &huge,
+            &Position::new(i32::MAX - 1, 0)
+        ));
+        assert!(!shapes_overlap(&square, &min, &dot, &max));
+        assert!(!shapes_overlap(
+            &dot,
+            &Position::new(i32::MIN, i32::MIN),
+            &square,
+            &Position::new(i32::MAX, i32::MAX)
+        ));
+
+        // Negative sizes count as their absolute value
+        assert_eq!(Collider::aabb(-2, -2), Collider::aabb(2, 2));
+        assert_eq!(Collider::circle(i32::MIN), Collider::circle(i32::MAX));
+        let inverted = Shape::Circle { radius: -3 };
+        assert!(shapes_overlap(
+            &inverted,
+            &origin,
+            &dot,
+            &Position::new(3, 0)
+        ));
+        let inverted = Shape::Aabb {
+            half_width: -2,
+            half_height: i32::MIN,
+        };
+        assert!(shapes_overlap(
+            &inverted,
+            &origin,
+            &square,
+            &Position::new(4, 4)
+        ));
+    }
+
+    #[test]
+    fn test_layers_and_masks() {
+        let bullet = Collider::point().with_layer(0b01).with_mask(0b10);
+        let invader = Collider::aabb(2, 2).with_layer(0b10).with_mask(0b01);
+        let other_bullet = bullet;
+        assert!(bullet.interacts_with(&invader));
+        assert!(!bullet.interacts_with(&other_bullet));
+        assert!(bullet.collides(&Position::new(1, 1), &invader, &Position::new(0, 0)));
+    }
+
+    #[test]
+    fn test_collider_roundtrip() {
+        let env = Env::default();
+        for collider in [
+            Collider::aabb(3, 4),
+            Collider::circle(7).with_layer(4),
+            Collider::point().with_mask(2),
+            Collider::cell(16),
+        ] {
+            let data = collider.serialize(&env);
+            assert_eq!(Collider::deserialize(&env, &data), Some(collider));
+        }
+    }
+
+    #[test]
+    fn test_collision_system_emits_only_overlaps() {
+        let mut world = World::new();
+        let ship = spawn_body(&mut world, 100, 100, Collider::circle(20));
+        let asteroid = spawn_body(&mut world, 130, 100, Collider::circle(28));
+        let far = spawn_body(&mut world, 500, 500, Collider::circle(28));
+        // A collision marker alone does not make a collision
+        let ghost = world.spawn_empty().id();
+        world.add_typed_component(ghost, &Position::new(100, 100));
+
+        let mut system = CollisionSystem::new();
+        assert_eq!(system.run(&mut world, ()), 1);
+
+        let events = world.get_typed_events::<CollisionEvent>();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].entity_a, ship.id());
+        assert_eq!(events[0].entity_b, asteroid.id());
+        assert_eq!(events[0].collision_type, symbol_short!("physical"));
+        assert!(find_collisions(&world)
+            .iter()
+            .all(|(a, b)| *a != far && *b != far));
+        assert_eq!(world.get_events(&CollisionEvent::event_type()).len(), 1);
+    }
+
+    fn asteroid_field(world: &mut World) {
+        let mut rng = GameRng::from_seed(7);
+        let asteroids = Collider::circle(12).with_layer(0b01).with_mask(0b10);
+        let bullets = Collider::point().with_layer(0b10).with_mask(0b01);
+        for _ in 0..64 {
+            let (x, y) = (rng.range_i32(0, 1024), rng.range_i32(0, 768));
+            spawn_body(world, x, y, asteroids);
+        }
+        for _ in 0..32 {
+            let (x, y) = (rng.range_i32(0, 1024), rng.range_i32(0, 768));
+            spawn_body(world, x, y, bullets);
+        }
+    }
+
+    #[test]
+    fn test_spatial_broad_phase_matches_pairwise() {
+        let env = Env::default();
+        env.cost_estimate().budget().reset_unlimited();
+        let mut world = World::with_env(&env);
+        asteroid_field(&mut world);
+        let pairwise = find_collisions(&world);
+
+        world.enable_spatial_hash(32);
+        assert_eq!(find_collisions(&world), pairwise);
+        assert!(!pairwise.is_empty());
+
+        // Each asteroid is only tested against the few bodies around it
+        let spatial = world.spatial_hash().unwrap();
+        let candidates: usize = world
+            .query_entities(&[Collider::component_type()])
+            .iter()
+            .map(|entity_id| {
+                let position = spatial.position_of(entity_id).unwrap();
+                let reach = Position::new(24, 24);
+                spatial
+                    .entities_in_rect(
+                        &Position::new(position.x - reach.x, position.y - reach.y),
+                        &Position::new(position.x + reach.x, position.y + reach.y),
+                    )
+                    .len()
+            })
+            .sum();
+        assert!(candidates < 96 * 96 / 20);
+
+        // Large colliders reach entities filed several cells away
+        let big = Collider::aabb(150, 150).with_layer(0b10).with_mask(0b01);
+        let big = spawn_body(&mut world, 500, 400, big);
+        let hashed = find_collisions(&world);
+        world.disable_spatial_hash();
+        assert_eq!(hashed, find_collisions(&world));
+        assert!(hashed.iter().any(|(_, b)| *b == big));
+    }
+}
@@ -1,7 +1,7 @@
 use alloc::vec::Vec;
-use soroban_sdk::{
-    contracttype, symbol_short, Bytes, Env, FromVal, IntoVal, Symbol, TryFromVal, Val,
-};
+use soroban_sdk::{contracttype, Bytes, Env, IntoVal, Symbol, TryFromVal, Val};
+
+pub use crate::components::{Position, Velocity};
 
 /// A unique identifier for a component type
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -35,15 +35,29 @@ impl TryFromVal<Env, Val> for ComponentId {
 
 #[contracttype]
 #[repr(u32)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum ComponentStorage {
+    #[default]
     Table = 0,
     Sparse = 1,
 }
-impl Default for ComponentStorage {
-    fn default() -> Self {
-        Self::Table
-    }
+
+/// Contract storage a saved component is placed in
+///
+/// See [`WorldStore`](crate::persistence::WorldStore) for how each tier is used.
+#[contracttype]
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StorageTier {
+    /// Instance storage, sharing the TTL of the contract instance; for small
+    /// data needed by every call
+    Instance = 0,
+    /// Persistent storage, archived when its TTL runs out; for long-lived data
+    #[default]
+    Persistent = 1,
+    /// Temporary storage, deleted for good when its TTL runs out; for
+    /// short-lived data such as bullets or per-tick scratch state
+    Temporary = 2,
 }
 
 #[contracttype]
@@ -168,98 +182,31 @@ pub trait ComponentTrait {
     fn default_storage() -> ComponentStorage {
         ComponentStorage::Table
     }
-}
-
-#[contracttype]
-#[derive(Clone)]
-pub struct Position {
-    pub x: i32,
-    pub y: i32,
-}
-impl Position {
-    pub fn new(x: i32, y: i32) -> Self {
-        Self { x, y }
-    }
-}
-impl ComponentTrait for Position {
-    fn component_type() -> Symbol {
-        symbol_short!("position")
-    }
-    fn serialize(&self, env: &Env) -> Bytes {
-        let mut bytes = Bytes::new(env);
-        let x_bytes = Bytes::from_array(env, &self.x.to_be_bytes());
-        let y_bytes = Bytes::from_array(env, &self.y.to_be_bytes());
-        bytes.append(&x_bytes);
-        bytes.append(&y_bytes);
-        bytes
-    }
-    fn deserialize(env: &Env, data: &Bytes) -> Option<Self> {
-        if data.len() != 8 {
-            return None;
-        }
-        let x = i32::from_be_bytes([
-            data.get(0).unwrap(),
-            data.get(1).unwrap(),
-            data.get(2).unwrap(),
-            data.get(3).unwrap(),
-        ]);
-        let y = i32::from_be_bytes([
-            data.get(4).unwrap(),
-            data.get(5).unwrap(),
-            data.get(6).unwrap(),
-            data.get(7).unwrap(),
-        ]);
-        Some(Self { x, y })
-    }
-}
-
-#[contracttype]
-#[derive(Clone)]
-pub struct Velocity {
-    pub x: i32,
-    pub y: i32,
-}
-impl Velocity {
-    pub fn new(x: i32, y: i32) -> Self {
-        Self { x, y }
-    }
-}
-impl ComponentTrait for Velocity {
-    fn component_type() -> Symbol {
-        symbol_short!("velocity")
-    }
-    fn serialize(&self, env: &Env) -> Bytes {
-        let mut bytes = Bytes::new(env);
-        let x_bytes = Bytes::from_array(env, &self.x.to_be_bytes());
-        let y_bytes = Bytes::from_array(env, &self.y.to_be_bytes());
-        bytes.append(&x_bytes);
-        bytes.append(&y_bytes);
-        bytes
+    /// Contract storage the component is saved to
+    ///
+    /// The world records the tier when a component of the type is added with
+    /// [`World::add_typed_component`](crate::world::World::add_typed_component),
+    /// and a loaded world remembers the tier its components were loaded from.
+    /// Components only ever added untyped are saved to persistent storage
+    /// unless their type is registered with
+    /// [`WorldStore::with_component`](crate::persistence::WorldStore::with_component).
+    fn storage_tier() -> StorageTier {
+        StorageTier::Persistent
     }
-    fn deserialize(env: &Env, data: &Bytes) -> Option<Self> {
-        if data.len() != 8 {
-            return None;
-        }
-        let x = i32::from_be_bytes([
-            data.get(0).unwrap(),
-            data.get(1).unwrap(),
-            data.get(2).unwrap(),
-            data.get(3).unwrap(),
-        ]);
-        let y = i32::from_be_bytes([
-            data.get(4).unwrap(),
-            data.get(5).unwrap(),
-            data.get(6).unwrap(),
-            data.get(7).unwrap(),
-        ]);
-        Some(Self { x, y })
+    /// Version of the component's serialized layout, raised whenever it changes
+    ///
+    /// The world records the version along with the storage tier, and when a
+    /// migration to the type is registered. See [`crate::migration`] for
+    /// reading data saved at older versions.
+    fn schema_version() -> u32 {
+        1
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use soroban_sdk::Env;
+    use soroban_sdk::{symbol_short, Env};
 
     #[test]
     fn test_component_id_creation() {
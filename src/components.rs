@@ -1,8 +1,168 @@
-use soroban_sdk::contracttype;
+//! Canonical spatial components shared by games and built-in systems.
+//!
+//! Positions, velocities and accelerations are integer world units (pixels,
+//! cells or a game-chosen fixed scale); [`MovementSystem`](crate::systems::MovementSystem)
+//! integrates them every tick. [`Rotation`] is a [`Fixed`] angle in radians.
 
+use crate::component::ComponentTrait;
+use crate::math::{Fixed, Vec2};
+use soroban_sdk::{contracttype, symbol_short, Bytes, Env, Symbol};
+
+fn serialize_pair(env: &Env, x: i32, y: i32) -> Bytes {
+    let mut bytes = Bytes::from_array(env, &x.to_be_bytes());
+    bytes.extend_from_array(&y.to_be_bytes());
+    bytes
+}
+
+fn deserialize_pair(data: &Bytes) -> Option<(i32, i32)> {
+    if data.len() != 8 {
+        return None;
+    }
+    let mut buf = [0u8; 8];
+    data.copy_into_slice(&mut buf);
+    let x = i32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]);
+    let y = i32::from_be_bytes([buf[4], buf[5], buf[6], buf[7]]);
+    Some((x, y))
+}
+
+/// Location of an entity in world units
 #[contracttype]
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
 pub struct Position {
-    pub x: u32,
-    pub y: u32,
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Position {
+    pub fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+}
+
+impl ComponentTrait for Position {
+    fn component_type() -> Symbol {
+        symbol_short!("position")
+    }
+    fn serialize(&self, env: &Env) -> Bytes {
+        serialize_pair(env, self.x, self.y)
+    }
+    fn deserialize(_env: &Env, data: &Bytes) -> Option<Self> {
+        deserialize_pair(data).map(|(x, y)| Self { x, y })
+    }
+}
+
+/// Change of [`Position`] per tick
+#[contracttype]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct Velocity {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Velocity {
+    pub fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+}
+
+impl ComponentTrait for Velocity {
+    fn component_type() -> Symbol {
+        symbol_short!("velocity")
+    }
+    fn serialize(&self, env: &Env) -> Bytes {
+        serialize_pair(env, self.x, self.y)
+    }
+    fn deserialize(_env: &Env, data: &Bytes) -> Option<Self> {
+        deserialize_pair(data).map(|(x, y)| Self { x, y })
+    }
+}
+
+/// Change of [`Velocity`] per tick
+#[contracttype]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct Acceleration {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Acceleration {
+    pub fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+}
+
+impl ComponentTrait for Acceleration {
+    fn component_type() -> Symbol {
+        symbol_short!("accel")
+    }
+    fn serialize(&self, env: &Env) -> Bytes {
+        serialize_pair(env, self.x, self.y)
+    }
+    fn deserialize(_env: &Env, data: &Bytes) -> Option<Self> {
+        deserialize_pair(data).map(|(x, y)| Self { x, y })
+    }
+}
+
+/// Facing of an entity, in radians counter-clockwise from the positive x axis
+#[contracttype]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct Rotation {
+    pub angle: Fixed,
+}
+
+impl Rotation {
+    pub fn new(angle: Fixed) -> Self {
+        Self { angle }
+    }
+
+    /// Unit vector pointing where the entity faces
+    pub fn heading(&self) -> Vec2 {
+        Vec2::from_angle(self.angle)
+    }
+}
+
+impl ComponentTrait for Rotation {
+    fn component_type() -> Symbol {
+        symbol_short!("rotation")
+    }
+    fn serialize(&self, env: &Env) -> Bytes {
+        self.angle.to_bytes(env)
+    }
+    fn deserialize(_env: &Env, data: &Bytes) -> Option<Self> {
+        if data.len() != Fixed::ENCODED_LEN {
+            return None;
+        }
+        Fixed::from_bytes(data, 0).map(Self::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spatial_component_roundtrip() {
+        let env = Env::default();
+        let position = Position::new(-100, 200);
+        assert_eq!(
+            Position::deserialize(&env, &position.serialize(&env)),
+            Some(position)
+        );
+        let velocity = Velocity::new(3, -4);
+        assert_eq!(
+            Velocity::deserialize(&env, &velocity.serialize(&env)),
+            Some(velocity)
+        );
+        let acceleration = Acceleration::new(0, 1);
+        assert_eq!(
+            Acceleration::deserialize(&env, &acceleration.serialize(&env)),
+            Some(acceleration)
+        );
+        let rotation = Rotation::new(Fixed::PI);
+        assert_eq!(
+            Rotation::deserialize(&env, &rotation.serialize(&env)),
+            Some(rotation)
+        );
+        assert_eq!(Position::deserialize(&env, &Bytes::new(&env)), None);
+    }
 }
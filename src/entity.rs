@@ -1,5 +1,10 @@
-use core::fmt;
-use soroban_sdk::{Env, FromVal, IntoVal, Symbol, TryFromVal, Val, Vec};
+use soroban_sdk::{Bytes, Env, IntoVal, Symbol, TryFromVal, Val, Vec};
+
+mod clone_entities;
+mod map_entities;
+
+pub use clone_entities::EntityCloner;
+pub use map_entities::{ComponentEntityMapper, EntityMap, EntityMapper, MapEntities};
 
 /// A unique identifier for an entity in the ECS world
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -28,12 +33,43 @@ impl EntityId {
     pub fn is_valid(&self) -> bool {
         self.id != 0
     }
+
+    /// Number of bytes used by [`EntityId::to_bytes`]
+    pub const ENCODED_LEN: u32 = 12;
+
+    /// Encode this entity ID as 12 big-endian bytes (id followed by generation)
+    pub fn to_bytes(&self, env: &Env) -> Bytes {
+        let mut bytes = Bytes::from_array(env, &self.id.to_be_bytes());
+        bytes.append(&Bytes::from_array(env, &self.generation.to_be_bytes()));
+        bytes
+    }
+
+    /// Decode an entity ID written by [`EntityId::to_bytes`] starting at `offset`
+    pub fn from_bytes(data: &Bytes, offset: u32) -> Option<Self> {
+        if data.len() < offset + Self::ENCODED_LEN {
+            return None;
+        }
+        let mut id = [0u8; 8];
+        for (i, byte) in id.iter_mut().enumerate() {
+            *byte = data.get(offset + i as u32)?;
+        }
+        let mut generation = [0u8; 4];
+        for (i, byte) in generation.iter_mut().enumerate() {
+            *byte = data.get(offset + 8 + i as u32)?;
+        }
+        Some(Self::new(
+            u64::from_be_bytes(id),
+            u32::from_be_bytes(generation),
+        ))
+    }
 }
 
 // Soroban SDK trait implementations for EntityId
-impl IntoVal<Env, Val> for EntityId {
-    fn into_val(&self, env: &Env) -> Val {
-        (self.id, self.generation).into_val(env)
+impl TryFromVal<Env, EntityId> for Val {
+    type Error = soroban_sdk::ConversionError;
+
+    fn try_from_val(env: &Env, value: &EntityId) -> Result<Self, Self::Error> {
+        Ok((value.id, value.generation).into_val(env))
     }
 }
 
@@ -76,7 +112,7 @@ impl Entity {
     /// Remove a component type from this entity
     pub fn remove_component_type(&mut self, component_type: &Symbol) -> bool {
         let mut found = false;
-        let mut new_components = Vec::new(&soroban_sdk::Env::default());
+        let mut new_components = Vec::new(self.component_types.env());
 
         for i in 0..self.component_types.len() {
             let ctype = self.component_types.get(i).unwrap();
@@ -121,12 +157,14 @@ impl Entity {
 }
 
 // Soroban SDK trait implementations for Entity
-impl IntoVal<Env, Val> for Entity {
-    fn into_val(&self, env: &Env) -> Val {
+impl TryFromVal<Env, Entity> for Val {
+    type Error = soroban_sdk::ConversionError;
+
+    fn try_from_val(env: &Env, value: &Entity) -> Result<Self, Self::Error> {
         // Serialize as a simple structure that Soroban can handle
-        let id_val: Val = self.id.into_val(env);
-        let types_val: Val = self.component_types.clone().into_val(env);
-        (id_val, types_val).into_val(env)
+        let id_val: Val = value.id.into_val(env);
+        let types_val: Val = value.component_types.clone().into_val(env);
+        Ok((id_val, types_val).into_val(env))
     }
 }
 
@@ -149,45 +187,77 @@ impl TryFromVal<Env, Val> for Entity {
 pub struct EntityManager {
     next_id: u64,
     entities: Vec<Entity>,
-    free_list: Vec<u64>,
+    /// Despawned ids, each with the generation it last had
+    free_list: Vec<EntityId>,
 }
 
 impl EntityManager {
     /// Create a new entity manager
     pub fn new() -> Self {
-        let env = soroban_sdk::Env::default();
+        Self::with_env(&soroban_sdk::Env::default())
+    }
+
+    /// Create a new entity manager whose entity lists live in `env`
+    pub fn with_env(env: &Env) -> Self {
         Self {
             next_id: 1,
-            entities: Vec::new(&env),
-            free_list: Vec::new(&env),
+            entities: Vec::new(env),
+            free_list: Vec::new(env),
         }
     }
 
     /// Spawn a new entity
+    ///
+    /// A despawned id is reused with its generation raised, so ids held for
+    /// the despawned entity do not refer to the new one.
     pub fn spawn(&mut self) -> EntityId {
-        let id = if self.free_list.len() > 0 {
-            let freed_id = self.free_list.get(self.free_list.len() - 1).unwrap();
-            self.free_list.remove(self.free_list.len() - 1);
-            freed_id
-        } else {
-            let id = self.next_id;
-            self.next_id += 1;
-            id
+        let entity_id = self.allocate();
+        let entity = Entity {
+            id: entity_id,
+            component_types: Vec::new(self.entities.env()),
         };
-
-        let entity_id = EntityId::new(id, 0);
-        let entity = Entity::new(entity_id);
         self.entities.push_back(entity);
         entity_id
     }
 
+    /// Allocate `count` entity ids without creating the entities yet
+    ///
+    /// Every reserved id must be passed to [`EntityManager::insert_reserved`],
+    /// otherwise it is lost until the manager is cleared.
+    pub(crate) fn reserve(&mut self, count: u32) -> Vec<EntityId> {
+        let mut reserved = Vec::new(self.entities.env());
+        for _ in 0..count {
+            reserved.push_back(self.allocate());
+        }
+        reserved
+    }
+
+    fn allocate(&mut self) -> EntityId {
+        match self.free_list.pop_back() {
+            Some(freed) => EntityId::new(freed.id(), freed.generation().wrapping_add(1)),
+            None => {
+                let id = self.next_id;
+                self.next_id += 1;
+                EntityId::new(id, 0)
+            }
+        }
+    }
+
+    /// Create a reserved entity with its component types already known
+    pub(crate) fn insert_reserved(&mut self, entity_id: EntityId, component_types: Vec<Symbol>) {
+        self.entities.push_back(Entity {
+            id: entity_id,
+            component_types,
+        });
+    }
+
     /// Despawn an entity
     pub fn despawn(&mut self, entity_id: EntityId) -> bool {
         for i in 0..self.entities.len() {
             let entity = self.entities.get(i).unwrap();
             if entity.id() == entity_id {
                 self.entities.remove(i);
-                self.free_list.push_back(entity_id.id());
+                self.free_list.push_back(entity_id);
                 return true;
             }
         }
@@ -212,6 +282,19 @@ impl EntityManager {
         self.get_entity(entity_id)
     }
 
+    /// Write back an entity obtained from [`EntityManager::get_entity_mut`]
+    ///
+    /// Returns `false` if no live entity has the same ID.
+    pub fn set_entity(&mut self, entity: Entity) -> bool {
+        for i in 0..self.entities.len() {
+            if self.entities.get(i).unwrap().id() == entity.id() {
+                self.entities.set(i, entity);
+                return true;
+            }
+        }
+        false
+    }
+
     /// Get the total number of entities
     pub fn entity_count(&self) -> usize {
         self.entities.len().try_into().unwrap()
@@ -229,7 +312,7 @@ impl EntityManager {
     }
 
     /// Iterate over all entities
-    pub fn iter_entities(&self) -> EntityIterator {
+    pub fn iter_entities(&self) -> EntityIterator<'_> {
         EntityIterator {
             entities: &self.entities,
             index: 0,
@@ -237,7 +320,7 @@ impl EntityManager {
     }
 
     /// Iterate over all entities mutably
-    pub fn iter_entities_mut(&mut self) -> EntityIteratorMut {
+    pub fn iter_entities_mut(&mut self) -> EntityIteratorMut<'_> {
         EntityIteratorMut {
             entities: &mut self.entities,
             index: 0,
@@ -252,9 +335,16 @@ impl Default for EntityManager {
 }
 
 // Soroban SDK trait implementations for EntityManager
-impl IntoVal<Env, Val> for EntityManager {
-    fn into_val(&self, env: &Env) -> Val {
-        (self.next_id, self.entities.clone(), self.free_list.clone()).into_val(env)
+impl TryFromVal<Env, EntityManager> for Val {
+    type Error = soroban_sdk::ConversionError;
+
+    fn try_from_val(env: &Env, value: &EntityManager) -> Result<Self, Self::Error> {
+        Ok((
+            value.next_id,
+            value.entities.clone(),
+            value.free_list.clone(),
+        )
+            .into_val(env))
     }
 }
 
@@ -262,8 +352,17 @@ impl TryFromVal<Env, Val> for EntityManager {
     type Error = soroban_sdk::ConversionError;
 
     fn try_from_val(env: &Env, val: &Val) -> Result<Self, Self::Error> {
-        let (next_id, entities, free_list): (u64, Vec<Entity>, Vec<u64>) =
+        let (next_id, entities, saved_free_list): (u64, Vec<Entity>, Val) =
             TryFromVal::try_from_val(env, val)?;
+        // Managers saved before generations were kept list bare ids
+        let mut free_list = Vec::new(env);
+        for freed in Vec::<Val>::try_from_val(env, &saved_free_list)?.iter() {
+            let entity_id = match EntityId::try_from_val(env, &freed) {
+                Ok(entity_id) => entity_id,
+                Err(_) => EntityId::new(u64::try_from_val(env, &freed)?, 0),
+            };
+            free_list.push_back(entity_id);
+        }
         Ok(EntityManager {
             next_id,
             entities,
@@ -327,7 +426,6 @@ mod tests {
 
     #[test]
     fn test_entity_creation() {
-        let env = Env::default();
         let entity_id = EntityId::new(1, 0);
         let entity = Entity::new(entity_id);
         assert_eq!(entity.id(), entity_id);
@@ -346,5 +444,45 @@ mod tests {
         assert!(manager.despawn(entity_id));
         assert_eq!(manager.entity_count(), 0);
         assert!(!manager.exists(entity_id));
+
+        // The id is reused at the next generation
+        let respawned = manager.spawn();
+        assert_eq!(respawned, EntityId::new(entity_id.id(), 1));
+        assert!(!manager.exists(entity_id));
+        assert!(!manager.despawn(entity_id));
+        assert!(manager.exists(respawned));
+    }
+
+    #[test]
+    fn test_entity_manager_decodes_bare_free_list() {
+        let env = Env::default();
+        let entities: Vec<Entity> = Vec::new(&env);
+        let saved: Val = (3u64, entities, Vec::from_array(&env, [2u64])).into_val(&env);
+        let mut manager = EntityManager::try_from_val(&env, &saved).unwrap();
+        assert_eq!(manager.spawn(), EntityId::new(2, 1));
+    }
+
+    #[test]
+    fn test_entity_id_bytes_roundtrip() {
+        let env = Env::default();
+        let entity_id = EntityId::new(0x0102_0304_0506_0708, 9);
+        let bytes = entity_id.to_bytes(&env);
+        assert_eq!(bytes.len(), EntityId::ENCODED_LEN);
+        assert_eq!(EntityId::from_bytes(&bytes, 0), Some(entity_id));
+        assert_eq!(EntityId::from_bytes(&bytes, 1), None);
+    }
+
+    #[test]
+    fn test_entity_manager_set_entity() {
+        let mut manager = EntityManager::new();
+        let entity_id = manager.spawn();
+
+        let mut entity = manager.get_entity_mut(entity_id).unwrap();
+        entity.add_component_type(soroban_sdk::symbol_short!("position"));
+        assert!(manager.set_entity(entity));
+        assert!(manager
+            .get_entity(entity_id)
+            .unwrap()
+            .has_component(&soroban_sdk::symbol_short!("position")));
     }
 }
@@ -1,307 +1,259 @@
 //! Disabled entities do not show up in queries unless the query explicitly mentions them.
 //!
 //! Entities which are disabled in this way are not removed from the [`World`],
-//! and their relationships remain intact.
-//! In many cases, you may want to disable entire trees of entities at once,
-//! using [`EntityCommands::insert_recursive`](crate::prelude::EntityCommands::insert_recursive).
+//! their components are not rewritten and their relationships remain intact,
+//! which makes disabling much cheaper than despawning and respawning pooled
+//! entities such as bullets or paused units.
 //!
-//! While Bevy ships with a built-in [`Disabled`] component, you can also create your own
-//! disabling components, which will operate in the same way but can have distinct semantics.
-//!
-//! ```
-//! use bevy_ecs::prelude::*;
-//!
-//! // Our custom disabling component!
-//! #[derive(Component, Clone)]
-//! struct Prefab;
-//!
-//! #[derive(Component)]
-//! struct A;
-//!
-//! let mut world = World::new();
-//! world.register_disabling_component::<Prefab>();
-//! world.spawn((A, Prefab));
-//! world.spawn((A,));
-//! world.spawn((A,));
-//!
-//! let mut normal_query = world.query::<&A>();
-//! assert_eq!(2, normal_query.iter(&world).count());
-//!
-//! let mut prefab_query = world.query_filtered::<&A, With<Prefab>>();
-//! assert_eq!(1, prefab_query.iter(&world).count());
-//!
-//! let mut maybe_prefab_query = world.query::<(&A, Has<Prefab>)>();
-//! assert_eq!(3, maybe_prefab_query.iter(&world).count());
-//! ```
+//! Cougr ships with a built-in [`Disabled`] component, but you can also
+//! register your own disabling components with
+//! [`World::register_disabling_component`], which operate in the same way but
+//! can have distinct semantics.
 //!
 //! ## Default query filters
 //!
-//! In Bevy, entity disabling is implemented through the construction of a global "default query filter".
-//! Queries which do not explicitly mention the disabled component will not include entities with that component.
-//! If an entity has multiple disabling components, it will only be included in queries that mention all of them.
-//!
-//! For example, `Query<&Position>` will not include entities with the [`Disabled`] component,
-//! even if they have a `Position` component,
-//! but `Query<&Position, With<Disabled>>` or `Query<(&Position, Has<Disabled>)>` will see them.
-//!
-//! Entities with disabling components are still present in the [`World`] and can be accessed directly,
-//! using methods on [`World`] or [`Commands`](crate::prelude::Commands).
-//!
-//! ### Warnings
+//! Entity disabling is implemented through [`DefaultQueryFilters`], a list of
+//! disabling components kept by the [`World`]. Queries which do not explicitly
+//! mention a disabling component will not include entities with that
+//! component. If an entity has multiple disabling components, it will only be
+//! included in queries that mention all of them.
 //!
-//! Currently, only queries for which the cache is built after enabling a default query filter will have entities
-//! with those components filtered. As a result, they should generally only be modified before the
-//! app starts.
+//! This applies to [`Query::execute`](crate::query::Query::execute),
+//! [`World::query_entities`], [`query_with_filter`](crate::query::query_with_filter)
+//! and the system [`Query`](crate::system::Query). A query mentions a
+//! component by requiring or excluding it; [`Query::include_disabled`](crate::query::Query::include_disabled)
+//! opts in to every disabled entity at once.
 //!
-//! Because filters are applied to all queries they can have performance implication for
-//! the enire [`World`], especially when they cause queries to mix sparse and table components.
-//! See [`Query` performance] for more info.
-//!
-//! Custom disabling components can cause significant interoperability issues within the ecosystem,
-//! as users must be aware of each disabling component in use.
-//! Libraries should think carefully about whether they need to use a new disabling component,
-//! and clearly communicate their presence to their users to avoid the new for library compatibility flags.
-//!
-//! [`With`]: crate::prelude::With
-//! [`Has`]: crate::prelude::Has
-//! [`World`]: crate::prelude::World
-//! [`Query` performance]: crate::prelude::Query#performance
-
-use crate::{
-    component::{ComponentId, Components, StorageType},
-    query::FilteredAccess,
-    world::{FromWorld, World},
-};
-use bevy_ecs_macros::{Component, Resource};
-use smallvec::SmallVec;
-
-#[cfg(feature = "bevy_reflect")]
-use {
-    crate::reflect::ReflectComponent, bevy_reflect::std_traits::ReflectDefault,
-    bevy_reflect::Reflect,
-};
-
-/// A marker component for disabled entities.
-///
-/// Semantically, this component is used to mark entities that are temporarily disabled (typically for gameplay reasons),
-/// but will likely be re-enabled at some point.
-///
-/// Like all disabling components, this only disables the entity itself,
-/// not its children or other entities that reference it.
-/// To disable an entire tree of entities, use [`EntityCommands::insert_recursive`](crate::prelude::EntityCommands::insert_recursive).
-///
-/// Every [`World`] has a default query filter that excludes entities with this component,
-/// registered in the [`DefaultQueryFilters`] resource.
-/// See [the module docs] for more info.
+//! Entities with disabling components are still present in the [`World`] and
+//! can be accessed directly, e.g. with [`World::get_component`].
+
+use crate::component::{Component, ComponentStorage, ComponentTrait};
+use crate::entity::{Entity, EntityId};
+use crate::world::World;
+use soroban_sdk::{symbol_short, Bytes, Env, Symbol, Vec};
+
+/// A marker component for disabled entities. See [the module docs] for more info.
 ///
 /// [the module docs]: crate::entity_disabling
-#[derive(Component, Clone, Debug, Default)]
-#[cfg_attr(
-    feature = "bevy_reflect",
-    derive(Reflect),
-    reflect(Component),
-    reflect(Debug, Clone, Default)
-)]
-// This component is registered as a disabling component during World::bootstrap
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub struct Disabled;
 
-/// Default query filters work by excluding entities with certain components from most queries.
-///
-/// If a query does not explicitly mention a given disabling component, it will not include entities with that component.
-/// To be more precise, this checks if the query's [`FilteredAccess`] contains the component,
-/// and if it does not, adds a [`Without`](crate::prelude::Without) filter for that component to the query.
-///
-/// This resource is initialized in the [`World`] whenever a new world is created,
-/// with the [`Disabled`] component as a disabling component.
-///
-/// Note that you can remove default query filters by overwriting the [`DefaultQueryFilters`] resource.
-/// This can be useful as a last resort escape hatch, but is liable to break compatibility with other libraries.
-///
-/// See the [module docs](crate::entity_disabling) for more info.
-///
-///
-/// # Warning
-///
-/// Default query filters are a global setting that affects all queries in the [`World`],
-/// and incur a small performance cost for each query.
-///
-/// They can cause significant interoperability issues within the ecosystem,
-/// as users must be aware of each disabling component in use.
-///
-/// Think carefully about whether you need to use a new disabling component,
-/// and clearly communicate their presence in any libraries you publish.
-#[derive(Resource, Debug)]
-#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+impl ComponentTrait for Disabled {
+    fn component_type() -> Symbol {
+        symbol_short!("disabled")
+    }
+    fn serialize(&self, env: &Env) -> Bytes {
+        Bytes::new(env)
+    }
+    fn deserialize(_env: &Env, data: &Bytes) -> Option<Self> {
+        if !data.is_empty() {
+            return None;
+        }
+        Some(Self)
+    }
+    fn default_storage() -> ComponentStorage {
+        ComponentStorage::Sparse
+    }
+}
+
+/// The disabling components hidden from every query that does not mention them
+#[derive(Debug, Clone)]
 pub struct DefaultQueryFilters {
-    // We only expect a few components per application to act as disabling components, so we use a SmallVec here
-    // to avoid heap allocation in most cases.
-    disabling: SmallVec<[ComponentId; 4]>,
+    disabling: Vec<Symbol>,
 }
 
-impl FromWorld for DefaultQueryFilters {
-    fn from_world(world: &mut World) -> Self {
-        let mut filters = DefaultQueryFilters::empty();
-        let disabled_component_id = world.register_component::<Disabled>();
-        filters.register_disabling_component(disabled_component_id);
+impl DefaultQueryFilters {
+    /// Create filters that only hide [`Disabled`] entities
+    pub fn new(env: &Env) -> Self {
+        let mut filters = Self::empty(env);
+        filters.register_disabling_component(Disabled::component_type());
         filters
     }
-}
 
-impl DefaultQueryFilters {
-    /// Creates a new, completely empty [`DefaultQueryFilters`].
-    ///
-    /// This is provided as an escape hatch; in most cases you should initialize this using [`FromWorld`],
-    /// which is automatically called when creating a new [`World`].
-    #[must_use]
-    pub fn empty() -> Self {
-        DefaultQueryFilters {
-            disabling: SmallVec::new(),
+    /// Create filters that hide nothing
+    pub fn empty(env: &Env) -> Self {
+        Self {
+            disabling: Vec::new(env),
         }
     }
 
-    /// Adds this [`ComponentId`] to the set of [`DefaultQueryFilters`],
-    /// causing entities with this component to be excluded from queries.
-    ///
-    /// This method is idempotent, and will not add the same component multiple times.
-    ///
-    /// # Warning
-    ///
-    /// This method should only be called before the app starts, as it will not affect queries
-    /// initialized before it is called.
-    ///
-    /// As discussed in the [module docs](crate::entity_disabling), this can have performance implications,
-    /// as well as create interoperability issues, and should be used with caution.
-    pub fn register_disabling_component(&mut self, component_id: ComponentId) {
-        if !self.disabling.contains(&component_id) {
-            self.disabling.push(component_id);
+    /// Hide entities with `component_type` from queries that do not mention it
+    pub fn register_disabling_component(&mut self, component_type: Symbol) {
+        if !self.disabling.contains(&component_type) {
+            self.disabling.push_back(component_type);
         }
     }
 
-    /// Get an iterator over all of the components which disable entities when present.
-    pub fn disabling_ids(&self) -> impl Iterator<Item = ComponentId> + use<'_> {
-        self.disabling.iter().copied()
+    /// Get the registered disabling component types
+    pub fn disabling_components(&self) -> &Vec<Symbol> {
+        &self.disabling
     }
 
-    /// Modifies the provided [`FilteredAccess`] to include the filters from this [`DefaultQueryFilters`].
-    pub(super) fn modify_access(&self, component_access: &mut FilteredAccess<ComponentId>) {
-        for component_id in self.disabling_ids() {
-            if !component_access.contains(component_id) {
-                component_access.and_without(component_id);
-            }
-        }
+    /// Check whether `component_type` is a registered disabling component
+    pub fn is_disabling(&self, component_type: &Symbol) -> bool {
+        self.disabling.contains(component_type)
     }
 
-    pub(super) fn is_dense(&self, components: &Components) -> bool {
-        self.disabling_ids().all(|component_id| {
-            components
-                .get_info(component_id)
-                .is_some_and(|info| info.storage_type() == StorageType::Table)
+    /// Check whether a query that `mentions` some component types may see `entity`
+    ///
+    /// The entity is visible if every disabling component it has is mentioned.
+    pub fn allows(&self, entity: &Entity, mentions: impl Fn(&Symbol) -> bool) -> bool {
+        self.disabling.iter().all(|component_type| {
+            !entity.has_component(&component_type) || mentions(&component_type)
         })
     }
 }
 
+impl World {
+    /// Get the filters applied to queries by default
+    pub fn default_query_filters(&self) -> &DefaultQueryFilters {
+        &self.default_query_filters
+    }
+
+    /// Hide entities with the component `T` from queries that do not mention it
+    pub fn register_disabling_component<T: ComponentTrait>(&mut self) {
+        self.default_query_filters
+            .register_disabling_component(T::component_type());
+    }
+
+    /// Hide `entity_id` from queries by adding the [`Disabled`] component
+    ///
+    /// Returns `false` if the entity does not exist.
+    pub fn disable(&mut self, entity_id: EntityId) -> bool {
+        if !self.exists(entity_id) {
+            return false;
+        }
+        let component = Component::with_storage(
+            Disabled::component_type(),
+            Bytes::new(self.env()),
+            Disabled::default_storage(),
+        );
+        self.add_component_to_entity(entity_id, component);
+        true
+    }
+
+    /// Make `entity_id` visible to queries again by removing the [`Disabled`] component
+    ///
+    /// Returns `false` if the entity was not disabled.
+    pub fn enable(&mut self, entity_id: EntityId) -> bool {
+        self.remove_component_from_entity(entity_id, &Disabled::component_type())
+    }
+
+    /// Check if `entity_id` has any registered disabling component
+    pub fn is_disabled(&self, entity_id: EntityId) -> bool {
+        match self.get_entity(entity_id) {
+            Some(entity) => !self.default_query_filters.allows(&entity, |_| false),
+            None => false,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-
     use super::*;
-    use crate::{
-        prelude::World,
-        query::{Has, With},
-    };
-    use alloc::{vec, vec::Vec};
+    use crate::query::{query_with_filter, Query, WithComponent};
+    use soroban_sdk::symbol_short;
+
+    fn pooled_world() -> (World, EntityId, EntityId) {
+        let env = Env::default();
+        let mut world = World::with_env(&env);
+        let mut components = Vec::new(&env);
+        components.push_back(Component::new(symbol_short!("bullet"), Bytes::new(&env)));
+        let active = world.spawn(components.clone()).id();
+        let pooled = world.spawn(components).id();
+        world.disable(pooled);
+        (world, active, pooled)
+    }
 
     #[test]
-    fn filters_modify_access() {
-        let mut filters = DefaultQueryFilters::empty();
-        filters.register_disabling_component(ComponentId::new(1));
-
-        // A component access with an unrelated component
-        let mut component_access = FilteredAccess::<ComponentId>::default();
-        component_access
-            .access_mut()
-            .add_component_read(ComponentId::new(2));
-
-        let mut applied_access = component_access.clone();
-        filters.modify_access(&mut applied_access);
-        assert_eq!(0, applied_access.with_filters().count());
-        assert_eq!(
-            vec![ComponentId::new(1)],
-            applied_access.without_filters().collect::<Vec<_>>()
-        );
+    fn test_disable_hides_from_queries() {
+        let (world, active, pooled) = pooled_world();
+        assert!(world.is_disabled(pooled));
+        assert!(!world.is_disabled(active));
 
-        // We add a with filter, now we expect to see both filters
-        component_access.and_with(ComponentId::new(4));
+        let results = world.query_entities(&[symbol_short!("bullet")]);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results.get(0), Some(active));
 
-        let mut applied_access = component_access.clone();
-        filters.modify_access(&mut applied_access);
-        assert_eq!(
-            vec![ComponentId::new(4)],
-            applied_access.with_filters().collect::<Vec<_>>()
-        );
-        assert_eq!(
-            vec![ComponentId::new(1)],
-            applied_access.without_filters().collect::<Vec<_>>()
-        );
+        let results = Query::new()
+            .with_component(symbol_short!("bullet"))
+            .execute(&world);
+        assert_eq!(results.len(), 1);
 
-        let copy = component_access.clone();
-        // We add a rule targeting a default component, that filter should no longer be added
-        component_access.and_with(ComponentId::new(1));
+        let results = query_with_filter(&world, &WithComponent::new(symbol_short!("bullet")));
+        assert_eq!(results.len(), 1);
 
-        let mut applied_access = component_access.clone();
-        filters.modify_access(&mut applied_access);
-        assert_eq!(
-            vec![ComponentId::new(1), ComponentId::new(4)],
-            applied_access.with_filters().collect::<Vec<_>>()
-        );
-        assert_eq!(0, applied_access.without_filters().count());
-
-        // Archetypal access should also filter rules
-        component_access = copy.clone();
-        component_access
-            .access_mut()
-            .add_archetypal(ComponentId::new(1));
-
-        let mut applied_access = component_access.clone();
-        filters.modify_access(&mut applied_access);
-        assert_eq!(
-            vec![ComponentId::new(4)],
-            applied_access.with_filters().collect::<Vec<_>>()
-        );
-        assert_eq!(0, applied_access.without_filters().count());
-    }
+        let mut component_types = Vec::new(world.env());
+        component_types.push_back(symbol_short!("bullet"));
+        let system_query = crate::system::Query::new(component_types);
+        assert_eq!(system_query.execute(&world).len(), 1);
+        assert_eq!(system_query.include_disabled().execute(&world).len(), 2);
 
-    #[derive(Component)]
-    struct CustomDisabled;
+        // Disabled entities stay reachable directly
+        assert!(world.has_component(pooled, &symbol_short!("bullet")));
+    }
 
     #[test]
-    fn multiple_disabling_components() {
-        let mut world = World::new();
-        world.register_disabling_component::<CustomDisabled>();
+    fn test_queries_can_opt_in() {
+        let (world, _, pooled) = pooled_world();
 
-        world.spawn_empty();
-        world.spawn(Disabled);
-        world.spawn(CustomDisabled);
-        world.spawn((Disabled, CustomDisabled));
+        let results = Query::new()
+            .with_component(symbol_short!("bullet"))
+            .include_disabled()
+            .execute(&world);
+        assert_eq!(results.len(), 2);
 
-        let mut query = world.query::<()>();
-        assert_eq!(1, query.iter(&world).count());
+        let results = world.query_entities(&[symbol_short!("bullet"), Disabled::component_type()]);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results.get(0), Some(pooled));
 
-        let mut query = world.query_filtered::<(), With<Disabled>>();
-        assert_eq!(1, query.iter(&world).count());
+        let results = query_with_filter(&world, &WithComponent::new(Disabled::component_type()));
+        assert_eq!(results.len(), 1);
+    }
 
-        let mut query = world.query::<Has<Disabled>>();
-        assert_eq!(2, query.iter(&world).count());
+    #[test]
+    fn test_enable_restores_visibility() {
+        let (mut world, _, pooled) = pooled_world();
+        assert!(world.enable(pooled));
+        assert!(!world.enable(pooled));
+        assert_eq!(world.query_entities(&[symbol_short!("bullet")]).len(), 2);
+    }
 
-        let mut query = world.query_filtered::<(), With<CustomDisabled>>();
-        assert_eq!(1, query.iter(&world).count());
+    #[test]
+    fn test_custom_disabling_component() {
+        let env = Env::default();
+        let mut world = World::with_env(&env);
+        world.register_disabling_component::<Paused>();
 
-        let mut query = world.query::<Has<CustomDisabled>>();
-        assert_eq!(2, query.iter(&world).count());
+        let unit = world.spawn_empty().id();
+        world.add_typed_component(unit, &Paused);
+        assert!(world.is_disabled(unit));
+        assert_eq!(Query::new().execute(&world).len(), 0);
 
-        let mut query = world.query_filtered::<(), (With<Disabled>, With<CustomDisabled>)>();
-        assert_eq!(1, query.iter(&world).count());
+        // An entity with several disabling components needs all of them mentioned
+        world.disable(unit);
+        let results = Query::new()
+            .with_component(Paused::component_type())
+            .execute(&world);
+        assert_eq!(results.len(), 0);
+        let results = Query::new()
+            .with_component(Paused::component_type())
+            .with_component(Disabled::component_type())
+            .execute(&world);
+        assert_eq!(results.len(), 1);
+    }
 
-        let mut query = world.query::<(Has<Disabled>, Has<CustomDisabled>)>();
-        assert_eq!(4, query.iter(&world).count());
+    struct Paused;
+
+    impl ComponentTrait for Paused {
+        fn component_type() -> Symbol {
+            symbol_short!("paused")
+        }
+        fn serialize(&self, env: &Env) -> Bytes {
+            Bytes::new(env)
+        }
+        fn deserialize(_env: &Env, _data: &Bytes) -> Option<Self> {
+            Some(Self)
+        }
     }
 }
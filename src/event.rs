@@ -1,5 +1,6 @@
-use alloc::string::String;
-use soroban_sdk::{contracttype, symbol_short, Bytes, Env, IntoVal, Symbol, TryFromVal, Val, Vec};
+use crate::codec::{Reader, Writer};
+use crate::entity::EntityId;
+use soroban_sdk::{contracttype, symbol_short, Bytes, Env, Symbol, Vec};
 
 #[contracttype]
 #[derive(Debug, Clone)]
@@ -109,141 +110,156 @@ impl CollisionEvent {
             collision_type,
         }
     }
-    fn to_string(&self) -> String {
-        let s = "collision"; // Simplified for now
-        String::from(s)
-    }
 }
 impl EventTrait for CollisionEvent {
     fn event_type() -> Symbol {
         symbol_short!("collision")
     }
     fn serialize(&self, env: &Env) -> Bytes {
-        let mut bytes = Bytes::new(env);
-        bytes.append(&Bytes::from_slice(env, &self.entity_a.to_be_bytes()));
-        bytes.append(&Bytes::from_slice(env, &self.entity_b.to_be_bytes()));
-        // Serialize the collision_type symbol by converting to Val and then to u64
-        let symbol_val: Val = self.collision_type.to_val();
-        let symbol_bits = symbol_val.get_payload();
-        bytes.append(&Bytes::from_slice(env, &symbol_bits.to_be_bytes()));
-        bytes
+        let mut writer = Writer::new(env);
+        writer
+            .write_u64(self.entity_a)
+            .write_u64(self.entity_b)
+            .write_symbol(&self.collision_type);
+        writer.finish()
     }
-    fn deserialize(env: &Env, data: &Bytes) -> Option<Self> {
-        if data.len() < 24 {
-            return None;
-        }
-        let entity_a = u64::from_be_bytes([
-            data.get(0)?,
-            data.get(1)?,
-            data.get(2)?,
-            data.get(3)?,
-            data.get(4)?,
-            data.get(5)?,
-            data.get(6)?,
-            data.get(7)?,
-        ]);
-        let entity_b = u64::from_be_bytes([
-            data.get(8)?,
-            data.get(9)?,
-            data.get(10)?,
-            data.get(11)?,
-            data.get(12)?,
-            data.get(13)?,
-            data.get(14)?,
-            data.get(15)?,
-        ]);
-        // Deserialize the symbol from its Val representation
-        let symbol_bits = u64::from_be_bytes([
-            data.get(16)?,
-            data.get(17)?,
-            data.get(18)?,
-            data.get(19)?,
-            data.get(20)?,
-            data.get(21)?,
-            data.get(22)?,
-            data.get(23)?,
-        ]);
-        let symbol_val = Val::from_payload(symbol_bits);
-        let collision_type: Symbol = Symbol::try_from_val(env, &symbol_val).ok()?;
-        Some(Self {
-            entity_a,
-            entity_b,
-            collision_type,
-        })
+    fn deserialize(_env: &Env, data: &Bytes) -> Option<Self> {
+        let mut reader = Reader::new(data);
+        let event = Self {
+            entity_a: reader.read_u64().ok()?,
+            entity_b: reader.read_u64().ok()?,
+            collision_type: reader.read_symbol().ok()?,
+        };
+        reader.finish().ok()?;
+        Some(event)
     }
 }
 
-#[contracttype]
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DamageEvent {
-    pub target_entity: u64,
+    pub target_entity: EntityId,
     pub damage_amount: i32,
     pub damage_type: Symbol,
 }
 impl DamageEvent {
-    pub fn new(target_entity: u64, damage_amount: i32, damage_type: Symbol) -> Self {
+    pub fn new(target_entity: EntityId, damage_amount: i32, damage_type: Symbol) -> Self {
         Self {
             target_entity,
             damage_amount,
             damage_type,
         }
     }
-    fn to_string(&self) -> String {
-        let s = "damage"; // Simplified for now
-        String::from(s)
-    }
 }
 impl EventTrait for DamageEvent {
     fn event_type() -> Symbol {
         symbol_short!("damage")
     }
     fn serialize(&self, env: &Env) -> Bytes {
-        let mut bytes = Bytes::new(env);
-        bytes.append(&Bytes::from_slice(env, &self.target_entity.to_be_bytes()));
-        bytes.append(&Bytes::from_slice(env, &self.damage_amount.to_be_bytes()));
-        // Serialize the damage_type symbol by converting to Val and then to u64
-        let symbol_val: Val = self.damage_type.to_val();
-        let symbol_bits = symbol_val.get_payload();
-        bytes.append(&Bytes::from_slice(env, &symbol_bits.to_be_bytes()));
+        let mut writer = Writer::new(env);
+        writer
+            .write_entity_id(self.target_entity)
+            .write_i32(self.damage_amount)
+            .write_symbol(&self.damage_type);
+        writer.finish()
+    }
+    fn deserialize(_env: &Env, data: &Bytes) -> Option<Self> {
+        let mut reader = Reader::new(data);
+        let event = Self {
+            target_entity: reader.read_entity_id().ok()?,
+            damage_amount: reader.read_i32().ok()?,
+            damage_type: reader.read_symbol().ok()?,
+        };
+        reader.finish().ok()?;
+        Some(event)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealEvent {
+    pub target_entity: EntityId,
+    pub heal_amount: i32,
+}
+impl HealEvent {
+    pub fn new(target_entity: EntityId, heal_amount: i32) -> Self {
+        Self {
+            target_entity,
+            heal_amount,
+        }
+    }
+}
+impl EventTrait for HealEvent {
+    fn event_type() -> Symbol {
+        symbol_short!("heal")
+    }
+    fn serialize(&self, env: &Env) -> Bytes {
+        let mut bytes = self.target_entity.to_bytes(env);
+        bytes.append(&Bytes::from_slice(env, &self.heal_amount.to_be_bytes()));
         bytes
     }
-    fn deserialize(env: &Env, data: &Bytes) -> Option<Self> {
-        if data.len() < 20 {
+    fn deserialize(_env: &Env, data: &Bytes) -> Option<Self> {
+        if data.len() != 16 {
             return None;
         }
-        let target_entity = u64::from_be_bytes([
-            data.get(0)?,
-            data.get(1)?,
-            data.get(2)?,
-            data.get(3)?,
-            data.get(4)?,
-            data.get(5)?,
-            data.get(6)?,
-            data.get(7)?,
-        ]);
-        let damage_amount =
-            i32::from_be_bytes([data.get(8)?, data.get(9)?, data.get(10)?, data.get(11)?]);
-        // Deserialize the symbol from its Val representation
-        let symbol_bits = u64::from_be_bytes([
-            data.get(12)?,
-            data.get(13)?,
-            data.get(14)?,
-            data.get(15)?,
-            data.get(16)?,
-            data.get(17)?,
-            data.get(18)?,
-            data.get(19)?,
-        ]);
-        let symbol_val = Val::from_payload(symbol_bits);
-        let damage_type: Symbol = Symbol::try_from_val(env, &symbol_val).ok()?;
+        let target_entity = EntityId::from_bytes(data, 0)?;
+        let heal_amount =
+            i32::from_be_bytes([data.get(12)?, data.get(13)?, data.get(14)?, data.get(15)?]);
         Some(Self {
             target_entity,
-            damage_amount,
-            damage_type,
+            heal_amount,
         })
     }
 }
 
+/// Sent when an entity's health drops to zero or below
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiedEvent {
+    pub entity: EntityId,
+}
+impl DiedEvent {
+    pub fn new(entity: EntityId) -> Self {
+        Self { entity }
+    }
+}
+impl EventTrait for DiedEvent {
+    fn event_type() -> Symbol {
+        symbol_short!("died")
+    }
+    fn serialize(&self, env: &Env) -> Bytes {
+        self.entity.to_bytes(env)
+    }
+    fn deserialize(_env: &Env, data: &Bytes) -> Option<Self> {
+        if data.len() != EntityId::ENCODED_LEN {
+            return None;
+        }
+        EntityId::from_bytes(data, 0).map(Self::new)
+    }
+}
+
+/// Sent when a [`Timer`](crate::timer::Timer) on an entity finishes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimerFinishedEvent {
+    pub entity: EntityId,
+}
+impl TimerFinishedEvent {
+    pub fn new(entity: EntityId) -> Self {
+        Self { entity }
+    }
+}
+impl EventTrait for TimerFinishedEvent {
+    fn event_type() -> Symbol {
+        symbol_short!("timerdone")
+    }
+    fn serialize(&self, env: &Env) -> Bytes {
+        self.entity.to_bytes(env)
+    }
+    fn deserialize(_env: &Env, data: &Bytes) -> Option<Self> {
+        if data.len() != EntityId::ENCODED_LEN {
+            return None;
+        }
+        EntityId::from_bytes(data, 0).map(Self::new)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -278,7 +294,7 @@ mod tests {
     #[test]
     fn test_damage_event_serialization() {
         let env = Env::default();
-        let damage_event = DamageEvent::new(789, 50, symbol_short!("fire"));
+        let damage_event = DamageEvent::new(EntityId::new(789, 3), 50, symbol_short!("fire"));
 
         let data = damage_event.serialize(&env);
         let deserialized = DamageEvent::deserialize(&env, &data).unwrap();
@@ -286,6 +302,38 @@ mod tests {
         assert_eq!(damage_event.target_entity, deserialized.target_entity);
         assert_eq!(damage_event.damage_amount, deserialized.damage_amount);
         assert_eq!(damage_event.damage_type, deserialized.damage_type);
+        assert_eq!(DamageEvent::deserialize(&env, &data.slice(..4)), None);
+    }
+
+    #[test]
+    fn test_long_symbols_survive_serialization() {
+        let env = Env::default();
+        // Symbols over nine characters live in the host, not in their Val
+        let collision_event = CollisionEvent::new(1, 2, Symbol::new(&env, "projectile_impact"));
+        let data = collision_event.serialize(&env);
+        let deserialized = CollisionEvent::deserialize(&env, &data).unwrap();
+        assert_eq!(collision_event.collision_type, deserialized.collision_type);
+
+        let damage_event = DamageEvent::new(
+            EntityId::new(5, 0),
+            -3,
+            Symbol::new(&env, "poison_over_time"),
+        );
+        let data = damage_event.serialize(&env);
+        assert_eq!(DamageEvent::deserialize(&env, &data), Some(damage_event));
+    }
+
+    #[test]
+    fn test_heal_and_died_event_serialization() {
+        let env = Env::default();
+        let heal_event = HealEvent::new(EntityId::new(12, 1), 30);
+        let data = heal_event.serialize(&env);
+        assert_eq!(HealEvent::deserialize(&env, &data), Some(heal_event));
+
+        let died_event = DiedEvent::new(EntityId::new(12, 1));
+        let data = died_event.serialize(&env);
+        assert_eq!(DiedEvent::deserialize(&env, &data), Some(died_event));
+        assert_eq!(DiedEvent::deserialize(&env, &Bytes::new(&env)), None);
     }
 
     // TODO: These tests require std vec! macro - need to adapt for Soroban
@@ -0,0 +1,288 @@
+//! Health, damage and healing.
+//!
+//! Entities with a [`Health`] component take damage from [`DamageEvent`]s,
+//! reduced by their [`Armor`], and recover from [`HealEvent`]s up to their
+//! [`MaxHealth`]. [`HealthSystem`] applies these events in the order they were
+//! sent and sends a [`DiedEvent`] for every entity whose health drops to zero.
+
+use crate::component::ComponentTrait;
+use crate::entity::EntityId;
+use crate::event::{DamageEvent, DiedEvent, EventTrait, HealEvent};
+use crate::system::System;
+use crate::world::World;
+use alloc::vec::Vec as StdVec;
+use soroban_sdk::{symbol_short, Bytes, Env, Symbol, Vec};
+
+fn serialize_amount(env: &Env, amount: i32) -> Bytes {
+    Bytes::from_array(env, &amount.to_be_bytes())
+}
+
+fn deserialize_amount(data: &Bytes) -> Option<i32> {
+    if data.len() != 4 {
+        return None;
+    }
+    let mut buf = [0u8; 4];
+    data.copy_into_slice(&mut buf);
+    Some(i32::from_be_bytes(buf))
+}
+
+/// Current hit points of an entity, dead at zero or below
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Health(pub i32);
+
+impl Health {
+    /// Check whether the entity still has hit points left
+    pub fn is_alive(&self) -> bool {
+        self.0 > 0
+    }
+}
+
+impl ComponentTrait for Health {
+    fn component_type() -> Symbol {
+        symbol_short!("health")
+    }
+    fn serialize(&self, env: &Env) -> Bytes {
+        serialize_amount(env, self.0)
+    }
+    fn deserialize(_env: &Env, data: &Bytes) -> Option<Self> {
+        deserialize_amount(data).map(Self)
+    }
+}
+
+/// Cap on the [`Health`] an entity can be healed to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaxHealth(pub i32);
+
+impl ComponentTrait for MaxHealth {
+    fn component_type() -> Symbol {
+        symbol_short!("maxhealth")
+    }
+    fn serialize(&self, env: &Env) -> Bytes {
+        serialize_amount(env, self.0)
+    }
+    fn deserialize(_env: &Env, data: &Bytes) -> Option<Self> {
+        deserialize_amount(data).map(Self)
+    }
+}
+
+/// Flat amount subtracted from every hit an entity takes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Armor(pub i32);
+
+impl ComponentTrait for Armor {
+    fn component_type() -> Symbol {
+        symbol_short!("armor")
+    }
+    fn serialize(&self, env: &Env) -> Bytes {
+        serialize_amount(env, self.0)
+    }
+    fn deserialize(_env: &Env, data: &Bytes) -> Option<Self> {
+        deserialize_amount(data).map(Self)
+    }
+}
+
+/// Applies [`DamageEvent`]s and [`HealEvent`]s to [`Health`] components
+///
+/// The events are consumed, so running the system again does not apply them
+/// twice. Events aimed at entities that no longer exist, have no health or
+/// are already dead are dropped; dead entities cannot be healed. Returns the
+/// entities that died during the run.
+#[derive(Debug, Clone, Default)]
+pub struct HealthSystem {
+    despawn_dead: bool,
+}
+
+impl HealthSystem {
+    /// Create a health system that leaves dead entities in the world
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a health system that despawns entities once they die
+    pub fn despawning_dead() -> Self {
+        Self { despawn_dead: true }
+    }
+
+    /// Change the health of `entity_id` by `change`, returning whether it died
+    fn apply(world: &mut World, entity_id: EntityId, change: impl Fn(i32) -> i32) -> bool {
+        if !world.exists(entity_id) {
+            return false;
+        }
+        let Some(health) = world.get_typed_component::<Health>(entity_id) else {
+            return false;
+        };
+        if !health.is_alive() {
+            return false;
+        }
+        let health = Health(change(health.0));
+        world.add_typed_component(entity_id, &health);
+        !health.is_alive()
+    }
+}
+
+impl System for HealthSystem {
+    type In = ();
+    type Out = StdVec<EntityId>;
+
+    fn run(&mut self, world: &mut World, _input: Self::In) -> Self::Out {
+        let (damage_type, heal_type) = (DamageEvent::event_type(), HealEvent::event_type());
+        let env = world.env().clone();
+        let mut pending = Vec::new(&env);
+        let mut kept = Vec::new(&env);
+        for event in world.events.iter() {
+            if event.event_type() == &damage_type || event.event_type() == &heal_type {
+                pending.push_back(event);
+            } else {
+                kept.push_back(event);
+            }
+        }
+        world.events = kept;
+
+        let mut died = StdVec::new();
+        for event in pending.iter() {
+            let dead = if let Some(damage) = DamageEvent::deserialize(&env, event.data()) {
+                let armor = world
+                    .get_typed_component::<Armor>(damage.target_entity)
+                    .map_or(0, |armor| armor.0);
+                let amount = damage.damage_amount.saturating_sub(armor).max(0);
+                Self::apply(world, damage.target_entity, |health| {
+                    health.saturating_sub(amount)
+                })
+                .then_some(damage.target_entity)
+            } else if let Some(heal) = HealEvent::deserialize(&env, event.data()) {
+                let max = world
+                    .get_typed_component::<MaxHealth>(heal.target_entity)
+                    .map_or(i32::MAX, |max| max.0);
+                let amount = heal.heal_amount.max(0);
+                Self::apply(world, heal.target_entity, |health| {
+                    health.saturating_add(amount).min(max.max(health))
+                })
+                .then_some(heal.target_entity)
+            } else {
+                None
+            };
+            if let Some(entity_id) = dead {
+                world.send_typed_event(&DiedEvent::new(entity_id));
+                died.push(entity_id);
+            }
+        }
+
+        if self.despawn_dead {
+            for entity_id in died.iter() {
+                world.despawn(*entity_id);
+            }
+        }
+        died
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spawn_fighter(world: &mut World, health: i32, max: i32, armor: i32) -> EntityId {
+        let entity_id = world.spawn_empty().id();
+        world.add_typed_component(entity_id, &Health(health));
+        world.add_typed_component(entity_id, &MaxHealth(max));
+        if armor > 0 {
+            world.add_typed_component(entity_id, &Armor(armor));
+        }
+        entity_id
+    }
+
+    fn health(world: &World, entity_id: EntityId) -> i32 {
+        world.get_typed_component::<Health>(entity_id).unwrap().0
+    }
+
+    #[test]
+    fn test_health_component_roundtrip() {
+        let env = Env::default();
+        assert_eq!(
+            Health::deserialize(&env, &Health(-3).serialize(&env)),
+            Some(Health(-3))
+        );
+        assert_eq!(
+            MaxHealth::deserialize(&env, &MaxHealth(100).serialize(&env)),
+            Some(MaxHealth(100))
+        );
+        assert_eq!(Armor::deserialize(&env, &Bytes::new(&env)), None);
+    }
+
+    #[test]
+    fn test_damage_armor_and_healing() {
+        let mut world = World::new();
+        let knight = spawn_fighter(&mut world, 100, 100, 5);
+        let squire = spawn_fighter(&mut world, 40, 50, 0);
+
+        world.send_typed_event(&DamageEvent::new(knight, 30, symbol_short!("slash")));
+        world.send_typed_event(&DamageEvent::new(knight, 3, symbol_short!("slash")));
+        world.send_typed_event(&DamageEvent::new(squire, 30, symbol_short!("fire")));
+        world.send_typed_event(&HealEvent::new(squire, 100));
+        world.send_typed_event(&HealEvent::new(knight, 10));
+
+        let mut system = HealthSystem::new();
+        assert!(system.run(&mut world, ()).is_empty());
+        // 30 - 5 armor, then a hit fully absorbed, then healed by 10
+        assert_eq!(health(&world, knight), 85);
+        // Healing stops at the maximum
+        assert_eq!(health(&world, squire), 50);
+
+        // Events are consumed
+        assert!(system.run(&mut world, ()).is_empty());
+        assert_eq!(health(&world, knight), 85);
+        assert!(world.get_typed_events::<DamageEvent>().is_empty());
+    }
+
+    #[test]
+    fn test_death_is_reported_once() {
+        let mut world = World::new();
+        let slime = spawn_fighter(&mut world, 10, 10, 0);
+        world.send_typed_event(&DamageEvent::new(slime, 15, symbol_short!("hit")));
+        world.send_typed_event(&HealEvent::new(slime, 50));
+        world.send_typed_event(&DamageEvent::new(slime, 15, symbol_short!("hit")));
+
+        let died = HealthSystem::new().run(&mut world, ());
+        assert_eq!(died, [slime]);
+        assert_eq!(health(&world, slime), -5);
+        assert_eq!(
+            world.get_typed_events::<DiedEvent>(),
+            [DiedEvent::new(slime)]
+        );
+        assert!(world.exists(slime));
+    }
+
+    #[test]
+    fn test_dead_entities_are_despawned() {
+        let mut world = World::new();
+        let invader = spawn_fighter(&mut world, 1, 1, 0);
+        let bystander = world.spawn_empty().id();
+        world.send_typed_event(&DamageEvent::new(invader, 1, symbol_short!("laser")));
+        world.send_typed_event(&DamageEvent::new(bystander, 1, symbol_short!("laser")));
+        // Aimed at an entity that is gone
+        world.send_typed_event(&DamageEvent::new(
+            EntityId::new(99, 0),
+            1,
+            symbol_short!("laser"),
+        ));
+
+        let died = HealthSystem::despawning_dead().run(&mut world, ());
+        assert_eq!(died, [invader]);
+        assert!(!world.exists(invader));
+        assert!(world.exists(bystander));
+        assert_eq!(world.get_typed_events::<DiedEvent>().len(), 1);
+    }
+
+    #[test]
+    fn test_stale_target_misses_respawned_id() {
+        let mut world = World::new();
+        let goblin = spawn_fighter(&mut world, 5, 5, 0);
+        world.send_typed_event(&DamageEvent::new(goblin, 7, symbol_short!("arrow")));
+        world.despawn(goblin);
+        let hero = spawn_fighter(&mut world, 10, 10, 0);
+        assert_eq!(hero.id(), goblin.id());
+        assert_ne!(hero, goblin);
+
+        assert!(HealthSystem::new().run(&mut world, ()).is_empty());
+        assert_eq!(health(&world, hero), 10);
+    }
+}
@@ -3,34 +3,75 @@
 
 extern crate alloc;
 
-use soroban_sdk::{symbol_short, Bytes, Symbol, Vec};
+use soroban_sdk::{Symbol, Vec};
 
 // Global allocator for WASM
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
 // Core ECS types adapted for Soroban
+pub mod codec;
+pub mod collision;
 pub mod component;
 pub mod components;
 pub mod entity;
+pub mod entity_disabling;
 pub mod event;
+pub mod health;
+pub mod hierarchy;
+pub mod math;
+pub mod migration;
+pub mod name;
+pub mod pathfinding;
+pub mod persistence;
+pub mod player;
+pub mod prefab;
 pub mod query;
+pub mod relationship;
 pub mod resource;
+pub mod rng;
+pub mod schedule;
+pub mod session;
+pub mod spatial;
 pub mod storage;
 pub mod system;
 pub mod systems;
+pub mod tilemap;
+pub mod timer;
+pub mod turn;
+pub mod upgrade;
 pub mod world;
 
 // Re-export core types
-pub use component::{Component, ComponentId, ComponentStorage};
-pub use components::Position;
+pub use collision::{Collider, CollisionSystem, Shape};
+pub use component::{Component, ComponentId, ComponentStorage, StorageTier};
+pub use components::{Acceleration, Position, Rotation, Velocity};
 pub use entity::{Entity, EntityId};
+pub use entity_disabling::Disabled;
 pub use event::{Event, EventReader, EventWriter};
+pub use health::{Armor, Health, HealthSystem, MaxHealth};
+pub use hierarchy::ChildOf;
+pub use math::{Fixed, Vec2};
+pub use migration::MigrationError;
+pub use name::Name;
+pub use pathfinding::{Path, PathError, Pathfinder};
+pub use persistence::{TtlPolicy, WorldStore};
+pub use player::{Player, PlayerError};
+pub use prefab::Prefab;
 pub use query::{Query, QueryState};
+pub use relationship::{DespawnPolicy, Relationship};
 pub use resource::Resource;
+pub use rng::{GameRng, Rng};
+pub use schedule::{BudgetGuard, Schedule, SystemBudget, TickOutcome};
+pub use session::GameSession;
+pub use spatial::SpatialHash;
 pub use storage::{SparseStorage, Storage, TableStorage};
 pub use system::{IntoSystem, System, SystemParam};
-pub use systems::MovementSystem;
+pub use systems::{Bounds, BoundsBehavior, MovementSystem};
+pub use tilemap::{Connectivity, TileMap, TileProperties};
+pub use timer::{Cooldown, Deadline, Lifetime, TimeSource, Timer, TimerSystem};
+pub use turn::{TurnAction, TurnError, TurnManager};
+pub use upgrade::{UpgradeError, Upgrader};
 pub use world::World;
 
 // Library functions for ECS operations
@@ -63,11 +104,10 @@ pub fn get_component(
 pub fn query_entities(
     world: &World,
     component_types: Vec<Symbol>,
-    env: &soroban_sdk::Env,
+    _env: &soroban_sdk::Env,
 ) -> Vec<EntityId> {
-    // Since we can't easily convert Vec<Symbol> to &[Symbol] in Soroban,
-    // we'll need to restructure this. For now, return empty result.
-    Vec::new(env)
+    let component_types: alloc::vec::Vec<Symbol> = component_types.iter().collect();
+    world.query_entities(&component_types)
 }
 
 // Predule for common types
@@ -75,9 +115,16 @@ pub mod prelude {
     pub use super::{
         component::{Component, ComponentId, ComponentStorage},
         entity::{Entity, EntityId},
+        entity_disabling::Disabled,
         event::{Event, EventReader, EventWriter},
+        hierarchy::ChildOf,
+        math::{Fixed, Vec2},
+        name::Name,
+        prefab::Prefab,
         query::{Query, QueryState},
+        relationship::{DespawnPolicy, Relationship},
         resource::Resource,
+        rng::{GameRng, Rng},
         storage::{SparseStorage, Storage, TableStorage},
         system::{IntoSystem, System, SystemParam},
         world::World,
@@ -87,7 +134,7 @@ pub mod prelude {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use soroban_sdk::{testutils::Address as _, Env};
+    use soroban_sdk::Env;
 
     #[test]
     fn test_world_creation() {
@@ -100,7 +147,7 @@ mod tests {
     fn test_entity_spawn() {
         let _env = Env::default();
         let mut world = World::new();
-        let entity = world.spawn_empty();
+        world.spawn_empty();
         assert_eq!(world.entity_count(), 1);
     }
 }
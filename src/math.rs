@@ -0,0 +1,568 @@
+//! Fixed-point math for deterministic simulation.
+//!
+//! Floating point is not available to contracts, and ad-hoc integer scales
+//! make physics code hard to share between games. [`Fixed`] is a Q47.16
+//! fixed-point scalar (a signed 64-bit integer with 16 fractional bits) and
+//! [`Vec2`] a 2D vector of them. Operators panic on overflow, like integer
+//! arithmetic in a contract; the `checked_*` methods return `None` instead.
+//!
+//! Trigonometry uses lookup tables with linear interpolation, so results are
+//! identical on every host. Angles are in radians.
+
+use core::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+use soroban_sdk::{contracttype, Bytes, Env};
+
+/// Number of fractional bits of a [`Fixed`]
+pub const FRAC_BITS: u32 = 16;
+
+/// `sin` sampled at 65 evenly spaced angles from 0 to pi/2, in Q16
+const SIN_TABLE: [i64; 65] = [
+    0, 1608, 3216, 4821, 6424, 8022, 9616, 11204, 12785, 14359, 15924, 17479, 19024, 20557, 22078,
+    23586, 25080, 26558, 28020, 29466, 30893, 32303, 33692, 35062, 36410, 37736, 39040, 40320,
+    41576, 42806, 44011, 45190, 46341, 47464, 48559, 49624, 50660, 51665, 52639, 53581, 54491,
+    55368, 56212, 57022, 57798, 58538, 59244, 59914, 60547, 61145, 61705, 62228, 62714, 63162,
+    63572, 63944, 64277, 64571, 64827, 65043, 65220, 65358, 65457, 65516, 65536,
+];
+
+/// `atan` sampled at 65 evenly spaced ratios from 0 to 1, in Q16
+const ATAN_TABLE: [i64; 65] = [
+    0, 1024, 2047, 3070, 4091, 5110, 6126, 7140, 8150, 9156, 10158, 11155, 12147, 13133, 14114,
+    15088, 16055, 17015, 17968, 18913, 19850, 20779, 21699, 22610, 23512, 24406, 25289, 26163,
+    27028, 27882, 28727, 29561, 30386, 31200, 32003, 32797, 33580, 34353, 35115, 35867, 36608,
+    37340, 38060, 38771, 39472, 40162, 40842, 41512, 42172, 42823, 43464, 44095, 44716, 45328,
+    45931, 46525, 47109, 47685, 48251, 48809, 49359, 49899, 50432, 50956, 51472,
+];
+
+/// Look up `table` at `position` (Q16, scaled so the table spans 0..=ONE)
+fn interpolate(table: &[i64; 65], position: i64) -> i64 {
+    let scaled = position * 64;
+    let index = (scaled >> FRAC_BITS) as usize;
+    if index >= 64 {
+        return table[64];
+    }
+    let frac = scaled & ((1 << FRAC_BITS) - 1);
+    table[index] + (((table[index + 1] - table[index]) * frac) >> FRAC_BITS)
+}
+
+/// Integer square root: the largest `r` with `r * r <= n`
+pub fn isqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+    let mut x = 1u128 << ((129 - n.leading_zeros()) / 2);
+    loop {
+        let y = (x + n / x) / 2;
+        if y >= x {
+            return x;
+        }
+        x = y;
+    }
+}
+
+/// Fixed-point number with [`FRAC_BITS`] fractional bits
+#[contracttype]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct Fixed(pub i64);
+
+impl Fixed {
+    pub const ZERO: Fixed = Fixed(0);
+    pub const ONE: Fixed = Fixed(1 << FRAC_BITS);
+    pub const HALF: Fixed = Fixed(1 << (FRAC_BITS - 1));
+    /// Smallest positive value
+    pub const EPSILON: Fixed = Fixed(1);
+    pub const MIN: Fixed = Fixed(i64::MIN);
+    pub const MAX: Fixed = Fixed(i64::MAX);
+    pub const FRAC_PI_2: Fixed = Fixed(102_944);
+    pub const PI: Fixed = Fixed(205_887);
+    pub const TAU: Fixed = Fixed(411_775);
+
+    /// Size of the serialized form in bytes
+    pub const ENCODED_LEN: u32 = 8;
+
+    /// Create a value from its raw Q-format representation
+    pub const fn from_raw(raw: i64) -> Self {
+        Self(raw)
+    }
+
+    /// Get the raw Q-format representation
+    pub const fn raw(self) -> i64 {
+        self.0
+    }
+
+    /// Convert an integer, panicking if it does not fit
+    pub fn from_int(value: i64) -> Self {
+        Self::checked_from_int(value).expect("fixed-point overflow")
+    }
+
+    /// Convert an integer, or `None` if it does not fit
+    pub fn checked_from_int(value: i64) -> Option<Self> {
+        value.checked_mul(1 << FRAC_BITS).map(Self)
+    }
+
+    /// Create the value `numerator / denominator`, or `None` if it does not fit
+    pub fn from_ratio(numerator: i64, denominator: i64) -> Option<Self> {
+        if denominator == 0 {
+            return None;
+        }
+        let raw = ((numerator as i128) << FRAC_BITS) / denominator as i128;
+        i64::try_from(raw).ok().map(Self)
+    }
+
+    /// Round towards negative infinity and convert to an integer
+    pub fn to_int(self) -> i64 {
+        self.0 >> FRAC_BITS
+    }
+
+    /// Round to the nearest integer, halves away from zero
+    pub fn round(self) -> i64 {
+        // Adding a half overflows i64 at the extremes, never the result
+        let (raw, half) = (self.0 as i128, Self::HALF.0 as i128);
+        let rounded = if raw >= 0 {
+            (raw + half) >> FRAC_BITS
+        } else {
+            -((half - raw) >> FRAC_BITS)
+        };
+        rounded as i64
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        self.0.checked_add(other.0).map(Self)
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        self.0.checked_sub(other.0).map(Self)
+    }
+
+    pub fn checked_mul(self, other: Self) -> Option<Self> {
+        let raw = (self.0 as i128 * other.0 as i128) >> FRAC_BITS;
+        i64::try_from(raw).ok().map(Self)
+    }
+
+    /// Divide, or `None` on division by zero or overflow
+    pub fn checked_div(self, other: Self) -> Option<Self> {
+        if other.0 == 0 {
+            return None;
+        }
+        let raw = ((self.0 as i128) << FRAC_BITS) / other.0 as i128;
+        i64::try_from(raw).ok().map(Self)
+    }
+
+    pub fn checked_neg(self) -> Option<Self> {
+        self.0.checked_neg().map(Self)
+    }
+
+    pub fn abs(self) -> Self {
+        Self(self.0.checked_abs().expect("fixed-point overflow"))
+    }
+
+    /// Square root, or `None` for negative values
+    pub fn sqrt(self) -> Option<Self> {
+        if self.0 < 0 {
+            return None;
+        }
+        Some(Self(isqrt((self.0 as u128) << FRAC_BITS) as i64))
+    }
+
+    /// Interpolate from `self` (at `t = 0`) to `other` (at `t = 1`)
+    pub fn lerp(self, other: Self, t: Self) -> Self {
+        self + (other - self) * t
+    }
+
+    /// Sine of an angle in radians
+    pub fn sin(self) -> Self {
+        let quarter = Self::FRAC_PI_2.0;
+        let angle = self.0.rem_euclid(Self::TAU.0);
+        let offset = angle % quarter;
+        let position = |offset: i64| (offset << FRAC_BITS) / quarter;
+        let raw = match angle / quarter {
+            0 => interpolate(&SIN_TABLE, position(offset)),
+            1 => interpolate(&SIN_TABLE, position(quarter - offset)),
+            2 => -interpolate(&SIN_TABLE, position(offset)),
+            _ => -interpolate(&SIN_TABLE, position(quarter - offset)),
+        };
+        Self(raw)
+    }
+
+    /// Cosine of an angle in radians
+    pub fn cos(self) -> Self {
+        Self(self.0.rem_euclid(Self::TAU.0) + Self::FRAC_PI_2.0).sin()
+    }
+
+    /// Angle in radians, in `-PI..=PI`, of the vector (`x`, `self`)
+    pub fn atan2(self, x: Self) -> Self {
+        let (y, x) = (self.0 as i128, x.0 as i128);
+        if x == 0 && y == 0 {
+            return Self::ZERO;
+        }
+        let (ax, ay) = (x.abs(), y.abs());
+        let mut angle = if ay <= ax {
+            interpolate(&ATAN_TABLE, ((ay << FRAC_BITS) / ax) as i64)
+        } else {
+            Self::FRAC_PI_2.0 - interpolate(&ATAN_TABLE, ((ax << FRAC_BITS) / ay) as i64)
+        };
+        if x < 0 {
+            angle = Self::PI.0 - angle;
+        }
+        if y < 0 {
+            angle = -angle;
+        }
+        Self(angle)
+    }
+
+    /// Serialize for use in component data
+    pub fn to_bytes(&self, env: &Env) -> Bytes {
+        Bytes::from_array(env, &self.0.to_be_bytes())
+    }
+
+    /// Deserialize a value written by [`Fixed::to_bytes`] at `offset`
+    pub fn from_bytes(data: &Bytes, offset: u32) -> Option<Self> {
+        let end = offset.checked_add(Self::ENCODED_LEN)?;
+        if data.len() < end {
+            return None;
+        }
+        let mut buf = [0u8; 8];
+        data.slice(offset..end).copy_into_slice(&mut buf);
+        Some(Self(i64::from_be_bytes(buf)))
+    }
+}
+
+impl Add for Fixed {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        self.checked_add(other).expect("fixed-point overflow")
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self {
+        self.checked_sub(other).expect("fixed-point overflow")
+    }
+}
+
+impl Mul for Fixed {
+    type Output = Self;
+    fn mul(self, other: Self) -> Self {
+        self.checked_mul(other).expect("fixed-point overflow")
+    }
+}
+
+impl Div for Fixed {
+    type Output = Self;
+    fn div(self, other: Self) -> Self {
+        self.checked_div(other)
+            .expect("fixed-point division by zero or overflow")
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Self;
+    fn neg(self) -> Self {
+        self.checked_neg().expect("fixed-point overflow")
+    }
+}
+
+impl AddAssign for Fixed {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Fixed {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+/// 2D vector of [`Fixed`] components
+#[contracttype]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Vec2 {
+    pub x: Fixed,
+    pub y: Fixed,
+}
+
+impl Vec2 {
+    pub const ZERO: Vec2 = Vec2 {
+        x: Fixed::ZERO,
+        y: Fixed::ZERO,
+    };
+
+    /// Size of the serialized form in bytes
+    pub const ENCODED_LEN: u32 = 2 * Fixed::ENCODED_LEN;
+
+    pub fn new(x: Fixed, y: Fixed) -> Self {
+        Self { x, y }
+    }
+
+    /// Create a vector from integer components
+    pub fn from_ints(x: i64, y: i64) -> Self {
+        Self::new(Fixed::from_int(x), Fixed::from_int(y))
+    }
+
+    /// Unit vector pointing at `angle` radians
+    pub fn from_angle(angle: Fixed) -> Self {
+        Self::new(angle.cos(), angle.sin())
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        Some(Self::new(
+            self.x.checked_add(other.x)?,
+            self.y.checked_add(other.y)?,
+        ))
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        Some(Self::new(
+            self.x.checked_sub(other.x)?,
+            self.y.checked_sub(other.y)?,
+        ))
+    }
+
+    /// Multiply both components by `factor`
+    pub fn checked_scale(self, factor: Fixed) -> Option<Self> {
+        Some(Self::new(
+            self.x.checked_mul(factor)?,
+            self.y.checked_mul(factor)?,
+        ))
+    }
+
+    /// Dot product
+    pub fn dot(self, other: Self) -> Fixed {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// Z component of the 3D cross product
+    pub fn cross(self, other: Self) -> Fixed {
+        self.x * other.y - self.y * other.x
+    }
+
+    /// Squared length, cheaper than [`Vec2::length`] for comparisons
+    pub fn length_squared(self) -> Fixed {
+        self.dot(self)
+    }
+
+    /// Length, or `None` if it does not fit in a [`Fixed`]
+    pub fn checked_length(self) -> Option<Fixed> {
+        let (x, y) = (self.x.0 as i128, self.y.0 as i128);
+        let squared = (x * x) as u128 + (y * y) as u128;
+        i64::try_from(isqrt(squared)).ok().map(Fixed)
+    }
+
+    /// Length, computed with an integer square root
+    pub fn length(self) -> Fixed {
+        self.checked_length().expect("fixed-point overflow")
+    }
+
+    /// Distance between two points
+    pub fn distance(self, other: Self) -> Fixed {
+        (other - self).length()
+    }
+
+    /// Vector with the same direction and a length of one, or `None` for the zero vector
+    pub fn normalize(self) -> Option<Self> {
+        let length = self.checked_length()?;
+        Some(Self::new(
+            self.x.checked_div(length)?,
+            self.y.checked_div(length)?,
+        ))
+    }
+
+    /// Interpolate from `self` (at `t = 0`) to `other` (at `t = 1`)
+    pub fn lerp(self, other: Self, t: Fixed) -> Self {
+        Self::new(self.x.lerp(other.x, t), self.y.lerp(other.y, t))
+    }
+
+    /// Angle in radians, in `-PI..=PI`, from the positive x axis
+    pub fn angle(self) -> Fixed {
+        self.y.atan2(self.x)
+    }
+
+    /// Serialize for use in component data
+    pub fn to_bytes(&self, env: &Env) -> Bytes {
+        let mut bytes = self.x.to_bytes(env);
+        bytes.append(&self.y.to_bytes(env));
+        bytes
+    }
+
+    /// Deserialize a vector written by [`Vec2::to_bytes`] at `offset`
+    pub fn from_bytes(data: &Bytes, offset: u32) -> Option<Self> {
+        Some(Self::new(
+            Fixed::from_bytes(data, offset)?,
+            Fixed::from_bytes(data, offset.checked_add(Fixed::ENCODED_LEN)?)?,
+        ))
+    }
+}
+
+impl Add for Vec2 {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        Self::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl Sub for Vec2 {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl Mul<Fixed> for Vec2 {
+    type Output = Self;
+    fn mul(self, factor: Fixed) -> Self {
+        Self::new(self.x * factor, self.y * factor)
+    }
+}
+
+impl Div<Fixed> for Vec2 {
+    type Output = Self;
+    fn div(self, divisor: Fixed) -> Self {
+        Self::new(self.x / divisor, self.y / divisor)
+    }
+}
+
+impl Neg for Vec2 {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Self::new(-self.x, -self.y)
+    }
+}
+
+impl AddAssign for Vec2 {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Vec2 {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: Fixed, expected: Fixed, tolerance: i64) {
+        assert!(
+            (actual.raw() - expected.raw()).abs() <= tolerance,
+            "{actual:?} is not within {tolerance} of {expected:?}"
+        );
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let a = Fixed::from_int(3);
+        let b = Fixed::from_ratio(1, 2).unwrap();
+        assert_eq!(a + b, Fixed::from_raw(3 * 65536 + 32768));
+        assert_eq!(a * b, Fixed::from_ratio(3, 2).unwrap());
+        assert_eq!(a / b, Fixed::from_int(6));
+        assert_eq!((-a).to_int(), -3);
+        assert_eq!((-b).to_int(), -1);
+        assert_eq!(Fixed::from_ratio(5, 2).unwrap().round(), 3);
+        assert_eq!(Fixed::from_ratio(-5, 2).unwrap().round(), -3);
+        assert_eq!(Fixed::MAX.round(), Fixed::MAX.to_int() + 1);
+        assert_eq!(Fixed::MIN.round(), Fixed::MIN.to_int());
+        assert_eq!(Fixed::ONE.lerp(Fixed::from_int(3), b), Fixed::from_int(2));
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        assert_eq!(Fixed::MAX.checked_add(Fixed::EPSILON), None);
+        assert_eq!(Fixed::MIN.checked_sub(Fixed::EPSILON), None);
+        assert_eq!(Fixed::MAX.checked_mul(Fixed::from_int(2)), None);
+        assert_eq!(Fixed::ONE.checked_div(Fixed::ZERO), None);
+        assert_eq!(Fixed::MIN.checked_neg(), None);
+        assert_eq!(Fixed::checked_from_int(i64::MAX), None);
+        assert_eq!(Fixed::from_ratio(1, 0), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "fixed-point overflow")]
+    fn test_operator_overflow_panics() {
+        let _ = Fixed::MAX + Fixed::ONE;
+    }
+
+    #[test]
+    fn test_isqrt() {
+        assert_eq!(isqrt(0), 0);
+        assert_eq!(isqrt(1), 1);
+        assert_eq!(isqrt(15), 3);
+        assert_eq!(isqrt(16), 4);
+        assert_eq!(isqrt(u128::MAX), u64::MAX as u128);
+        assert_eq!(Fixed::from_int(9).sqrt(), Some(Fixed::from_int(3)));
+        assert_eq!(Fixed::from_int(-1).sqrt(), None);
+    }
+
+    #[test]
+    fn test_vector_operations() {
+        let v = Vec2::from_ints(3, 4);
+        assert_eq!(v.length(), Fixed::from_int(5));
+        assert_eq!(v.length_squared(), Fixed::from_int(25));
+        assert_eq!(v.dot(Vec2::from_ints(2, 1)), Fixed::from_int(10));
+        assert_eq!(v.cross(Vec2::from_ints(2, 1)), Fixed::from_int(-5));
+        assert_eq!(
+            v + Vec2::from_ints(1, 1) - Vec2::from_ints(4, 5),
+            Vec2::ZERO
+        );
+        assert_eq!(v * Fixed::from_int(2), Vec2::from_ints(6, 8));
+        assert_eq!(Vec2::ZERO.distance(v), Fixed::from_int(5));
+
+        let unit = v.normalize().unwrap();
+        assert_close(unit.length(), Fixed::ONE, 2);
+        assert_eq!(Vec2::ZERO.normalize(), None);
+
+        let mid = Vec2::ZERO.lerp(v, Fixed::HALF);
+        assert_eq!(
+            mid,
+            Vec2::new(Fixed::from_ratio(3, 2).unwrap(), Fixed::from_int(2))
+        );
+        assert_eq!(Vec2::new(Fixed::MAX, Fixed::MAX).checked_add(v), None);
+    }
+
+    #[test]
+    fn test_trigonometry() {
+        let tolerance = 64;
+        assert_close(Fixed::ZERO.sin(), Fixed::ZERO, tolerance);
+        assert_close(Fixed::FRAC_PI_2.sin(), Fixed::ONE, tolerance);
+        assert_close(Fixed::PI.sin(), Fixed::ZERO, tolerance);
+        assert_close((-Fixed::FRAC_PI_2).sin(), -Fixed::ONE, tolerance);
+        assert_close(Fixed::ZERO.cos(), Fixed::ONE, tolerance);
+        assert_close(Fixed::PI.cos(), -Fixed::ONE, tolerance);
+        // sin(pi / 6) = 0.5
+        assert_close(
+            (Fixed::PI / Fixed::from_int(6)).sin(),
+            Fixed::HALF,
+            tolerance,
+        );
+
+        assert_close(
+            Fixed::ONE.atan2(Fixed::ONE),
+            Fixed::PI / Fixed::from_int(4),
+            tolerance,
+        );
+        assert_close(Fixed::ONE.atan2(Fixed::ZERO), Fixed::FRAC_PI_2, tolerance);
+        assert_close(Fixed::ZERO.atan2(-Fixed::ONE), Fixed::PI, tolerance);
+        assert_close(
+            (-Fixed::ONE).atan2(-Fixed::ONE),
+            -(Fixed::PI * Fixed::from_ratio(3, 4).unwrap()),
+            tolerance,
+        );
+        assert_eq!(Fixed::ZERO.atan2(Fixed::ZERO), Fixed::ZERO);
+
+        let heading = Fixed::from_ratio(2, 3).unwrap();
+        assert_close(Vec2::from_angle(heading).angle(), heading, tolerance);
+    }
+
+    #[test]
+    fn test_serialization() {
+        let env = Env::default();
+        let v = Vec2::new(Fixed::from_ratio(-7, 4).unwrap(), Fixed::MAX);
+        let bytes = v.to_bytes(&env);
+        assert_eq!(bytes.len(), Vec2::ENCODED_LEN);
+        assert_eq!(Vec2::from_bytes(&bytes, 0), Some(v));
+        assert_eq!(Vec2::from_bytes(&bytes, 1), None);
+    }
+}
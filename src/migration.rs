@@ -0,0 +1,510 @@
+//! Versioned component layouts and migrations between versions.
+//!
+//! Every component type has a schema version, given by
+//! [`ComponentTrait::schema_version`] and 1 unless the type says otherwise.
+//! When a deployed game changes the layout of a component type, it raises the
+//! version and registers a migration with [`World::register_migration`] that
+//! upgrades data from the previous version.
+//!
+//! A [`WorldStore`](crate::persistence::WorldStore) records the version each
+//! component type was saved at. Loading a world saved by older code marks the
+//! components saved at an older version as stale, leaving their data as it
+//! was. They are then upgraded either:
+//!
+//! - lazily: [`World::get_typed_component`] upgrades stale data each time it
+//!   is read, and the stored data is replaced when the component is next
+//!   written, so only the entities a game touches are ever migrated;
+//! - in bulk: [`World::migrate_components`] upgrades every stale component at
+//!   once, and [`WorldStore::migrate`](crate::persistence::WorldStore::migrate)
+//!   saves the result.
+//!
+//! Stale components keep their stale mark when saved, so a lazily migrated
+//! world can be saved and loaded any number of times.
+
+use crate::component::{Component, ComponentTrait};
+use crate::entity::EntityId;
+use crate::world::World;
+use alloc::vec::Vec as StdVec;
+use soroban_sdk::{Bytes, Env, Symbol};
+
+/// Upgrades serialized component data by one schema version
+pub type MigrateFn = fn(&Env, &Bytes) -> Option<Bytes>;
+
+/// Errors returned when stale components cannot be upgraded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MigrationError {
+    /// No migration upgrades `component_type` from `version`
+    Missing {
+        component_type: Symbol,
+        version: u32,
+    },
+    /// The migration of `component_type` from `version` rejected the data of
+    /// `entity_id`
+    Failed {
+        entity_id: EntityId,
+        component_type: Symbol,
+        version: u32,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Migration {
+    component_type: Symbol,
+    from_version: u32,
+    migrate: MigrateFn,
+}
+
+/// Migrations registered with a world, and the version they upgrade each
+/// component type to
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MigrationRegistry {
+    migrations: StdVec<Migration>,
+    versions: StdVec<(Symbol, u32)>,
+}
+
+impl MigrationRegistry {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    fn register<T: ComponentTrait>(&mut self, from_version: u32, migrate: MigrateFn) {
+        let component_type = T::component_type();
+        self.migrations.retain(|migration| {
+            migration.component_type != component_type || migration.from_version != from_version
+        });
+        self.migrations.push(Migration {
+            component_type: component_type.clone(),
+            from_version,
+            migrate,
+        });
+        self.versions.retain(|(ctype, _)| *ctype != component_type);
+        self.versions.push((component_type, T::schema_version()));
+    }
+
+    /// Get the version migrations upgrade `component_type` to
+    fn current_version(&self, component_type: &Symbol) -> Option<u32> {
+        self.versions
+            .iter()
+            .find(|(ctype, _)| ctype == component_type)
+            .map(|(_, version)| *version)
+    }
+
+    /// Upgrade `data` of `component_type` from `version` to `target`, one
+    /// version at a time
+    fn upgrade(
+        &self,
+        env: &Env,
+        entity_id: EntityId,
+        component_type: &Symbol,
+        mut version: u32,
+        target: u32,
+        data: &Bytes,
+    ) -> Result<Bytes, MigrationError> {
+        let mut data = data.clone();
+        while version < target {
+            let migration = self
+                .migrations
+                .iter()
+                .find(|migration| {
+                    migration.component_type == *component_type && migration.from_version == version
+                })
+                .ok_or_else(|| MigrationError::Missing {
+                    component_type: component_type.clone(),
+                    version,
+                })?;
+            data = (migration.migrate)(env, &data).ok_or_else(|| MigrationError::Failed {
+                entity_id,
+                component_type: component_type.clone(),
+                version,
+            })?;
+            version += 1;
+        }
+        Ok(data)
+    }
+}
+
+impl World {
+    /// Upgrade data of `T` saved at `from_version` to `from_version + 1`
+    ///
+    /// `T` is the current layout; data several versions old goes through the
+    /// migration from each version in turn. The world also records the
+    /// version `T` declares, so worlds it loads mark older data as stale.
+    pub fn register_migration<T: ComponentTrait>(&mut self, from_version: u32, migrate: MigrateFn) {
+        self.declare_component::<T>();
+        self.migrations.register::<T>(from_version, migrate);
+    }
+
+    /// Get the older schema version a component is stored at, or `None` if
+    /// its data is current
+    pub fn stale_version(&self, entity_id: EntityId, component_type: &Symbol) -> Option<u32> {
+        if self.stale.is_empty() {
+            return None;
+        }
+        self.stale.get((entity_id, component_type.clone()))
+    }
+
+    /// Get the number of components stored at an older schema version
+    pub fn stale_component_count(&self) -> u32 {
+        self.stale.len()
+    }
+
+    /// Upgrade every stale component to the version its migrations lead to,
+    /// returning how many were upgraded
+    ///
+    /// Nothing is changed if any component cannot be upgraded.
+    pub fn migrate_components(&mut self) -> Result<u32, MigrationError> {
+        let env = self.env().clone();
+        let mut upgraded = StdVec::new();
+        for ((entity_id, component_type), version) in self.stale.iter() {
+            let Some(component) = self.get_component(entity_id, &component_type) else {
+                continue;
+            };
+            let target = self
+                .migrations
+                .current_version(&component_type)
+                .ok_or_else(|| MigrationError::Missing {
+                    component_type: component_type.clone(),
+                    version,
+                })?;
+            let data = self.migrations.upgrade(
+                &env,
+                entity_id,
+                &component_type,
+                version,
+                target,
+                component.data(),
+            )?;
+            upgraded.push((entity_id, Component::new(component_type, data)));
+        }
+        let count = upgraded.len() as u32;
+        // Writing a component clears its stale mark
+        for (entity_id, component) in upgraded {
+            self.add_component_to_entity(entity_id, component);
+        }
+        Ok(count)
+    }
+
+    /// Get the data of `T` on `entity_id` at the current version of `T`,
+    /// upgrading it if it is stale
+    pub(crate) fn current_data<T: ComponentTrait>(
+        &self,
+        entity_id: EntityId,
+        component: Component,
+    ) -> Option<Bytes> {
+        let Some(version) = self.stale_version(entity_id, component.component_type()) else {
+            return Some(component.data().clone());
+        };
+        self.migrations
+            .upgrade(
+                self.env(),
+                entity_id,
+                component.component_type(),
+                version,
+                T::schema_version(),
+                component.data(),
+            )
+            .ok()
+    }
+
+    /// Get `component` of `entity_id` at the current version of its type,
+    /// upgrading it if it is stale, or `None` if it cannot be upgraded
+    pub(crate) fn upgraded_component(
+        &self,
+        entity_id: EntityId,
+        component: &Component,
+    ) -> Option<Component> {
+        let component_type = component.component_type();
+        let Some(version) = self.stale_version(entity_id, component_type) else {
+            return Some(component.clone());
+        };
+        let target = self.migrations.current_version(component_type)?;
+        self.migrations
+            .upgrade(
+                self.env(),
+                entity_id,
+                component_type,
+                version,
+                target,
+                component.data(),
+            )
+            .ok()
+            .map(|data| Component::new(component_type.clone(), data))
+    }
+
+    /// Record that a component is stored at `version`, older than its type's
+    pub(crate) fn mark_stale(&mut self, entity_id: EntityId, component_type: Symbol, version: u32) {
+        self.stale.set((entity_id, component_type), version);
+    }
+
+    /// Forget the stale mark of a component whose data was replaced or removed
+    pub(crate) fn unmark_stale(&mut self, entity_id: EntityId, component_type: &Symbol) {
+        if !self.stale.is_empty() {
+            self.stale.remove((entity_id, component_type.clone()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::WorldStore;
+    use soroban_sdk::{contract, symbol_short, Address};
+
+    #[contract]
+    struct MigrationTestContract;
+
+    /// A ship as the first release of a game stored it
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct ShipV1(i32);
+
+    impl ComponentTrait for ShipV1 {
+        fn component_type() -> Symbol {
+            symbol_short!("ship")
+        }
+        fn serialize(&self, env: &Env) -> Bytes {
+            Bytes::from_array(env, &self.0.to_be_bytes())
+        }
+        fn deserialize(_env: &Env, data: &Bytes) -> Option<Self> {
+            let mut buf = [0u8; 4];
+            (data.len() == 4).then(|| {
+                data.copy_into_slice(&mut buf);
+                Self(i32::from_be_bytes(buf))
+            })
+        }
+    }
+
+    /// The same component after the second release added shields
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct ShipV2 {
+        hull: i32,
+        shield: i32,
+    }
+
+    impl ComponentTrait for ShipV2 {
+        fn component_type() -> Symbol {
+            symbol_short!("ship")
+        }
+        fn serialize(&self, env: &Env) -> Bytes {
+            let mut bytes = Bytes::from_array(env, &self.hull.to_be_bytes());
+            bytes.extend_from_array(&self.shield.to_be_bytes());
+            bytes
+        }
+        fn deserialize(_env: &Env, data: &Bytes) -> Option<Self> {
+            let mut buf = [0u8; 8];
+            (data.len() == 8).then(|| {
+                data.copy_into_slice(&mut buf);
+                Self {
+                    hull: i32::from_be_bytes(buf[..4].try_into().unwrap()),
+                    shield: i32::from_be_bytes(buf[4..].try_into().unwrap()),
+                }
+            })
+        }
+        fn schema_version() -> u32 {
+            2
+        }
+    }
+
+    /// Give every ship an empty shield, rejecting wrecked ones
+    fn add_shield(env: &Env, data: &Bytes) -> Option<Bytes> {
+        let ShipV1(hull) = ShipV1::deserialize(env, data)?;
+        (hull > 0).then(|| ShipV2 { hull, shield: 0 }.serialize(env))
+    }
+
+    fn v2_world(env: &Env) -> World {
+        let mut world = World::with_env(env);
+        world.register_migration::<ShipV2>(1, add_shield);
+        world
+    }
+
+    fn v2_store(env: &Env) -> WorldStore {
+        WorldStore::new(env, symbol_short!("fleet")).with_component::<ShipV2>()
+    }
+
+    /// Save a world of ships with the first release's code
+    fn save_v1(env: &Env, hulls: &[i32]) -> (Address, StdVec<EntityId>) {
+        let contract_id = env.register(MigrationTestContract, ());
+        let ships = env.as_contract(&contract_id, || {
+            let mut world = World::with_env(env);
+            let ships = hulls
+                .iter()
+                .map(|hull| {
+                    let ship = world.spawn_empty().id();
+                    world.add_typed_component(ship, &ShipV1(*hull));
+                    ship
+                })
+                .collect();
+            WorldStore::new(env, symbol_short!("fleet"))
+                .with_component::<ShipV1>()
+                .save(&world);
+            ships
+        });
+        (contract_id, ships)
+    }
+
+    #[test]
+    fn test_v1_world_migrates_lazily() {
+        let env = Env::default();
+        let (contract_id, ships) = save_v1(&env, &[10, 20]);
+        let ship_type = ShipV2::component_type();
+
+        env.as_contract(&contract_id, || {
+            let mut world = v2_world(&env);
+            let mut store = v2_store(&env);
+            assert!(store.load(&mut world));
+            assert_eq!(store.version_of(&ship_type), 2);
+            assert_eq!(world.stale_component_count(), 2);
+            assert_eq!(world.stale_version(ships[0], &ship_type), Some(1));
+
+            // Reads upgrade the data without rewriting it
+            assert_eq!(
+                world.get_typed_component::<ShipV2>(ships[0]),
+                Some(ShipV2 {
+                    hull: 10,
+                    shield: 0
+                })
+            );
+            let stored = world.get_component(ships[0], &ship_type).unwrap();
+            assert_eq!(stored.data().len(), 4);
+
+            // Writing the component stores it at the current version
+            world.add_typed_component(
+                ships[0],
+                &ShipV2 {
+                    hull: 10,
+                    shield: 5,
+                },
+            );
+            assert_eq!(world.stale_version(ships[0], &ship_type), None);
+            store.save(&world);
+        });
+
+        env.as_contract(&contract_id, || {
+            let mut world = v2_world(&env);
+            assert!(v2_store(&env).load(&mut world));
+            assert_eq!(world.stale_component_count(), 1);
+            assert_eq!(world.stale_version(ships[1], &ship_type), Some(1));
+            assert_eq!(
+                world.get_typed_component::<ShipV2>(ships[0]),
+                Some(ShipV2 {
+                    hull: 10,
+                    shield: 5
+                })
+            );
+            assert_eq!(
+                world.get_typed_component::<ShipV2>(ships[1]),
+                Some(ShipV2 {
+                    hull: 20,
+                    shield: 0
+                })
+            );
+        });
+    }
+
+    #[test]
+    fn test_registered_migrations_declare_versions() {
+        let env = Env::default();
+        let (contract_id, ships) = save_v1(&env, &[10]);
+        env.as_contract(&contract_id, || {
+            let mut world = v2_world(&env);
+            let mut store = WorldStore::new(&env, symbol_short!("fleet"));
+            assert!(store.load(&mut world));
+            assert_eq!(
+                world.stale_version(ships[0], &ShipV2::component_type()),
+                Some(1)
+            );
+            assert_eq!(store.migrate(&mut world), Ok(1));
+        });
+    }
+
+    #[test]
+    fn test_clones_of_stale_components() {
+        let env = Env::default();
+        let (contract_id, ships) = save_v1(&env, &[7]);
+        let ship_type = ShipV2::component_type();
+
+        env.as_contract(&contract_id, || {
+            let mut world = v2_world(&env);
+            assert!(v2_store(&env).load(&mut world));
+            let clone = world.clone_entity(ships[0]).unwrap();
+            assert_eq!(
+                world.get_typed_component::<ShipV2>(clone),
+                Some(ShipV2 { hull: 7, shield: 0 })
+            );
+            assert_eq!(world.stale_version(clone, &ship_type), None);
+
+            // Without a migration, the clone keeps the stale mark
+            let mut world = World::with_env(&env);
+            assert!(v2_store(&env).load(&mut world));
+            let clone = world.clone_entity(ships[0]).unwrap();
+            assert_eq!(world.stale_version(clone, &ship_type), Some(1));
+            assert_eq!(
+                world.get_component(clone, &ship_type).unwrap().data().len(),
+                4
+            );
+        });
+    }
+
+    #[test]
+    fn test_v1_world_migrates_in_bulk() {
+        let env = Env::default();
+        let (contract_id, ships) = save_v1(&env, &[10, 20, 30]);
+
+        env.as_contract(&contract_id, || {
+            let mut world = v2_world(&env);
+            assert_eq!(v2_store(&env).migrate(&mut world), Ok(3));
+            assert_eq!(world.stale_component_count(), 0);
+        });
+
+        // The saved world no longer needs the migrations
+        env.as_contract(&contract_id, || {
+            let mut world = World::with_env(&env);
+            assert!(v2_store(&env).load(&mut world));
+            assert_eq!(world.stale_component_count(), 0);
+            let hulls: StdVec<i32> = ships
+                .iter()
+                .map(|ship| world.get_typed_component::<ShipV2>(*ship).unwrap().hull)
+                .collect();
+            assert_eq!(hulls, [10, 20, 30]);
+            assert_eq!(v2_store(&env).migrate(&mut world), Ok(0));
+        });
+    }
+
+    #[test]
+    fn test_failed_migrations_change_nothing() {
+        let env = Env::default();
+        let (contract_id, ships) = save_v1(&env, &[10, 0]);
+        let ship_type = ShipV2::component_type();
+
+        env.as_contract(&contract_id, || {
+            // Without the migration, v1 data cannot be read as v2
+            let mut world = World::with_env(&env);
+            assert!(v2_store(&env).load(&mut world));
+            assert_eq!(world.get_typed_component::<ShipV2>(ships[0]), None);
+            assert_eq!(
+                world.migrate_components(),
+                Err(MigrationError::Missing {
+                    component_type: ship_type.clone(),
+                    version: 1
+                })
+            );
+
+            let mut world = v2_world(&env);
+            assert_eq!(
+                v2_store(&env).migrate(&mut world),
+                Err(MigrationError::Failed {
+                    entity_id: ships[1],
+                    component_type: ship_type.clone(),
+                    version: 1
+                })
+            );
+            assert_eq!(world.stale_component_count(), 2);
+        });
+
+        env.as_contract(&contract_id, || {
+            let mut world = v2_world(&env);
+            assert!(v2_store(&env).load(&mut world));
+            assert_eq!(world.stale_component_count(), 2);
+            assert_eq!(world.get_typed_component::<ShipV2>(ships[1]), None);
+        });
+    }
+}
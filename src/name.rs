@@ -1,299 +1,285 @@
-//! Provides the [`Name`] [`Component`], used for identifying an [`Entity`].
-
-use crate::{component::Component, entity::Entity, query::QueryData};
-
-use alloc::{
-    borrow::{Cow, ToOwned},
-    string::String,
-};
-use bevy_platform::hash::FixedHasher;
-use core::{
-    hash::{BuildHasher, Hash, Hasher},
-    ops::Deref,
-};
-
-#[cfg(feature = "serialize")]
-use {
-    alloc::string::ToString,
-    serde::{
-        de::{Error, Visitor},
-        Deserialize, Deserializer, Serialize, Serializer,
-    },
-};
-
-#[cfg(feature = "bevy_reflect")]
-use {
-    crate::reflect::ReflectComponent,
-    bevy_reflect::{std_traits::ReflectDefault, Reflect},
-};
-
-#[cfg(all(feature = "serialize", feature = "bevy_reflect"))]
-use bevy_reflect::{ReflectDeserialize, ReflectSerialize};
-
-/// Component used to identify an entity. Stores a hash for faster comparisons.
-///
-/// The hash is eagerly re-computed upon each update to the name.
-///
-/// [`Name`] should not be treated as a globally unique identifier for entities,
-/// as multiple entities can have the same name.  [`Entity`] should be
-/// used instead as the default unique identifier.
-#[derive(Component, Clone)]
-#[cfg_attr(
-    feature = "bevy_reflect",
-    derive(Reflect),
-    reflect(Component, Default, Debug, Clone, Hash, PartialEq)
-)]
-#[cfg_attr(
-    all(feature = "serialize", feature = "bevy_reflect"),
-    reflect(Deserialize, Serialize)
-)]
-pub struct Name {
-    hash: u64, // Won't be serialized
-    name: Cow<'static, str>,
-}
-
-impl Default for Name {
-    fn default() -> Self {
-        Name::new("")
-    }
-}
+//! Provides the [`Name`] component, used for identifying an entity, and the
+//! name index the [`World`] keeps so well-known entities can be looked up with
+//! [`World::entity_by_name`] instead of hardcoded ids.
+//!
+//! Names are not unique by default, as multiple entities can have the same
+//! name; [`World::set_unique_names`] makes the world reject duplicates.
+
+use crate::component::{Component, ComponentTrait};
+use crate::entity::EntityId;
+use crate::world::World;
+use soroban_sdk::xdr::{FromXdr, ToXdr};
+use soroban_sdk::{symbol_short, Bytes, Env, Map, Symbol, Vec};
+
+/// Component used to identify an entity
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Name(pub Symbol);
 
 impl Name {
-    /// Creates a new [`Name`] from any string-like type.
-    ///
-    /// The internal hash will be computed immediately.
-    pub fn new(name: impl Into<Cow<'static, str>>) -> Self {
-        let name = name.into();
-        let mut name = Name { name, hash: 0 };
-        name.update_hash();
-        name
-    }
-
-    /// Sets the entity's name.
-    ///
-    /// The internal hash will be re-computed.
-    #[inline(always)]
-    pub fn set(&mut self, name: impl Into<Cow<'static, str>>) {
-        *self = Name::new(name);
+    /// Create a new name
+    pub fn new(name: Symbol) -> Self {
+        Self(name)
     }
 
-    /// Updates the name of the entity in place.
-    ///
-    /// This will allocate a new string if the name was previously
-    /// created from a borrow.
-    #[inline(always)]
-    pub fn mutate<F: FnOnce(&mut String)>(&mut self, f: F) {
-        f(self.name.to_mut());
-        self.update_hash();
+    /// Get the name as a symbol
+    pub fn as_symbol(&self) -> &Symbol {
+        &self.0
     }
+}
 
-    /// Gets the name of the entity as a `&str`.
-    #[inline(always)]
-    pub fn as_str(&self) -> &str {
-        &self.name
+impl ComponentTrait for Name {
+    fn component_type() -> Symbol {
+        symbol_short!("name")
     }
-
-    fn update_hash(&mut self) {
-        self.hash = FixedHasher.hash_one(&self.name);
+    fn serialize(&self, env: &Env) -> Bytes {
+        self.0.clone().to_xdr(env)
     }
-}
-
-impl core::fmt::Display for Name {
-    #[inline(always)]
-    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
-        core::fmt::Display::fmt(&self.name, f)
+    fn deserialize(env: &Env, data: &Bytes) -> Option<Self> {
+        Symbol::from_xdr(env, data).ok().map(Self)
     }
 }
 
-impl core::fmt::Debug for Name {
-    #[inline(always)]
-    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
-        core::fmt::Debug::fmt(&self.name, f)
-    }
+/// Errors returned when naming an entity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameError {
+    /// The entity does not exist
+    EntityNotFound,
+    /// Unique names are enforced and another entity already has this name
+    NameTaken,
 }
 
-/// Convenient query for giving a human friendly name to an entity.
-///
-/// ```
-/// # use bevy_ecs::prelude::*;
-/// # #[derive(Component)] pub struct Score(f32);
-/// fn increment_score(mut scores: Query<(NameOrEntity, &mut Score)>) {
-///     for (name, mut score) in &mut scores {
-///         score.0 += 1.0;
-///         if score.0.is_nan() {
-///             log::error!("Score for {name} is invalid");
-///         }
-///     }
-/// }
-/// # bevy_ecs::system::assert_is_system(increment_score);
-/// ```
-///
-/// # Implementation
-///
-/// The `Display` impl for `NameOrEntity` returns the `Name` where there is one
-/// or {index}v{generation} for entities without one.
-#[derive(QueryData)]
-#[query_data(derive(Debug))]
-pub struct NameOrEntity {
-    /// A [`Name`] that the entity might have that is displayed if available.
-    pub name: Option<&'static Name>,
-    /// The unique identifier of the entity as a fallback.
-    pub entity: Entity,
+/// Index from names to the entities carrying them
+#[derive(Debug, Clone)]
+pub struct NameIndex {
+    entries: Map<Symbol, Vec<EntityId>>,
+    unique: bool,
 }
 
-impl<'w, 's> core::fmt::Display for NameOrEntityItem<'w, 's> {
-    #[inline(always)]
-    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
-        match self.name {
-            Some(name) => core::fmt::Display::fmt(name, f),
-            None => core::fmt::Display::fmt(&self.entity, f),
+impl NameIndex {
+    /// Create an empty index that allows duplicate names
+    pub fn new(env: &Env) -> Self {
+        Self {
+            entries: Map::new(env),
+            unique: false,
         }
     }
-}
-
-// Conversions from strings
 
-impl From<&str> for Name {
-    #[inline(always)]
-    fn from(name: &str) -> Self {
-        Name::new(name.to_owned())
+    /// Check whether the index rejects duplicate names
+    pub fn is_unique(&self) -> bool {
+        self.unique
     }
-}
 
-impl From<String> for Name {
-    #[inline(always)]
-    fn from(name: String) -> Self {
-        Name::new(name)
+    /// Get every entity named `name`, in the order they were named
+    pub fn get(&self, name: &Symbol) -> Vec<EntityId> {
+        self.entries
+            .get(name.clone())
+            .unwrap_or_else(|| Vec::new(self.entries.env()))
     }
-}
 
-// Conversions to strings
-
-impl AsRef<str> for Name {
-    #[inline(always)]
-    fn as_ref(&self) -> &str {
-        &self.name
+    /// Check whether `entity_id` may take `name`
+    pub fn can_insert(&self, name: &Symbol, entity_id: EntityId) -> bool {
+        !self.unique || self.get(name).iter().all(|named| named == entity_id)
     }
-}
 
-impl From<&Name> for String {
-    #[inline(always)]
-    fn from(val: &Name) -> String {
-        val.as_str().to_owned()
+    /// Record that `entity_id` is named `name`
+    pub fn insert(&mut self, name: Symbol, entity_id: EntityId) {
+        let mut entities = self.get(&name);
+        if !entities.contains(entity_id) {
+            entities.push_back(entity_id);
+            self.entries.set(name, entities);
+        }
     }
-}
 
-impl From<Name> for String {
-    #[inline(always)]
-    fn from(val: Name) -> String {
-        val.name.into_owned()
+    /// Forget that `entity_id` is named `name`
+    pub fn remove(&mut self, name: &Symbol, entity_id: EntityId) {
+        let mut entities = self.get(name);
+        if let Some(index) = entities.first_index_of(entity_id) {
+            entities.remove(index);
+            if entities.is_empty() {
+                self.entries.remove(name.clone());
+            } else {
+                self.entries.set(name.clone(), entities);
+            }
+        }
     }
 }
 
-impl Hash for Name {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.name.hash(state);
+impl World {
+    /// Reject names already used by another entity from now on
+    ///
+    /// Names given before uniqueness was enforced are left untouched.
+    pub fn set_unique_names(&mut self, unique: bool) {
+        self.names.unique = unique;
     }
-}
 
-impl PartialEq for Name {
-    fn eq(&self, other: &Self) -> bool {
-        if self.hash != other.hash {
-            // Makes the common case of two strings not been equal very fast
-            return false;
+    /// Give `entity_id` a [`Name`], replacing its previous one
+    pub fn set_name(&mut self, entity_id: EntityId, name: Symbol) -> Result<(), NameError> {
+        if !self.exists(entity_id) {
+            return Err(NameError::EntityNotFound);
         }
-
-        self.name.eq(&other.name)
+        if !self.names.can_insert(&name, entity_id) {
+            return Err(NameError::NameTaken);
+        }
+        self.add_typed_component(entity_id, &Name(name));
+        Ok(())
     }
-}
 
-impl Eq for Name {}
+    /// Get the name of `entity_id`, if it has one
+    pub fn name_of(&self, entity_id: EntityId) -> Option<Symbol> {
+        self.get_typed_component::<Name>(entity_id)
+            .map(|name| name.0)
+    }
 
-impl PartialOrd for Name {
-    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
-        Some(self.cmp(other))
+    /// Look up the first entity named `name`
+    pub fn entity_by_name(&self, name: &Symbol) -> Option<EntityId> {
+        self.names.get(name).first()
     }
-}
 
-impl Ord for Name {
-    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
-        self.name.cmp(&other.name)
+    /// Look up every entity named `name`
+    pub fn entities_by_name(&self, name: &Symbol) -> Vec<EntityId> {
+        self.names.get(name)
     }
-}
 
-impl Deref for Name {
-    type Target = str;
+    /// Update the name index before a [`Name`] component is stored
+    ///
+    /// Returns `false` if the name must not be stored, including on an entity
+    /// that does not exist.
+    pub(crate) fn index_name(&mut self, entity_id: EntityId, component: &Component) -> bool {
+        if !self.exists(entity_id) {
+            return false;
+        }
+        let name = match Name::deserialize(self.env(), component.data()) {
+            Some(name) => name.0,
+            None => return false,
+        };
+        if !self.names.can_insert(&name, entity_id) {
+            return false;
+        }
+        self.unindex_name(entity_id);
+        self.names.insert(name, entity_id);
+        true
+    }
 
-    fn deref(&self) -> &Self::Target {
-        self.name.as_ref()
+    /// Remove `entity_id` from the name index
+    pub(crate) fn unindex_name(&mut self, entity_id: EntityId) {
+        if let Some(name) = self.name_of(entity_id) {
+            self.names.remove(&name, entity_id);
+        }
     }
 }
 
-#[cfg(feature = "serialize")]
-impl Serialize for Name {
-    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
-        serializer.serialize_str(self.as_str())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use soroban_sdk::{symbol_short, Env};
+
+    #[test]
+    fn test_name_roundtrip() {
+        let env = Env::default();
+        let name = Name::new(Symbol::new(&env, "player_one_paddle"));
+        let data = name.serialize(&env);
+        assert_eq!(Name::deserialize(&env, &data), Some(name));
     }
-}
 
-#[cfg(feature = "serialize")]
-impl<'de> Deserialize<'de> for Name {
-    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
-        deserializer.deserialize_str(NameVisitor)
+    #[test]
+    fn test_entity_by_name() {
+        let mut world = World::new();
+        let paddle = world.spawn_empty().id();
+        let ball = world.spawn_empty().id();
+
+        world.set_name(paddle, symbol_short!("paddle1")).unwrap();
+        world.set_name(ball, symbol_short!("ball")).unwrap();
+
+        assert_eq!(
+            world.entity_by_name(&symbol_short!("paddle1")),
+            Some(paddle)
+        );
+        assert_eq!(world.entity_by_name(&symbol_short!("ball")), Some(ball));
+        assert_eq!(world.name_of(ball), Some(symbol_short!("ball")));
+        assert_eq!(world.entity_by_name(&symbol_short!("paddle2")), None);
     }
-}
 
-#[cfg(feature = "serialize")]
-struct NameVisitor;
+    #[test]
+    fn test_rename_and_despawn_update_index() {
+        let mut world = World::new();
+        let head = world.spawn_empty().id();
+        world.set_name(head, symbol_short!("head")).unwrap();
+        world.set_name(head, symbol_short!("snake")).unwrap();
 
-#[cfg(feature = "serialize")]
-impl<'de> Visitor<'de> for NameVisitor {
-    type Value = Name;
+        assert_eq!(world.entity_by_name(&symbol_short!("head")), None);
+        assert_eq!(world.entity_by_name(&symbol_short!("snake")), Some(head));
 
-    fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
-        formatter.write_str(core::any::type_name::<Name>())
-    }
+        world.remove_component_from_entity(head, &Name::component_type());
+        assert_eq!(world.entity_by_name(&symbol_short!("snake")), None);
 
-    fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
-        Ok(Name::new(v.to_string()))
+        world.set_name(head, symbol_short!("snake")).unwrap();
+        assert!(world.despawn(head));
+        assert_eq!(world.entity_by_name(&symbol_short!("snake")), None);
     }
 
-    fn visit_string<E: Error>(self, v: String) -> Result<Self::Value, E> {
-        Ok(Name::new(v))
+    #[test]
+    fn test_duplicate_names() {
+        let mut world = World::new();
+        let a = world.spawn_empty().id();
+        let b = world.spawn_empty().id();
+        world.set_name(a, symbol_short!("invader")).unwrap();
+        world.set_name(b, symbol_short!("invader")).unwrap();
+        assert_eq!(world.entities_by_name(&symbol_short!("invader")).len(), 2);
+        assert_eq!(world.entity_by_name(&symbol_short!("invader")), Some(a));
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::world::World;
-    use alloc::string::ToString;
 
     #[test]
-    fn test_display_of_debug_name() {
+    fn test_unique_names_enforced() {
         let mut world = World::new();
-        let e1 = world.spawn_empty().id();
-        let name = Name::new("MyName");
-        let e2 = world.spawn(name.clone()).id();
-        let mut query = world.query::<NameOrEntity>();
-        let d1 = query.get(&world, e1).unwrap();
-        // NameOrEntity Display for entities without a Name should be {index}v{generation}
-        assert_eq!(d1.to_string(), "0v0");
-        let d2 = query.get(&world, e2).unwrap();
-        // NameOrEntity Display for entities with a Name should be the Name
-        assert_eq!(d2.to_string(), "MyName");
+        world.set_unique_names(true);
+        let a = world.spawn_empty().id();
+        let b = world.spawn_empty().id();
+
+        world.set_name(a, symbol_short!("player1")).unwrap();
+        assert_eq!(
+            world.set_name(b, symbol_short!("player1")),
+            Err(NameError::NameTaken)
+        );
+        assert_eq!(world.set_name(a, symbol_short!("player1")), Ok(()));
+        assert_eq!(
+            world.set_name(EntityId::new(42, 0), symbol_short!("ghost")),
+            Err(NameError::EntityNotFound)
+        );
+
+        // Raw component inserts are held to the same rule
+        let env = world.env().clone();
+        world.add_component_to_entity(
+            b,
+            Component::new(
+                Name::component_type(),
+                Name::new(symbol_short!("player1")).serialize(&env),
+            ),
+        );
+        assert_eq!(world.name_of(b), None);
     }
-}
 
-#[cfg(all(test, feature = "serialize"))]
-mod serde_tests {
-    use super::Name;
+    #[test]
+    fn test_missing_entity_takes_no_name() {
+        let mut world = World::new();
+        world.set_unique_names(true);
+        world.add_typed_component(EntityId::new(99, 0), &Name::new(symbol_short!("boss")));
+        assert_eq!(world.entity_by_name(&symbol_short!("boss")), None);
 
-    use serde_test::{assert_tokens, Token};
+        let boss = world.spawn_empty().id();
+        assert_eq!(world.set_name(boss, symbol_short!("boss")), Ok(()));
+    }
 
     #[test]
-    fn test_serde_name() {
-        let name = Name::new("MyComponent");
-        assert_tokens(&name, &[Token::String("MyComponent")]);
+    fn test_unique_names_survive_clear() {
+        let mut world = World::new();
+        world.set_unique_names(true);
+        world.clear_entities();
+        let a = world.spawn_empty().id();
+        let b = world.spawn_empty().id();
+        world.set_name(a, symbol_short!("boss")).unwrap();
+        assert_eq!(
+            world.set_name(b, symbol_short!("boss")),
+            Err(NameError::NameTaken)
+        );
     }
 }
@@ -0,0 +1,431 @@
+//! Deterministic grid pathfinding.
+//!
+//! A [`Pathfinder`] searches a grid of integer cells, described by a callback,
+//! with breadth-first search or A*. Every search stops after a fixed number of
+//! node expansions so a contract call cannot run out of CPU budget looking for
+//! a far or unreachable goal. Ties are always broken the same way, so the same
+//! inputs give the same path on every node.
+//!
+//! The resulting [`Path`] is a component, so entities can walk it one step at
+//! a time across ticks.
+
+use crate::component::ComponentTrait;
+use crate::components::Position;
+use crate::tilemap::{Connectivity, TileMap};
+use alloc::collections::btree_map::Entry;
+use alloc::collections::{BTreeMap, BTreeSet, BinaryHeap, VecDeque};
+use alloc::vec::Vec as StdVec;
+use core::cmp::Reverse;
+use soroban_sdk::{symbol_short, Bytes, Env, Symbol};
+
+/// Errors returned when no path is found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathError {
+    /// Every cell reachable from the start was visited without finding the goal
+    Unreachable,
+    /// The expansion budget ran out before the goal was found
+    BudgetExhausted,
+}
+
+/// Route through a grid, with the cells it visits from start to goal
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path {
+    steps: StdVec<Position>,
+    cost: u32,
+    next: u32,
+}
+
+impl Path {
+    /// Create a path through `steps`, starting at the first one
+    pub fn new(steps: StdVec<Position>, cost: u32) -> Self {
+        Self {
+            steps,
+            cost,
+            next: 1,
+        }
+    }
+
+    /// Get the cells of the path, start and goal included
+    pub fn steps(&self) -> &[Position] {
+        &self.steps
+    }
+
+    /// Get the total cost of entering every cell after the start
+    pub fn cost(&self) -> u32 {
+        self.cost
+    }
+
+    /// Get the number of moves from start to goal
+    pub fn len(&self) -> u32 {
+        (self.steps.len() as u32).saturating_sub(1)
+    }
+
+    /// Check whether the path starts at its goal
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the cell to move to next, if the goal has not been reached
+    pub fn next_step(&self) -> Option<Position> {
+        self.steps.get(self.next as usize).copied()
+    }
+
+    /// Move on to the next cell and return it
+    pub fn advance(&mut self) -> Option<Position> {
+        let step = self.next_step()?;
+        self.next += 1;
+        Some(step)
+    }
+
+    /// Check whether every step of the path has been taken
+    pub fn is_complete(&self) -> bool {
+        self.next_step().is_none()
+    }
+}
+
+impl ComponentTrait for Path {
+    fn component_type() -> Symbol {
+        symbol_short!("path")
+    }
+    fn serialize(&self, env: &Env) -> Bytes {
+        let mut bytes = Bytes::new(env);
+        bytes.extend_from_array(&self.cost.to_be_bytes());
+        bytes.extend_from_array(&self.next.to_be_bytes());
+        for step in self.steps.iter() {
+            bytes.extend_from_array(&step.x.to_be_bytes());
+            bytes.extend_from_array(&step.y.to_be_bytes());
+        }
+        bytes
+    }
+    fn deserialize(_env: &Env, data: &Bytes) -> Option<Self> {
+        if data.len() < 8 || (data.len() - 8) % 8 != 0 {
+            return None;
+        }
+        let read = |offset: u32| {
+            let mut buf = [0u8; 4];
+            data.slice(offset..offset + 4).copy_into_slice(&mut buf);
+            buf
+        };
+        let steps = (8..data.len())
+            .step_by(8)
+            .map(|offset| {
+                Position::new(
+                    i32::from_be_bytes(read(offset)),
+                    i32::from_be_bytes(read(offset + 4)),
+                )
+            })
+            .collect();
+        Some(Self {
+            steps,
+            cost: u32::from_be_bytes(read(0)),
+            next: u32::from_be_bytes(read(4)),
+        })
+    }
+}
+
+type Cell = (i32, i32);
+
+/// Grid search with a bounded number of node expansions
+///
+/// Cells are described by a callback: `walkable(x, y)` for breadth-first
+/// search, or `cost(x, y)` for A*, giving the cost of entering a cell or
+/// `None` if it cannot be entered. Diagonal moves, when allowed, cost the same
+/// as straight ones and may not cut the corner of an impassable cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pathfinder {
+    connectivity: Connectivity,
+    max_expansions: u32,
+}
+
+impl Pathfinder {
+    /// Create a pathfinder moving in four directions that expands at most
+    /// `max_expansions` cells per search
+    pub fn new(max_expansions: u32) -> Self {
+        Self {
+            connectivity: Connectivity::Four,
+            max_expansions,
+        }
+    }
+
+    /// Also allow diagonal moves, or not
+    pub fn with_connectivity(mut self, connectivity: Connectivity) -> Self {
+        self.connectivity = connectivity;
+        self
+    }
+
+    pub fn connectivity(&self) -> Connectivity {
+        self.connectivity
+    }
+
+    pub fn max_expansions(&self) -> u32 {
+        self.max_expansions
+    }
+
+    /// Cells reachable in one move from `cell`, with the cost of entering them
+    fn successors(
+        &self,
+        (x, y): Cell,
+        cost: &impl Fn(i32, i32) -> Option<u32>,
+    ) -> StdVec<(Cell, u32)> {
+        let mut successors = StdVec::new();
+        for &(dx, dy) in self.connectivity.offsets() {
+            let (Some(next_x), Some(next_y)) = (x.checked_add(dx), y.checked_add(dy)) else {
+                continue;
+            };
+            let Some(step_cost) = cost(next_x, next_y) else {
+                continue;
+            };
+            if dx != 0 && dy != 0 && (cost(next_x, y).is_none() || cost(x, next_y).is_none()) {
+                continue;
+            }
+            successors.push(((next_x, next_y), step_cost));
+        }
+        successors
+    }
+
+    /// Find a path with the fewest moves from `start` to `goal`
+    pub fn bfs(
+        &self,
+        start: Position,
+        goal: Position,
+        walkable: impl Fn(i32, i32) -> bool,
+    ) -> Result<Path, PathError> {
+        let cost = |x, y| walkable(x, y).then_some(1);
+        let (start, goal) = ((start.x, start.y), (goal.x, goal.y));
+        let mut came_from = BTreeMap::new();
+        came_from.insert(start, start);
+        let mut frontier = VecDeque::from([start]);
+        let mut expansions = 0;
+
+        while let Some(cell) = frontier.pop_front() {
+            if cell == goal {
+                let steps = trace(&came_from, start, goal);
+                let cost = steps.len() as u32 - 1;
+                return Ok(Path::new(steps, cost));
+            }
+            if expansions == self.max_expansions {
+                return Err(PathError::BudgetExhausted);
+            }
+            expansions += 1;
+            for (next, _) in self.successors(cell, &cost) {
+                if let Entry::Vacant(entry) = came_from.entry(next) {
+                    entry.insert(cell);
+                    frontier.push_back(next);
+                }
+            }
+        }
+        Err(PathError::Unreachable)
+    }
+
+    /// Find the cheapest path from `start` to `goal` with A*
+    ///
+    /// Costs below 1 are treated as 1, which keeps the distance heuristic from
+    /// overestimating.
+    pub fn astar(
+        &self,
+        start: Position,
+        goal: Position,
+        cost: impl Fn(i32, i32) -> Option<u32>,
+    ) -> Result<Path, PathError> {
+        let cost = |x, y| cost(x, y).map(|step_cost: u32| step_cost.max(1));
+        let (start, goal) = ((start.x, start.y), (goal.x, goal.y));
+        let heuristic = |(x, y): Cell| {
+            let (dx, dy) = (x.abs_diff(goal.0), y.abs_diff(goal.1));
+            match self.connectivity {
+                Connectivity::Four => dx.saturating_add(dy),
+                Connectivity::Eight => dx.max(dy),
+            }
+        };
+
+        let mut came_from = BTreeMap::new();
+        let mut expanded = BTreeSet::new();
+        let mut best_cost = BTreeMap::new();
+        best_cost.insert(start, 0u32);
+        // Lowest estimate first, then closest to the goal, then first found
+        let mut open = BinaryHeap::new();
+        let mut pushed = 0u64;
+        open.push(Reverse((heuristic(start), heuristic(start), pushed, start)));
+        let mut expansions = 0;
+
+        while let Some(Reverse((_, _, _, cell))) = open.pop() {
+            if !expanded.insert(cell) {
+                // Already reached more cheaply through another cell
+                continue;
+            }
+            let cell_cost = best_cost[&cell];
+            if cell == goal {
+                return Ok(Path::new(trace(&came_from, start, goal), cell_cost));
+            }
+            if expansions == self.max_expansions {
+                return Err(PathError::BudgetExhausted);
+            }
+            expansions += 1;
+            for (next, step_cost) in self.successors(cell, &cost) {
+                let next_cost = cell_cost.saturating_add(step_cost);
+                if best_cost
+                    .get(&next)
+                    .is_some_and(|known| *known <= next_cost)
+                {
+                    continue;
+                }
+                best_cost.insert(next, next_cost);
+                came_from.insert(next, cell);
+                pushed += 1;
+                let remaining = heuristic(next);
+                open.push(Reverse((
+                    next_cost.saturating_add(remaining),
+                    remaining,
+                    pushed,
+                    next,
+                )));
+            }
+        }
+        Err(PathError::Unreachable)
+    }
+}
+
+/// Walk `came_from` back from `goal` to `start`
+fn trace(came_from: &BTreeMap<Cell, Cell>, start: Cell, goal: Cell) -> StdVec<Position> {
+    let mut steps = alloc::vec![Position::new(goal.0, goal.1)];
+    let mut cell = goal;
+    while cell != start {
+        cell = came_from[&cell];
+        steps.push(Position::new(cell.0, cell.1));
+    }
+    steps.reverse();
+    steps
+}
+
+impl TileMap {
+    /// Cost of entering the tile at (`x`, `y`) for a [`Pathfinder`]: 1, or
+    /// `None` if the tile is blocked or off the map
+    pub fn step_cost(&self, x: i32, y: i32) -> Option<u32> {
+        (!self.is_blocked(x, y)).then_some(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tilemap::TileProperties;
+    use crate::world::World;
+
+    const WALL: u8 = 1;
+
+    /// 7x5 room with a wall down the middle, open only at the bottom
+    fn room() -> TileMap {
+        let mut map = TileMap::new(7, 5, WALL);
+        map.set_properties(WALL, TileProperties::BLOCKED);
+        map.fill(3, 0, 3, 3, WALL);
+        map
+    }
+
+    #[test]
+    fn test_bfs_goes_around_walls() {
+        let map = room();
+        let path = Pathfinder::new(100)
+            .bfs(Position::new(1, 1), Position::new(5, 1), |x, y| {
+                !map.is_blocked(x, y)
+            })
+            .unwrap();
+        // Down to the gap in row 4, across, and back up
+        assert_eq!(path.len(), 10);
+        assert_eq!(path.cost(), 10);
+        assert_eq!(path.steps()[0], Position::new(1, 1));
+        assert_eq!(path.steps()[10], Position::new(5, 1));
+        assert!(path.steps().contains(&Position::new(3, 4)));
+    }
+
+    #[test]
+    fn test_astar_prefers_cheap_cells() {
+        // Open field where the straight row is swamp, five times as costly
+        let cost = |x: i32, y: i32| match (x, y) {
+            (0..=6, 0..=2) if y == 1 && (1..=5).contains(&x) => Some(5),
+            (0..=6, 0..=2) => Some(1),
+            _ => None,
+        };
+        let path = Pathfinder::new(100)
+            .astar(Position::new(0, 1), Position::new(6, 1), cost)
+            .unwrap();
+        assert_eq!(path.cost(), 8);
+        assert!(path
+            .steps()
+            .iter()
+            .all(|step| step.y != 1 || step.x == 0 || step.x == 6));
+
+        let map = room();
+        let diagonal = Pathfinder::new(100)
+            .with_connectivity(Connectivity::Eight)
+            .astar(Position::new(1, 1), Position::new(5, 1), |x, y| {
+                map.step_cost(x, y)
+            })
+            .unwrap();
+        // Diagonals save two moves, but the gap must be crossed straight from
+        // (2, 4) to (4, 4) since the corners of the wall cannot be cut
+        assert_eq!(diagonal.cost(), 8);
+        assert!(diagonal.steps().contains(&Position::new(2, 4)));
+        assert!(diagonal.steps().contains(&Position::new(4, 4)));
+    }
+
+    #[test]
+    fn test_search_is_deterministic() {
+        let open = |x: i32, y: i32| (0..8).contains(&x) && (0..8).contains(&y);
+        let finder = Pathfinder::new(1000).with_connectivity(Connectivity::Eight);
+        let first = finder.bfs(Position::new(0, 0), Position::new(7, 5), open);
+        let second = finder.bfs(Position::new(0, 0), Position::new(7, 5), open);
+        assert_eq!(first, second);
+        let cost = |x, y| open(x, y).then_some(1);
+        assert_eq!(
+            finder.astar(Position::new(0, 0), Position::new(7, 5), cost),
+            finder.astar(Position::new(0, 0), Position::new(7, 5), cost)
+        );
+    }
+
+    #[test]
+    fn test_unreachable_and_budget() {
+        let mut map = room();
+        map.set(3, 4, WALL);
+        let finder = Pathfinder::new(1000);
+        assert_eq!(
+            finder.astar(Position::new(1, 1), Position::new(5, 1), |x, y| {
+                map.step_cost(x, y)
+            }),
+            Err(PathError::Unreachable)
+        );
+
+        // An unbounded grid would be searched forever without a budget
+        let anywhere = |_x: i32, _y: i32| Some(1);
+        assert_eq!(
+            Pathfinder::new(50).astar(Position::new(0, 0), Position::new(0, 100), anywhere),
+            Err(PathError::BudgetExhausted)
+        );
+        assert_eq!(
+            Pathfinder::new(50).bfs(Position::new(0, 0), Position::new(0, 0), |_, _| false),
+            Ok(Path::new(alloc::vec![Position::new(0, 0)], 0))
+        );
+    }
+
+    #[test]
+    fn test_path_component() {
+        let env = Env::default();
+        let mut world = World::with_env(&env);
+        let map = room();
+        let npc = world.spawn_empty().id();
+        let path = Pathfinder::new(100)
+            .astar(Position::new(0, 0), Position::new(2, 0), |x, y| {
+                map.step_cost(x, y)
+            })
+            .unwrap();
+        world.add_typed_component(npc, &path);
+
+        let mut stored = world.get_typed_component::<Path>(npc).unwrap();
+        assert_eq!(stored, path);
+        assert_eq!(stored.advance(), Some(Position::new(1, 0)));
+        world.add_typed_component(npc, &stored);
+
+        let mut stored = world.get_typed_component::<Path>(npc).unwrap();
+        assert_eq!(stored.advance(), Some(Position::new(2, 0)));
+        assert!(stored.is_complete());
+        assert_eq!(stored.advance(), None);
+    }
+}
@@ -0,0 +1,837 @@
+//! Saving worlds to contract storage and keeping them from being archived.
+//!
+//! A [`WorldStore`] saves a [`World`] to the current contract's persistent
+//! storage as a header entry, holding the entity list and the resources, and
+//! one entry per entity with its components. Persistent entries are archived
+//! once their time to live runs out, so the store tracks the ledger each of its
+//! entries is known to live until and extends them according to its
+//! [`TtlPolicy`].
+//!
+//! Components go to the [`StorageTier`] their type declares instead, as
+//! recorded by the world when they are added or by
+//! [`WorldStore::with_component`]: instance storage, kept
+//! alive along with the contract, or temporary storage, extended on every
+//! save and deleted once the world stops being saved. Entities whose temporary
+//! entry has expired load without those components.
+//!
+//! A store of a [`GameSession`], from [`GameSession::store`], keys all its
+//! entries by the session id, so worlds of the same name in different
+//! sessions never meet.
+//!
+//! Loading a world reads every one of its entries, so one archived entry
+//! blocks the whole world until it is restored. Data that may go idle on its
+//! own, such as the state of each player, belongs in a store of its own, for
+//! instance in the player's [`GameSession`], so it is archived without
+//! holding back the worlds others share.
+//!
+//! The store also records the schema version each declared component type
+//! is saved at, so a world saved by older code loads with its outdated
+//! components marked for [migration](crate::migration).
+//!
+//! Contracts cannot read the TTL of an entry, so the tracked ledgers are lower
+//! bounds: entries the store has not extended yet count as about to expire.
+
+use crate::component::{Component, ComponentTrait, StorageTier};
+use crate::entity::{EntityId, EntityManager};
+use crate::migration::MigrationError;
+use crate::resource::Resource;
+use crate::session::GameSession;
+use crate::world::World;
+use alloc::collections::BTreeSet;
+use soroban_sdk::{symbol_short, Env, IntoVal, Map, Symbol, Val, Vec};
+
+/// Default TTL below which entries are extended, about a day of ledgers
+pub const DEFAULT_TTL_THRESHOLD: u32 = 17_280;
+
+/// Default TTL entries are extended to, about thirty days of ledgers
+pub const DEFAULT_TTL_EXTEND_TO: u32 = 518_400;
+
+/// Default TTL of temporary entries, about an hour of ledgers
+pub const DEFAULT_TEMPORARY_TTL: u32 = 720;
+
+/// When a [`WorldStore`] extends the time to live of its entries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TtlPolicy {
+    /// Extend every entry the store writes, on every save
+    OnWrite,
+    /// Extend entries whose TTL has dropped below the threshold, on every
+    /// save and load
+    #[default]
+    BelowThreshold,
+}
+
+/// Entity list, resources, header TTL, instance TTL, entity TTLs, component
+/// type versions and the versions of stale components
+type Header = (
+    EntityManager,
+    Vec<Resource>,
+    u32,
+    u32,
+    Map<EntityId, u32>,
+    Map<Symbol, u32>,
+    Map<(EntityId, Symbol), u32>,
+);
+
+/// A world saved under a name in persistent storage, with the TTL of each of
+/// its entries
+#[derive(Debug, Clone)]
+pub struct WorldStore {
+    name: Symbol,
+    /// Session whose id prefixes every key, if any
+    session: Option<GameSession>,
+    policy: TtlPolicy,
+    threshold: u32,
+    extend_to: u32,
+    temporary_ttl: u32,
+    /// Storage tier of each component type not kept in persistent storage
+    tiers: Map<Symbol, StorageTier>,
+    /// Schema version of each component type newer than the first
+    versions: Map<Symbol, u32>,
+    /// Ledger the header is known to live until
+    header_live_until: u32,
+    /// Ledger the contract instance is known to live until
+    instance_live_until: u32,
+    /// Ledger the entry of each saved entity is known to live until
+    live_until: Map<EntityId, u32>,
+}
+
+impl WorldStore {
+    /// Create a store for the world saved as `name`, with the default policy
+    /// and TTLs
+    pub fn new(env: &Env, name: Symbol) -> Self {
+        Self {
+            name,
+            session: None,
+            policy: TtlPolicy::default(),
+            threshold: DEFAULT_TTL_THRESHOLD,
+            extend_to: DEFAULT_TTL_EXTEND_TO,
+            temporary_ttl: DEFAULT_TEMPORARY_TTL,
+            tiers: Map::new(env),
+            versions: Map::new(env),
+            header_live_until: 0,
+            instance_live_until: 0,
+            live_until: Map::new(env),
+        }
+    }
+
+    /// Save the world within `session`, apart from worlds of the same name in
+    /// other sessions
+    pub(crate) fn in_session(mut self, session: GameSession) -> Self {
+        self.session = Some(session);
+        self
+    }
+
+    /// Set when entries are extended
+    pub fn with_policy(mut self, policy: TtlPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Set the TTL below which entries are extended and the TTL they are
+    /// extended to, in ledgers
+    ///
+    /// `extend_to` is capped at the network's maximum TTL.
+    pub fn with_ttl(mut self, threshold: u32, extend_to: u32) -> Self {
+        self.threshold = threshold;
+        self.extend_to = extend_to.max(threshold);
+        self
+    }
+
+    /// Set the TTL temporary entries are extended to on every save, in ledgers
+    pub fn with_temporary_ttl(mut self, ledgers: u32) -> Self {
+        self.temporary_ttl = ledgers;
+        self
+    }
+
+    /// Save components of type `T` to the storage tier `T` declares, at the
+    /// schema version `T` declares
+    ///
+    /// Types the world has seen added with
+    /// [`World::add_typed_component`] are already saved that way; this covers
+    /// types the world only holds untyped.
+    pub fn with_component<T: ComponentTrait>(mut self) -> Self {
+        self.tiers.set(T::component_type(), T::storage_tier());
+        if T::schema_version() > 1 {
+            self.versions.set(T::component_type(), T::schema_version());
+        }
+        self
+    }
+
+    /// Get the storage tier components of `component_type` are saved to
+    /// when the world declares none
+    pub fn tier_of(&self, component_type: &Symbol) -> StorageTier {
+        self.tiers
+            .get(component_type.clone())
+            .unwrap_or(StorageTier::Persistent)
+    }
+
+    /// Get the schema version components of `component_type` are saved at
+    /// when the world declares none
+    pub fn version_of(&self, component_type: &Symbol) -> u32 {
+        self.versions.get(component_type.clone()).unwrap_or(1)
+    }
+
+    /// Check whether any component type is saved at a schema version above 1,
+    /// by this store or by `world`
+    pub fn declares_versions(&self, world: &World) -> bool {
+        !self.versions.is_empty() || !world.component_versions.is_empty()
+    }
+
+    /// Get the storage tier components of `component_type` in `world` are
+    /// saved to
+    fn world_tier_of(&self, world: &World, component_type: &Symbol) -> StorageTier {
+        self.tiers
+            .get(component_type.clone())
+            .or_else(|| world.component_tiers.get(component_type.clone()))
+            .unwrap_or(StorageTier::Persistent)
+    }
+
+    /// Get the schema version components of `component_type` in `world` are
+    /// saved at
+    fn world_version_of(&self, world: &World, component_type: &Symbol) -> u32 {
+        let declared = world
+            .component_versions
+            .get(component_type.clone())
+            .unwrap_or(1);
+        self.version_of(component_type).max(declared)
+    }
+
+    /// Get the schema version of every component type saved above version 1
+    fn saved_versions(&self, world: &World) -> Map<Symbol, u32> {
+        let mut versions = world.component_versions.clone();
+        for (component_type, version) in self.versions.iter() {
+            let declared = versions.get(component_type.clone()).unwrap_or(1);
+            versions.set(component_type, version.max(declared));
+        }
+        versions
+    }
+
+    /// Get the session the world is saved in, if any
+    pub fn session(&self) -> Option<GameSession> {
+        self.session
+    }
+
+    /// Get the name the world is saved as
+    pub fn name(&self) -> &Symbol {
+        &self.name
+    }
+
+    /// Get when entries are extended
+    pub fn policy(&self) -> TtlPolicy {
+        self.policy
+    }
+
+    /// Get the ledger the header entry is known to live until
+    pub fn header_live_until(&self) -> u32 {
+        self.header_live_until
+    }
+
+    /// Get the ledger the contract instance is known to live until, or 0 if
+    /// no component is saved to instance storage
+    pub fn instance_live_until(&self) -> u32 {
+        self.instance_live_until
+    }
+
+    /// Get the ledger the entry of `entity_id` is known to live until, if the
+    /// entity is saved
+    pub fn live_until(&self, entity_id: EntityId) -> Option<u32> {
+        self.live_until.get(entity_id)
+    }
+
+    /// Check whether a world is saved as `name`, outside of any session
+    pub fn exists(env: &Env, name: &Symbol) -> bool {
+        Self::new(env, name.clone()).is_saved()
+    }
+
+    /// Check whether the world is saved
+    pub fn is_saved(&self) -> bool {
+        self.env().storage().persistent().has(&self.header_key())
+    }
+
+    fn env(&self) -> &Env {
+        self.tiers.env()
+    }
+
+    /// Get the key `prefix` and the world's name make, prefixed by the
+    /// session id if the store is in a session
+    pub(crate) fn scoped_key(&self, prefix: Symbol) -> Val {
+        match self.session {
+            Some(session) => (session.id(), prefix, self.name.clone()).into_val(self.env()),
+            None => (prefix, self.name.clone()).into_val(self.env()),
+        }
+    }
+
+    fn header_key(&self) -> Val {
+        self.scoped_key(symbol_short!("world"))
+    }
+
+    fn entity_key(&self, entity_id: EntityId) -> Val {
+        let name = self.name.clone();
+        match self.session {
+            Some(session) => {
+                (session.id(), symbol_short!("wentity"), name, entity_id).into_val(self.env())
+            }
+            None => (symbol_short!("wentity"), name, entity_id).into_val(self.env()),
+        }
+    }
+
+    /// Save `world`, replacing what was saved before under the same name
+    ///
+    /// Every entity is written and the entries of despawned entities are
+    /// removed; entries are then extended according to the policy, and
+    /// temporary entries to the temporary TTL.
+    pub fn save(&mut self, world: &World) {
+        let env = world.env();
+        let storage = env.storage();
+        let mut saved = BTreeSet::new();
+        let mut uses_instance = false;
+        for entity in world.iter_entities() {
+            let entity_id = entity.id();
+            let key = self.entity_key(entity_id);
+            let [instance, persistent, temporary] = self.split_by_tier(world, entity_id);
+            storage.persistent().set(&key, &persistent);
+            if instance.is_empty() {
+                storage.instance().remove(&key);
+            } else {
+                storage.instance().set(&key, &instance);
+                uses_instance = true;
+            }
+            if temporary.is_empty() {
+                storage.temporary().remove(&key);
+            } else {
+                let ttl = self.temporary_ttl.min(storage.max_ttl());
+                storage.temporary().set(&key, &temporary);
+                storage.temporary().extend_ttl(&key, ttl, ttl);
+            }
+            if !self.live_until.contains_key(entity_id) {
+                self.live_until.set(entity_id, 0);
+            }
+            saved.insert(entity_id);
+        }
+        for entity_id in self.live_until.keys().iter() {
+            if !saved.contains(&entity_id) {
+                let key = self.entity_key(entity_id);
+                storage.persistent().remove(&key);
+                storage.instance().remove(&key);
+                storage.temporary().remove(&key);
+                self.live_until.remove(entity_id);
+            }
+        }
+
+        self.extend_entities(world, true);
+        if uses_instance {
+            self.extend_instance(env, true);
+        }
+        let extend_header = self.is_due(env, self.header_live_until, true);
+        self.write_header(world, extend_header);
+    }
+
+    /// Split the components of `entity_id` into instance, persistent and
+    /// temporary ones
+    fn split_by_tier(&self, world: &World, entity_id: EntityId) -> [Vec<Component>; 3] {
+        let env = world.env();
+        let mut tiers = [Vec::new(env), Vec::new(env), Vec::new(env)];
+        for component in world.storage.get_entity_components(entity_id).iter() {
+            let tier = self.world_tier_of(world, component.component_type());
+            tiers[tier as usize].push_back(component);
+        }
+        tiers
+    }
+
+    /// Load the saved world into `world`, replacing its entities and resources
+    ///
+    /// The world keeps its configuration, such as registered relationships and
+    /// the spatial hash, and its indexes are rebuilt from the loaded
+    /// components. Entries are extended according to the policy, which
+    /// rewrites the header to record their new TTLs. Returns `false`, leaving
+    /// `world` untouched, if no world is saved under the store's name.
+    pub fn load(&mut self, world: &mut World) -> bool {
+        let env = world.env().clone();
+        let storage = env.storage().persistent();
+        let Some((
+            mut entities,
+            resources,
+            header_live_until,
+            instance_live_until,
+            live_until,
+            saved_versions,
+            stale,
+        )) = storage.get::<_, Header>(&self.header_key())
+        else {
+            return false;
+        };
+        self.header_live_until = header_live_until;
+        self.instance_live_until = instance_live_until;
+        self.live_until = live_until;
+
+        world.clear_entities();
+        world.resources = resources;
+        let entity_ids: alloc::vec::Vec<EntityId> =
+            entities.iter_entities().map(|entity| entity.id()).collect();
+        // Component types are added back with the components, so the types of
+        // expired temporary components are dropped
+        for entity_id in entity_ids.iter() {
+            if let Some(mut entity) = entities.get_entity(*entity_id) {
+                for component_type in entity.component_types().clone().iter() {
+                    entity.remove_component_type(&component_type);
+                }
+                entities.set_entity(entity);
+            }
+        }
+        world.entities = entities;
+        // Components are added back one by one so the world's indexes see them
+        let mut uses_instance = false;
+        for entity_id in entity_ids {
+            let key = self.entity_key(entity_id);
+            let instance: Option<Vec<Component>> = env.storage().instance().get(&key);
+            uses_instance |= instance.is_some();
+            let tiers = [
+                (StorageTier::Persistent, storage.get(&key)),
+                (StorageTier::Instance, instance),
+                (StorageTier::Temporary, env.storage().temporary().get(&key)),
+            ];
+            for (tier, components) in tiers {
+                let Some(components) = components else {
+                    continue;
+                };
+                for component in components.iter() {
+                    let component_type: Symbol = component.component_type().clone();
+                    world.add_component_to_entity(entity_id, component);
+                    // The world keeps saving the component where it was found
+                    if tier != StorageTier::Persistent
+                        && world.component_tiers.get(component_type.clone()) != Some(tier)
+                    {
+                        world.component_tiers.set(component_type.clone(), tier);
+                    }
+                    if !self.declares_versions(world) {
+                        continue;
+                    }
+                    // Components saved by older code are left as they were
+                    // and upgraded when read or migrated
+                    let version = stale
+                        .get((entity_id, component_type.clone()))
+                        .or_else(|| saved_versions.get(component_type.clone()))
+                        .unwrap_or(1);
+                    if version < self.world_version_of(world, &component_type) {
+                        world.mark_stale(entity_id, component_type, version);
+                    }
+                }
+            }
+        }
+
+        let mut extended = self.extend_entities(world, false);
+        if uses_instance {
+            extended |= self.extend_instance(&env, false);
+        }
+        let extend_header = self.is_due(&env, self.header_live_until, false);
+        if extended || extend_header {
+            self.write_header(world, extend_header);
+        }
+        true
+    }
+
+    /// Load the saved world into `world`, upgrade all its stale components
+    /// with the world's migrations and save it back, returning how many
+    /// components were upgraded
+    ///
+    /// Nothing is saved if no component was stale or one could not be
+    /// upgraded. Returns `Ok(0)` if no world is saved under the store's name.
+    pub fn migrate(&mut self, world: &mut World) -> Result<u32, MigrationError> {
+        if !self.load(world) {
+            return Ok(0);
+        }
+        let upgraded = world.migrate_components()?;
+        if upgraded > 0 {
+            self.save(world);
+        }
+        Ok(upgraded)
+    }
+
+    /// Remove the saved world and every entry of its entities, returning
+    /// whether a world was saved
+    pub fn remove(&mut self) -> bool {
+        let storage = self.env().storage();
+        let header_key = self.header_key();
+        let Some(header) = storage.persistent().get::<_, Header>(&header_key) else {
+            return false;
+        };
+        for entity_id in header.4.keys().iter() {
+            let key = self.entity_key(entity_id);
+            storage.persistent().remove(&key);
+            storage.instance().remove(&key);
+            storage.temporary().remove(&key);
+        }
+        storage.persistent().remove(&header_key);
+        self.header_live_until = 0;
+        self.live_until = Map::new(self.env());
+        true
+    }
+
+    /// Check whether an entry living until `live_until` should be extended
+    fn is_due(&self, env: &Env, live_until: u32, written: bool) -> bool {
+        match self.policy {
+            TtlPolicy::OnWrite => written,
+            TtlPolicy::BelowThreshold => {
+                live_until.saturating_sub(env.ledger().sequence()) < self.threshold
+            }
+        }
+    }
+
+    /// Get the TTL entries are extended to, capped at the network maximum
+    fn extend_to(&self, env: &Env) -> u32 {
+        self.extend_to.min(env.storage().max_ttl())
+    }
+
+    /// Extend the entity entries that are due, returning whether any was
+    fn extend_entities(&mut self, world: &World, written: bool) -> bool {
+        let env = world.env();
+        let storage = env.storage().persistent();
+        let extend_to = self.extend_to(env);
+        let live_until = env.ledger().sequence().saturating_add(extend_to);
+        let mut extended = false;
+        for (entity_id, known) in self.live_until.iter() {
+            if !self.is_due(env, known, written) {
+                continue;
+            }
+            // Extending to `extend_to` whenever below it leaves the entry
+            // living until at least `live_until`, even if someone else
+            // extended it meanwhile
+            storage.extend_ttl(&self.entity_key(entity_id), extend_to, extend_to);
+            self.live_until.set(entity_id, live_until);
+            extended = true;
+        }
+        extended
+    }
+
+    /// Extend the contract instance if it is due, returning whether it was
+    fn extend_instance(&mut self, env: &Env, written: bool) -> bool {
+        if !self.is_due(env, self.instance_live_until, written) {
+            return false;
+        }
+        let extend_to = self.extend_to(env);
+        env.storage().instance().extend_ttl(extend_to, extend_to);
+        self.instance_live_until = env.ledger().sequence().saturating_add(extend_to);
+        true
+    }
+
+    fn write_header(&mut self, world: &World, extend: bool) {
+        let env = world.env();
+        let storage = env.storage().persistent();
+        let extend_to = self.extend_to(env);
+        if extend {
+            self.header_live_until = env.ledger().sequence().saturating_add(extend_to);
+        }
+        let key = self.header_key();
+        let header: Header = (
+            world.entities.clone(),
+            world.resources.clone(),
+            self.header_live_until,
+            self.instance_live_until,
+            self.live_until.clone(),
+            self.saved_versions(world),
+            world.stale.clone(),
+        );
+        storage.set(&key, &header);
+        if extend {
+            storage.extend_ttl(&key, extend_to, extend_to);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::Position;
+    use crate::hierarchy::ChildOf;
+    use crate::name::Name;
+    use crate::resource::GameState;
+    use soroban_sdk::testutils::storage::Persistent as _;
+    use soroban_sdk::testutils::Ledger;
+    use soroban_sdk::{contract, Address, Env};
+
+    #[contract]
+    struct PersistenceTestContract;
+
+    fn setup() -> (Env, Address) {
+        let env = Env::default();
+        env.ledger().set_sequence_number(1_000);
+        let contract_id = env.register(PersistenceTestContract, ());
+        (env, contract_id)
+    }
+
+    fn entry_ttl(env: &Env, contract_id: &Address, store: &WorldStore, entity_id: EntityId) -> u32 {
+        env.as_contract(contract_id, || {
+            env.storage()
+                .persistent()
+                .get_ttl(&store.entity_key(entity_id))
+        })
+    }
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let (env, contract_id) = setup();
+        let name = symbol_short!("dungeon");
+        let (hero, sword, slime) = env.as_contract(&contract_id, || {
+            let mut world = World::with_env(&env);
+            let hero = world.spawn_empty().id();
+            world.add_typed_component(hero, &Position::new(3, 4));
+            world.add_typed_component(hero, &Name(symbol_short!("hero")));
+            let sword = world.spawn_empty().id();
+            world.add_typed_component(sword, &ChildOf(hero));
+            let slime = world.spawn_empty().id();
+            world.add_typed_component(slime, &Position::new(9, 9));
+            let mut state = GameState::new();
+            state.increment_score(40);
+            world.set_typed_resource(&state);
+
+            let mut store = WorldStore::new(&env, name.clone());
+            store.save(&world);
+            world.despawn(slime);
+            store.save(&world);
+            (hero, sword, slime)
+        });
+
+        env.as_contract(&contract_id, || {
+            assert!(WorldStore::exists(&env, &name));
+            let mut store = WorldStore::new(&env, name.clone());
+            let mut world = World::with_env(&env);
+            assert!(store.load(&mut world));
+            assert_eq!(world.entity_count(), 2);
+            assert!(!world.exists(slime));
+            assert_eq!(store.live_until(slime), None);
+            assert!(!env.storage().persistent().has(&store.entity_key(slime)));
+            assert_eq!(
+                world.get_typed_component::<Position>(hero),
+                Some(Position::new(3, 4))
+            );
+            assert_eq!(world.entity_by_name(&symbol_short!("hero")), Some(hero));
+            assert_eq!(world.parent(sword), Some(hero));
+            assert_eq!(world.get_typed_resource::<GameState>().unwrap().score, 40);
+            // New entities do not reuse saved ids
+            let next = world.spawn_empty().id();
+            assert!(next != hero && next != sword);
+
+            let mut missing = WorldStore::new(&env, symbol_short!("nowhere"));
+            assert!(!missing.load(&mut world));
+            assert_eq!(world.entity_count(), 3);
+        });
+    }
+
+    #[test]
+    fn test_on_write_extends_every_save() {
+        let (env, contract_id) = setup();
+        let mut world = World::with_env(&env);
+        let paddle = world.spawn_empty().id();
+        let mut store = WorldStore::new(&env, symbol_short!("pong"))
+            .with_policy(TtlPolicy::OnWrite)
+            .with_ttl(100, 5_000);
+
+        env.as_contract(&contract_id, || store.save(&world));
+        assert_eq!(entry_ttl(&env, &contract_id, &store, paddle), 5_000);
+        assert_eq!(store.live_until(paddle), Some(6_000));
+        assert_eq!(store.header_live_until(), 6_000);
+
+        env.ledger().set_sequence_number(2_000);
+        env.as_contract(&contract_id, || store.save(&world));
+        assert_eq!(entry_ttl(&env, &contract_id, &store, paddle), 5_000);
+        assert_eq!(store.live_until(paddle), Some(7_000));
+
+        // Loading writes nothing, so nothing is extended
+        env.ledger().set_sequence_number(3_000);
+        env.as_contract(&contract_id, || assert!(store.load(&mut world)));
+        assert_eq!(entry_ttl(&env, &contract_id, &store, paddle), 4_000);
+    }
+
+    #[test]
+    fn test_below_threshold_keeps_idle_worlds_alive() {
+        let (env, contract_id) = setup();
+        let mut world = World::with_env(&env);
+        let board = world.spawn_empty().id();
+        world.add_typed_component(board, &Position::new(1, 1));
+        let mut store = WorldStore::new(&env, symbol_short!("chess")).with_ttl(1_000, 5_000);
+        env.as_contract(&contract_id, || store.save(&world));
+        assert_eq!(store.live_until(board), Some(6_000));
+
+        // Well above the threshold: saving again does not extend
+        env.ledger().set_sequence_number(2_000);
+        env.as_contract(&contract_id, || store.save(&world));
+        assert_eq!(entry_ttl(&env, &contract_id, &store, board), 4_000);
+        assert_eq!(store.live_until(board), Some(6_000));
+
+        // Below it: a read-only call is enough to keep the world alive
+        env.ledger().set_sequence_number(5_500);
+        let mut loaded = World::with_env(&env);
+        let mut reader = WorldStore::new(&env, symbol_short!("chess")).with_ttl(1_000, 5_000);
+        env.as_contract(&contract_id, || assert!(reader.load(&mut loaded)));
+        assert_eq!(entry_ttl(&env, &contract_id, &reader, board), 5_000);
+        assert_eq!(reader.header_live_until(), 10_500);
+
+        // Long past the original TTL, the world still loads with the tracked TTLs
+        env.ledger().set_sequence_number(10_000);
+        let mut later = WorldStore::new(&env, symbol_short!("chess"));
+        env.as_contract(&contract_id, || {
+            assert!(later.load(&mut World::with_env(&env)));
+        });
+        assert_eq!(
+            later.live_until(board),
+            Some(10_000 + DEFAULT_TTL_EXTEND_TO)
+        );
+        assert_eq!(
+            loaded.get_typed_component::<Position>(board),
+            Some(Position::new(1, 1))
+        );
+    }
+
+    #[test]
+    fn test_extensions_are_capped_at_the_maximum_ttl() {
+        let (env, contract_id) = setup();
+        let world = World::with_env(&env);
+        let mut store = WorldStore::new(&env, symbol_short!("forever")).with_ttl(10, u32::MAX);
+        env.as_contract(&contract_id, || {
+            store.save(&world);
+            assert_eq!(store.header_live_until(), 1_000 + env.storage().max_ttl());
+        });
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct Bullet;
+
+    impl ComponentTrait for Bullet {
+        fn component_type() -> Symbol {
+            symbol_short!("bullet")
+        }
+        fn serialize(&self, env: &Env) -> soroban_sdk::Bytes {
+            soroban_sdk::Bytes::new(env)
+        }
+        fn deserialize(_env: &Env, data: &soroban_sdk::Bytes) -> Option<Self> {
+            data.is_empty().then_some(Self)
+        }
+        fn storage_tier() -> StorageTier {
+            StorageTier::Temporary
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct Score(u32);
+
+    impl ComponentTrait for Score {
+        fn component_type() -> Symbol {
+            symbol_short!("score")
+        }
+        fn serialize(&self, env: &Env) -> soroban_sdk::Bytes {
+            soroban_sdk::Bytes::from_array(env, &self.0.to_be_bytes())
+        }
+        fn deserialize(_env: &Env, data: &soroban_sdk::Bytes) -> Option<Self> {
+            let mut buf = [0u8; 4];
+            (data.len() == 4).then(|| {
+                data.copy_into_slice(&mut buf);
+                Self(u32::from_be_bytes(buf))
+            })
+        }
+        fn storage_tier() -> StorageTier {
+            StorageTier::Instance
+        }
+    }
+
+    #[test]
+    fn test_declared_tiers_need_no_registration() {
+        let (env, contract_id) = setup();
+        let mut world = World::with_env(&env);
+        let bullet = world.spawn_empty().id();
+        world.add_typed_component(bullet, &Bullet);
+        let store = || WorldStore::new(&env, symbol_short!("shooter"));
+        let in_temporary = || {
+            env.as_contract(&contract_id, || {
+                let key = store().entity_key(bullet);
+                let persistent: Vec<Component> = env.storage().persistent().get(&key).unwrap();
+                persistent.is_empty() && env.storage().temporary().has(&key)
+            })
+        };
+
+        env.as_contract(&contract_id, || store().save(&world));
+        assert!(in_temporary());
+
+        // A loaded world saves the bullet where it found it
+        env.as_contract(&contract_id, || {
+            let mut reloaded = World::with_env(&env);
+            let mut writer = store();
+            assert!(writer.load(&mut reloaded));
+            assert!(reloaded.has_component(bullet, &Bullet::component_type()));
+            writer.save(&reloaded);
+        });
+        assert!(in_temporary());
+    }
+
+    #[test]
+    fn test_storage_tiers() {
+        let (env, contract_id) = setup();
+        let name = symbol_short!("asteroids");
+        let store = || {
+            WorldStore::new(&env, name.clone())
+                .with_component::<Bullet>()
+                .with_component::<Score>()
+                .with_temporary_ttl(50)
+                .with_ttl(1_000, 5_000)
+        };
+        let mut world = World::with_env(&env);
+        let ship = world.spawn_empty().id();
+        world.add_typed_component(ship, &Position::new(0, 0));
+        world.add_typed_component(ship, &Score(120));
+        let bullet = world.spawn_empty().id();
+        world.add_typed_component(bullet, &Position::new(5, 0));
+        world.add_typed_component(bullet, &Bullet);
+
+        let mut writer = store();
+        assert_eq!(
+            writer.tier_of(&Bullet::component_type()),
+            StorageTier::Temporary
+        );
+        assert_eq!(
+            writer.tier_of(&Position::component_type()),
+            StorageTier::Persistent
+        );
+        env.as_contract(&contract_id, || {
+            writer.save(&world);
+            let storage = env.storage();
+            let (ship_key, bullet_key) = (writer.entity_key(ship), writer.entity_key(bullet));
+            assert!(storage.instance().has(&ship_key));
+            assert!(!storage.instance().has(&bullet_key));
+            assert!(storage.temporary().has(&bullet_key));
+            assert!(!storage.temporary().has(&ship_key));
+            let persistent: Vec<Component> = storage.persistent().get(&bullet_key).unwrap();
+            assert_eq!(persistent.len(), 1);
+        });
+        assert_eq!(writer.instance_live_until(), 6_000);
+
+        env.ledger().set_sequence_number(1_040);
+        env.as_contract(&contract_id, || {
+            let mut loaded = World::with_env(&env);
+            assert!(store().load(&mut loaded));
+            assert!(loaded.has_component(bullet, &Bullet::component_type()));
+            assert_eq!(loaded.get_typed_component::<Score>(ship), Some(Score(120)));
+        });
+
+        // The bullet's temporary entry expires; the bullet keeps its position
+        env.ledger().set_sequence_number(1_100);
+        env.as_contract(&contract_id, || {
+            let mut loaded = World::with_env(&env);
+            assert!(store().load(&mut loaded));
+            assert!(loaded.exists(bullet));
+            assert!(!loaded.has_component(bullet, &Bullet::component_type()));
+            assert_eq!(loaded.get_typed_component::<Bullet>(bullet), None);
+            assert!(loaded
+                .query_entities(&[Bullet::component_type()])
+                .is_empty());
+            assert_eq!(
+                loaded.get_typed_component::<Position>(bullet),
+                Some(Position::new(5, 0))
+            );
+            assert_eq!(loaded.get_typed_component::<Score>(ship), Some(Score(120)));
+
+            // Saving again does not bring the bullet component back
+            let mut reader = store();
+            reader.save(&loaded);
+            assert!(!env.storage().temporary().has(&reader.entity_key(bullet)));
+        });
+    }
+}
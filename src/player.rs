@@ -0,0 +1,279 @@
+//! Provides the [`Player`] component, binding an entity to the Stellar
+//! [`Address`] of the account playing it, and the player index the [`World`]
+//! keeps so the entity of an address can be found with
+//! [`World::player_entity`].
+//!
+//! An address plays at most one entity. Entities below a player entity in the
+//! hierarchy, such as units or pieces, are owned by that player, and
+//! [`World::require_owner`] checks both the address's authorization and its
+//! ownership before a command is carried out.
+
+use crate::component::{Component, ComponentTrait};
+use crate::entity::EntityId;
+use crate::world::World;
+use soroban_sdk::xdr::{FromXdr, ToXdr};
+use soroban_sdk::{symbol_short, Address, Bytes, Env, Map, Symbol};
+
+/// Component binding an entity to the account that plays it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Player {
+    pub address: Address,
+}
+
+impl Player {
+    pub fn new(address: Address) -> Self {
+        Self { address }
+    }
+}
+
+impl ComponentTrait for Player {
+    fn component_type() -> Symbol {
+        symbol_short!("player")
+    }
+    fn serialize(&self, env: &Env) -> Bytes {
+        self.address.clone().to_xdr(env)
+    }
+    fn deserialize(env: &Env, data: &Bytes) -> Option<Self> {
+        Address::from_xdr(env, data).ok().map(Self::new)
+    }
+}
+
+/// Errors returned when binding or authorizing players
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayerError {
+    /// The entity does not exist
+    EntityNotFound,
+    /// Another entity is already played by this address
+    AddressTaken,
+    /// No entity is played by this address
+    NotAPlayer,
+    /// The entity is not owned by this address
+    NotOwner,
+}
+
+/// Index from addresses to the entities they play
+#[derive(Debug, Clone)]
+pub struct PlayerIndex {
+    entries: Map<Address, EntityId>,
+}
+
+impl PlayerIndex {
+    /// Create an empty index
+    pub fn new(env: &Env) -> Self {
+        Self {
+            entries: Map::new(env),
+        }
+    }
+
+    /// Get the entity played by `address`
+    pub fn get(&self, address: &Address) -> Option<EntityId> {
+        self.entries.get(address.clone())
+    }
+
+    /// Check whether `entity_id` may be played by `address`
+    pub fn can_insert(&self, address: &Address, entity_id: EntityId) -> bool {
+        self.get(address).map_or(true, |played| played == entity_id)
+    }
+
+    /// Record that `address` plays `entity_id`
+    pub fn insert(&mut self, address: Address, entity_id: EntityId) {
+        self.entries.set(address, entity_id);
+    }
+
+    /// Forget that `address` plays an entity
+    pub fn remove(&mut self, address: &Address) {
+        self.entries.remove(address.clone());
+    }
+
+    /// Get the number of indexed players
+    pub fn len(&self) -> u32 {
+        self.entries.len()
+    }
+
+    /// Check whether no player is indexed
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl World {
+    /// Bind `entity_id` to `address`, replacing its previous [`Player`]
+    pub fn set_player(&mut self, entity_id: EntityId, address: Address) -> Result<(), PlayerError> {
+        if !self.exists(entity_id) {
+            return Err(PlayerError::EntityNotFound);
+        }
+        if !self.players.can_insert(&address, entity_id) {
+            return Err(PlayerError::AddressTaken);
+        }
+        self.add_typed_component(entity_id, &Player::new(address));
+        Ok(())
+    }
+
+    /// Get the entity played by `address`
+    pub fn player_entity(&self, address: &Address) -> Option<EntityId> {
+        self.players.get(address)
+    }
+
+    /// Get the address playing `entity_id` or, failing that, its closest
+    /// ancestor with a [`Player`]
+    pub fn owner_of(&self, entity_id: EntityId) -> Option<Address> {
+        let mut current = entity_id;
+        // A hierarchy cannot be deeper than the number of entities
+        for _ in 0..=self.entity_count() {
+            if let Some(player) = self.get_typed_component::<Player>(current) {
+                return Some(player.address);
+            }
+            current = self.parent(current)?;
+        }
+        None
+    }
+
+    /// Require `address` to authorize the current call and return the entity
+    /// it plays
+    ///
+    /// Authorization failures abort the contract call, as with
+    /// [`Address::require_auth`], which can only be called once per address
+    /// in a contract call.
+    pub fn require_player(&self, address: &Address) -> Result<EntityId, PlayerError> {
+        address.require_auth();
+        self.player_entity(address).ok_or(PlayerError::NotAPlayer)
+    }
+
+    /// Require `address` to authorize the current call and to own `entity_id`
+    ///
+    /// Use this before carrying out a command on an entity, so players can
+    /// only command their own entity and the entities below it.
+    pub fn require_owner(&self, address: &Address, entity_id: EntityId) -> Result<(), PlayerError> {
+        address.require_auth();
+        if !self.exists(entity_id) {
+            return Err(PlayerError::EntityNotFound);
+        }
+        match self.owner_of(entity_id) {
+            Some(owner) if owner == *address => Ok(()),
+            _ => Err(PlayerError::NotOwner),
+        }
+    }
+
+    /// Update the player index before a [`Player`] component is stored
+    ///
+    /// Returns `false` if the player must not be stored, including on an
+    /// entity that does not exist.
+    pub(crate) fn index_player(&mut self, entity_id: EntityId, component: &Component) -> bool {
+        if !self.exists(entity_id) {
+            return false;
+        }
+        let Some(player) = Player::deserialize(self.env(), component.data()) else {
+            return false;
+        };
+        if !self.players.can_insert(&player.address, entity_id) {
+            return false;
+        }
+        self.unindex_player(entity_id);
+        self.players.insert(player.address, entity_id);
+        true
+    }
+
+    /// Remove `entity_id` from the player index
+    pub(crate) fn unindex_player(&mut self, entity_id: EntityId) {
+        if let Some(player) = self.get_typed_component::<Player>(entity_id) {
+            self.players.remove(&player.address);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hierarchy::ChildOf;
+    use soroban_sdk::testutils::Address as _;
+    use soroban_sdk::{contract, Env};
+
+    #[contract]
+    struct PlayerTestContract;
+
+    #[test]
+    fn test_player_roundtrip() {
+        let env = Env::default();
+        let player = Player::new(Address::generate(&env));
+        assert_eq!(
+            Player::deserialize(&env, &player.serialize(&env)),
+            Some(player)
+        );
+    }
+
+    #[test]
+    fn test_player_index() {
+        let env = Env::default();
+        let mut world = World::with_env(&env);
+        let (alice, bob) = (Address::generate(&env), Address::generate(&env));
+        let x = world.spawn_empty().id();
+        let o = world.spawn_empty().id();
+
+        assert_eq!(world.set_player(x, alice.clone()), Ok(()));
+        assert_eq!(
+            world.set_player(o, alice.clone()),
+            Err(PlayerError::AddressTaken)
+        );
+        // Adding the component directly is rejected the same way
+        world.add_typed_component(o, &Player::new(alice.clone()));
+        assert!(!world.has_component(o, &Player::component_type()));
+        assert_eq!(world.set_player(o, bob.clone()), Ok(()));
+        assert_eq!(world.player_entity(&alice), Some(x));
+        assert_eq!(world.player_entity(&bob), Some(o));
+
+        // Rebinding an entity frees its old address
+        assert_eq!(world.set_player(x, Address::generate(&env)), Ok(()));
+        assert_eq!(world.player_entity(&alice), None);
+
+        world.despawn(o);
+        assert_eq!(world.player_entity(&bob), None);
+        assert_eq!(world.set_player(o, bob), Err(PlayerError::EntityNotFound));
+    }
+
+    #[test]
+    fn test_missing_entity_takes_no_address() {
+        let env = Env::default();
+        let mut world = World::with_env(&env);
+        let alice = Address::generate(&env);
+        let ghost = EntityId::new(99, 0);
+        world.add_typed_component(ghost, &Player::new(alice.clone()));
+        assert_eq!(world.player_entity(&alice), None);
+
+        let real = world.spawn_empty().id();
+        assert_eq!(world.set_player(real, alice.clone()), Ok(()));
+        assert_eq!(world.player_entity(&alice), Some(real));
+    }
+
+    #[test]
+    fn test_require_owner() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let contract_id = env.register(PlayerTestContract, ());
+        let mut world = World::with_env(&env);
+        let (alice, bob) = (Address::generate(&env), Address::generate(&env));
+        let trainer = world.spawn_empty().id();
+        world.set_player(trainer, alice.clone()).unwrap();
+        let pikachu = world.spawn_empty().id();
+        world.add_typed_component(pikachu, &ChildOf(trainer));
+        assert_eq!(world.owner_of(pikachu), Some(alice.clone()));
+
+        // An address can only be authorized once per contract call
+        let call = |check: &dyn Fn(&World)| env.as_contract(&contract_id, || check(&world));
+        call(&|world| assert_eq!(world.require_player(&alice), Ok(trainer)));
+        call(&|world| assert_eq!(world.require_player(&bob), Err(PlayerError::NotAPlayer)));
+        call(&|world| assert_eq!(world.require_owner(&alice, pikachu), Ok(())));
+        call(&|world| {
+            assert_eq!(
+                world.require_owner(&bob, pikachu),
+                Err(PlayerError::NotOwner)
+            )
+        });
+        call(&|world| {
+            assert_eq!(
+                world.require_owner(&alice, EntityId::new(99, 0)),
+                Err(PlayerError::EntityNotFound)
+            )
+        });
+        assert_eq!(env.auths()[0].0, alice);
+    }
+}
@@ -0,0 +1,360 @@
+//! Prefabs: reusable entity templates.
+//!
+//! A [`Prefab`] is a named set of component blobs for a root entity and,
+//! optionally, a tree of child entities. Prefabs are registered in the
+//! [`World`] (or saved to and loaded from contract storage) and instantiated
+//! with [`World::spawn_prefab`], which can override components of the root.
+//!
+//! Components that reference other entities of the same prefab use the
+//! placeholder ids returned by [`Prefab::root`] and [`Prefab::add_child`].
+//! On instantiation these are remapped to the freshly spawned entities for
+//! every component registered with
+//! [`World::register_map_entities`](crate::world::World::register_map_entities).
+
+use crate::component::Component;
+use crate::entity::{EntityId, EntityMap, EntityMapper};
+use crate::world::World;
+use soroban_sdk::{contracttype, symbol_short, Env, Map, Symbol, Vec};
+
+/// Generation used by the placeholder ids of entities inside a prefab
+pub const PREFAB_LOCAL_GENERATION: u32 = u32::MAX;
+
+/// One entity of a [`Prefab`]
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct PrefabEntity {
+    /// Index of the parent entity within the prefab, `None` for the root
+    pub parent: Option<u32>,
+    /// Components the entity is spawned with
+    pub components: Vec<Component>,
+}
+
+/// A named template of entities and their components
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct Prefab {
+    pub name: Symbol,
+    /// The entities of the prefab; the root always comes first and parents
+    /// always come before their children
+    pub entities: Vec<PrefabEntity>,
+}
+
+impl Prefab {
+    /// Create a prefab with an empty root entity
+    pub fn new(env: &Env, name: Symbol) -> Self {
+        let mut entities = Vec::new(env);
+        entities.push_back(PrefabEntity {
+            parent: None,
+            components: Vec::new(env),
+        });
+        Self { name, entities }
+    }
+
+    /// Placeholder id of the entity at `index` within a prefab
+    pub fn local(index: u32) -> EntityId {
+        EntityId::new(index as u64, PREFAB_LOCAL_GENERATION)
+    }
+
+    /// Placeholder id of the root entity
+    pub fn root() -> EntityId {
+        Self::local(0)
+    }
+
+    /// Add a component to the root entity
+    pub fn with_component(mut self, component: Component) -> Self {
+        self.add_component(Self::root(), component);
+        self
+    }
+
+    /// Add a component to the prefab entity `local`, replacing one of the same type
+    ///
+    /// Returns `false` if `local` is not an entity of this prefab.
+    pub fn add_component(&mut self, local: EntityId, component: Component) -> bool {
+        let index = match self.index_of(local) {
+            Some(index) => index,
+            None => return false,
+        };
+        let mut entity = self.entities.get(index).unwrap();
+        let mut components = Vec::new(self.entities.env());
+        for existing in entity.components.iter() {
+            if existing.component_type() != component.component_type() {
+                components.push_back(existing);
+            }
+        }
+        components.push_back(component);
+        entity.components = components;
+        self.entities.set(index, entity);
+        true
+    }
+
+    /// Add an empty child entity under `parent` and return its placeholder id
+    pub fn add_child(&mut self, parent: EntityId) -> Option<EntityId> {
+        let parent = self.index_of(parent)?;
+        self.entities.push_back(PrefabEntity {
+            parent: Some(parent),
+            components: Vec::new(self.entities.env()),
+        });
+        Some(Self::local(self.entities.len() - 1))
+    }
+
+    /// Get the number of entities spawned by this prefab
+    pub fn entity_count(&self) -> u32 {
+        self.entities.len()
+    }
+
+    fn index_of(&self, local: EntityId) -> Option<u32> {
+        if local.generation() != PREFAB_LOCAL_GENERATION || local.id() >= self.entities.len() as u64
+        {
+            return None;
+        }
+        Some(local.id() as u32)
+    }
+
+    fn storage_key(name: &Symbol) -> (Symbol, Symbol) {
+        (symbol_short!("prefab"), name.clone())
+    }
+
+    /// Save this prefab to the current contract's persistent storage
+    pub fn save(&self, env: &Env) {
+        env.storage()
+            .persistent()
+            .set(&Self::storage_key(&self.name), self);
+    }
+
+    /// Load the prefab named `name` from the current contract's persistent storage
+    pub fn load(env: &Env, name: &Symbol) -> Option<Self> {
+        env.storage().persistent().get(&Self::storage_key(name))
+    }
+}
+
+/// Prefabs registered in a [`World`], by name
+#[derive(Debug, Clone)]
+pub struct PrefabRegistry {
+    prefabs: Map<Symbol, Prefab>,
+}
+
+impl PrefabRegistry {
+    /// Create an empty registry
+    pub fn new(env: &Env) -> Self {
+        Self {
+            prefabs: Map::new(env),
+        }
+    }
+
+    /// Register `prefab`, replacing any prefab with the same name
+    pub fn insert(&mut self, prefab: Prefab) {
+        self.prefabs.set(prefab.name.clone(), prefab);
+    }
+
+    /// Get the prefab named `name`
+    pub fn get(&self, name: &Symbol) -> Option<Prefab> {
+        self.prefabs.get(name.clone())
+    }
+
+    /// Get the number of registered prefabs
+    pub fn len(&self) -> u32 {
+        self.prefabs.len()
+    }
+
+    /// Check if no prefab is registered
+    pub fn is_empty(&self) -> bool {
+        self.prefabs.is_empty()
+    }
+}
+
+impl World {
+    /// Register `prefab` so it can be spawned by name
+    pub fn register_prefab(&mut self, prefab: Prefab) {
+        self.prefabs.insert(prefab);
+    }
+
+    /// Get the registered prefab named `name`
+    pub fn prefab(&self, name: &Symbol) -> Option<Prefab> {
+        self.prefabs.get(name)
+    }
+
+    /// Register the prefab named `name` from the current contract's storage
+    ///
+    /// Returns `false` if no such prefab was saved.
+    pub fn load_prefab(&mut self, name: &Symbol) -> bool {
+        match Prefab::load(self.env(), name) {
+            Some(prefab) => {
+                self.register_prefab(prefab);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Spawn the entities of the registered prefab `name` and return the root
+    ///
+    /// `overrides` are added to the root after the prefab's own components and
+    /// replace those of the same type. Child entities are linked to their
+    /// parent with [`ChildOf`](crate::hierarchy::ChildOf), and placeholder ids
+    /// inside components are remapped to the spawned entities.
+    pub fn spawn_prefab(&mut self, name: &Symbol, overrides: Vec<Component>) -> Option<EntityId> {
+        let prefab = self.prefab(name)?;
+
+        let mut entity_map = EntityMap::new(self.env());
+        let mut spawned = alloc::vec::Vec::new();
+        for index in 0..prefab.entity_count() {
+            let entity_id = self.entities.spawn();
+            entity_map.set_mapped(Prefab::local(index), entity_id);
+            spawned.push(entity_id);
+        }
+
+        for (index, template) in prefab.entities.iter().enumerate() {
+            let entity_id = spawned[index];
+            if let Some(parent) = template.parent {
+                self.add_child(spawned[parent as usize], entity_id);
+            }
+            for component in template.components.iter() {
+                let overridden = index == 0
+                    && overrides
+                        .iter()
+                        .any(|o| o.component_type() == component.component_type());
+                if !overridden {
+                    let component = self.map_component_entities(&component, &mut entity_map);
+                    self.add_component_to_entity(entity_id, component);
+                }
+            }
+        }
+
+        let root = spawned[0];
+        for component in overrides.iter() {
+            let component = self.map_component_entities(&component, &mut entity_map);
+            self.add_component_to_entity(root, component);
+        }
+        Some(root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::ComponentTrait;
+    use crate::entity::MapEntities;
+    use soroban_sdk::{contract, Bytes};
+
+    #[contract]
+    struct PrefabTestContract;
+
+    fn tagged(env: &Env, component_type: Symbol, value: u8) -> Component {
+        Component::new(component_type, Bytes::from_array(env, &[value]))
+    }
+
+    struct Turret {
+        target: EntityId,
+    }
+
+    impl ComponentTrait for Turret {
+        fn component_type() -> Symbol {
+            symbol_short!("turret")
+        }
+        fn serialize(&self, env: &Env) -> Bytes {
+            self.target.to_bytes(env)
+        }
+        fn deserialize(_env: &Env, data: &Bytes) -> Option<Self> {
+            Some(Self {
+                target: EntityId::from_bytes(data, 0)?,
+            })
+        }
+    }
+
+    impl MapEntities for Turret {
+        fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+            self.target.map_entities(entity_mapper);
+        }
+    }
+
+    #[test]
+    fn test_spawn_prefab_with_overrides() {
+        let env = Env::default();
+        let mut world = World::with_env(&env);
+        let prefab = Prefab::new(&env, symbol_short!("asteroid"))
+            .with_component(tagged(&env, symbol_short!("size"), 3))
+            .with_component(tagged(&env, symbol_short!("velocity"), 1));
+        world.register_prefab(prefab);
+
+        let big = world
+            .spawn_prefab(&symbol_short!("asteroid"), Vec::new(&env))
+            .unwrap();
+        let mut overrides = Vec::new(&env);
+        overrides.push_back(tagged(&env, symbol_short!("size"), 1));
+        let small = world
+            .spawn_prefab(&symbol_short!("asteroid"), overrides)
+            .unwrap();
+
+        assert_ne!(big, small);
+        let size = |entity| {
+            world
+                .get_component(entity, &symbol_short!("size"))
+                .unwrap()
+                .data()
+                .get(0)
+        };
+        assert_eq!(size(big), Some(3));
+        assert_eq!(size(small), Some(1));
+        assert!(world.has_component(small, &symbol_short!("velocity")));
+        assert_eq!(
+            world.spawn_prefab(&symbol_short!("unknown"), Vec::new(&env)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_spawn_hierarchical_prefab_remaps_entities() {
+        let env = Env::default();
+        let mut world = World::with_env(&env);
+        world.register_map_entities::<Turret>();
+
+        let mut prefab = Prefab::new(&env, symbol_short!("ship"));
+        let turret = prefab.add_child(Prefab::root()).unwrap();
+        let barrel = prefab.add_child(turret).unwrap();
+        prefab.add_component(
+            turret,
+            Component::new(
+                Turret::component_type(),
+                Turret { target: barrel }.serialize(&env),
+            ),
+        );
+        assert_eq!(prefab.add_child(Prefab::local(7)), None);
+        world.register_prefab(prefab);
+
+        let ship = world
+            .spawn_prefab(&symbol_short!("ship"), Vec::new(&env))
+            .unwrap();
+        assert_eq!(world.entity_count(), 3);
+
+        let turret_id = world.children(ship).get(0).unwrap();
+        let barrel_id = world.children(turret_id).get(0).unwrap();
+        let turret = world.get_typed_component::<Turret>(turret_id).unwrap();
+        assert_eq!(turret.target, barrel_id);
+
+        assert!(world.despawn(ship));
+        assert_eq!(world.entity_count(), 0);
+    }
+
+    #[test]
+    fn test_prefab_contract_storage() {
+        let env = Env::default();
+        let contract_id = env.register(PrefabTestContract, ());
+        env.as_contract(&contract_id, || {
+            let prefab = Prefab::new(&env, symbol_short!("invader")).with_component(tagged(
+                &env,
+                symbol_short!("row"),
+                2,
+            ));
+            prefab.save(&env);
+
+            let mut world = World::with_env(&env);
+            assert!(world.load_prefab(&symbol_short!("invader")));
+            assert!(!world.load_prefab(&symbol_short!("boss")));
+
+            let invader = world
+                .spawn_prefab(&symbol_short!("invader"), Vec::new(&env))
+                .unwrap();
+            assert!(world.has_component(invader, &symbol_short!("row")));
+        });
+    }
+}
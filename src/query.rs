@@ -1,6 +1,5 @@
 use crate::entity::EntityId;
 use crate::world::World;
-use alloc::boxed::Box;
 use soroban_sdk::{Symbol, Vec};
 
 /// A query for entities with specific components
@@ -10,6 +9,8 @@ pub struct Query {
     pub required_components: Vec<Symbol>,
     /// Excluded component types
     pub excluded_components: Vec<Symbol>,
+    /// Whether entities with disabling components are matched
+    pub include_disabled: bool,
 }
 
 impl Query {
@@ -19,6 +20,7 @@ impl Query {
         Self {
             required_components: Vec::new(&env),
             excluded_components: Vec::new(&env),
+            include_disabled: false,
         }
     }
 
@@ -34,12 +36,34 @@ impl Query {
         self
     }
 
+    /// Match entities with disabling components as well
+    ///
+    /// Without this, disabled entities are only matched if the query mentions
+    /// every disabling component they have.
+    pub fn include_disabled(mut self) -> Self {
+        self.include_disabled = true;
+        self
+    }
+
+    /// Check if the query requires or excludes a component type
+    pub fn mentions(&self, component_type: &Symbol) -> bool {
+        self.required_components.contains(component_type)
+            || self.excluded_components.contains(component_type)
+    }
+
     /// Execute the query on a world
     pub fn execute(&self, world: &World) -> Vec<EntityId> {
-        let env = soroban_sdk::Env::default();
-        let mut results = Vec::new(&env);
+        let mut results = Vec::new(world.env());
 
         for entity in world.iter_entities() {
+            if !self.include_disabled
+                && !world
+                    .default_query_filters()
+                    .allows(&entity, |component_type| self.mentions(component_type))
+            {
+                continue;
+            }
+
             // Check if entity has all required components
             let has_required = self
                 .required_components
@@ -119,7 +143,7 @@ impl QueryState {
     }
 
     /// Check if the query needs to be re-executed
-    pub fn needs_update(&self, current_time: u64) -> bool {
+    pub fn needs_update(&self, _current_time: u64) -> bool {
         // In a real implementation, you might check if the world has changed
         // For now, we'll just return true to always re-execute
         true
@@ -151,6 +175,12 @@ impl QueryBuilder {
         self
     }
 
+    /// Match entities with disabling components as well
+    pub fn include_disabled(mut self) -> Self {
+        self.query = self.query.include_disabled();
+        self
+    }
+
     /// Add multiple required component types
     pub fn with_components(mut self, component_types: Vec<Symbol>) -> Self {
         for component_type in component_types {
@@ -188,6 +218,14 @@ impl Default for QueryBuilder {
 pub trait QueryFilter {
     /// Check if an entity matches this filter
     fn matches(&self, world: &World, entity_id: EntityId) -> bool;
+
+    /// Check if the filter looks at a component type
+    ///
+    /// Disabled entities are only passed to [`QueryFilter::matches`] if the
+    /// filter mentions every disabling component they have.
+    fn mentions(&self, _component_type: &Symbol) -> bool {
+        false
+    }
 }
 
 /// Filter for entities with a specific component
@@ -206,6 +244,10 @@ impl QueryFilter for WithComponent {
     fn matches(&self, world: &World, entity_id: EntityId) -> bool {
         world.has_component(entity_id, &self.component_type)
     }
+
+    fn mentions(&self, component_type: &Symbol) -> bool {
+        &self.component_type == component_type
+    }
 }
 
 /// Filter for entities without a specific component
@@ -224,6 +266,10 @@ impl QueryFilter for WithoutComponent {
     fn matches(&self, world: &World, entity_id: EntityId) -> bool {
         !world.has_component(entity_id, &self.component_type)
     }
+
+    fn mentions(&self, component_type: &Symbol) -> bool {
+        &self.component_type == component_type
+    }
 }
 
 /// Combined filter that requires all sub-filters to match
@@ -247,6 +293,12 @@ impl AllFilters {
     }
 }
 
+impl Default for AllFilters {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl QueryFilter for AllFilters {
     fn matches(&self, world: &World, entity_id: EntityId) -> bool {
         if let Some(entity) = world.get_entity(entity_id) {
@@ -261,6 +313,10 @@ impl QueryFilter for AllFilters {
             false
         }
     }
+
+    fn mentions(&self, component_type: &Symbol) -> bool {
+        self.filters.contains(component_type)
+    }
 }
 
 /// Combined filter that requires any sub-filter to match
@@ -284,6 +340,12 @@ impl AnyFilter {
     }
 }
 
+impl Default for AnyFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl QueryFilter for AnyFilter {
     fn matches(&self, world: &World, entity_id: EntityId) -> bool {
         if let Some(entity) = world.get_entity(entity_id) {
@@ -298,14 +360,23 @@ impl QueryFilter for AnyFilter {
             false
         }
     }
+
+    fn mentions(&self, component_type: &Symbol) -> bool {
+        self.filters.contains(component_type)
+    }
 }
 
 /// Execute a query with a custom filter
 pub fn query_with_filter(world: &World, filter: &dyn QueryFilter) -> Vec<EntityId> {
-    let env = soroban_sdk::Env::default();
-    let mut results = Vec::new(&env);
+    let mut results = Vec::new(world.env());
 
     for entity in world.iter_entities() {
+        if !world
+            .default_query_filters()
+            .allows(&entity, |component_type| filter.mentions(component_type))
+        {
+            continue;
+        }
         if filter.matches(world, entity.id()) {
             results.push_back(entity.id());
         }
@@ -317,7 +388,7 @@ pub fn query_with_filter(world: &World, filter: &dyn QueryFilter) -> Vec<EntityI
 #[cfg(test)]
 mod tests {
     use super::*;
-    use soroban_sdk::{symbol_short, Env};
+    use soroban_sdk::symbol_short;
 
     #[test]
     fn test_query_creation() {
@@ -1,508 +1,292 @@
-//! This module provides functionality to link entities to each other using specialized components called "relationships". See the [`Relationship`] trait for more info.
+//! Links between entities, such as "Owns", "Targets" or "MemberOf", that the
+//! [`World`](crate::world::World) keeps consistent in both directions.
+//!
+//! A [`Relationship`] component lives on the "source" entity and names a
+//! single "target" entity. Once the relationship type is registered with
+//! [`World::register_relationship`](crate::world::World::register_relationship),
+//! inserting it makes the world add the source to a [`RelatedEntities`]
+//! collection stored on the target, and removing it (or despawning either side)
+//! keeps that collection up to date. What happens to the sources when the
+//! target is despawned is decided by the relationship's [`DespawnPolicy`].
 
 mod related_methods;
 mod relationship_query;
 mod relationship_source_collection;
 
-use alloc::format;
-
-use bevy_utils::prelude::DebugName;
-pub use related_methods::*;
-pub use relationship_query::*;
-pub use relationship_source_collection::*;
-
-use crate::{
-    component::{Component, Mutable},
-    entity::{ComponentCloneCtx, Entity, SourceComponent},
-    error::CommandWithEntity,
-    lifecycle::HookContext,
-    world::{DeferredWorld, EntityWorldMut},
-};
-use log::warn;
-
-/// A [`Component`] on a "source" [`Entity`] that references another target [`Entity`], creating a "relationship" between them. Every [`Relationship`]
-/// has a corresponding [`RelationshipTarget`] type (and vice-versa), which exists on the "target" entity of a relationship and contains the list of all
-/// "source" entities that relate to the given "target"
-///
-/// The [`Relationship`] component is the "source of truth" and the [`RelationshipTarget`] component reflects that source of truth. When a [`Relationship`]
-/// component is inserted on an [`Entity`], the corresponding [`RelationshipTarget`] component is immediately inserted on the target component if it does
-/// not already exist, and the "source" entity is automatically added to the [`RelationshipTarget`] collection (this is done via "component hooks").
-///
-/// A common example of a [`Relationship`] is the parent / child relationship. Bevy ECS includes a canonical form of this via the [`ChildOf`](crate::hierarchy::ChildOf)
-/// [`Relationship`] and the [`Children`](crate::hierarchy::Children) [`RelationshipTarget`].
-///
-/// [`Relationship`] and [`RelationshipTarget`] should always be derived via the [`Component`] trait to ensure the hooks are set up properly.
-///
-/// ## Derive
-///
-/// [`Relationship`] and [`RelationshipTarget`] can only be derived for structs with a single unnamed field, single named field
-/// or for named structs where one field is annotated with `#[relationship]`.
-/// If there are additional fields, they must all implement [`Default`].
-///
-/// [`RelationshipTarget`] also requires that the relationship field is private to prevent direct mutation,
-/// ensuring the correctness of relationships.
-/// ```
-/// # use bevy_ecs::component::Component;
-/// # use bevy_ecs::entity::Entity;
-/// #[derive(Component)]
-/// #[relationship(relationship_target = Children)]
-/// pub struct ChildOf {
-///     #[relationship]
-///     pub parent: Entity,
-///     internal: u8,
-/// };
-///
-/// #[derive(Component)]
-/// #[relationship_target(relationship = ChildOf)]
-/// pub struct Children(Vec<Entity>);
-/// ```
+pub use relationship_query::{AncestorIter, DescendantIter};
+pub use relationship_source_collection::RelatedEntities;
+
+use crate::component::ComponentTrait;
+use crate::entity::EntityId;
+use soroban_sdk::{contracttype, Bytes, Env, Symbol};
+
+/// What the world does with the sources of a relationship when its target is
+/// despawned
+#[contracttype]
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DespawnPolicy {
+    /// Remove the relationship component from every source
+    #[default]
+    Detach = 0,
+    /// Despawn every source as well
+    Cascade = 1,
+    /// Refuse to despawn the target while it still has sources
+    Forbid = 2,
+}
+
+/// A component on a "source" entity that points at a "target" entity
 ///
-/// When deriving [`RelationshipTarget`] you can specify the `#[relationship_target(linked_spawn)]` attribute to
-/// automatically despawn entities stored in an entity's [`RelationshipTarget`] when that entity is despawned:
+/// The relationship component is the source of truth: the
+/// [`RelatedEntities`] collection of type
+/// [`Relationship::target_component_type`] on the target is derived from it
+/// and should not be written directly.
 ///
-/// ```
-/// # use bevy_ecs::component::Component;
-/// # use bevy_ecs::entity::Entity;
-/// #[derive(Component)]
-/// #[relationship(relationship_target = Children)]
-/// pub struct ChildOf(pub Entity);
+/// ```ignore
+/// pub struct Owns(pub EntityId);
 ///
-/// #[derive(Component)]
-/// #[relationship_target(relationship = ChildOf, linked_spawn)]
-/// pub struct Children(Vec<Entity>);
+/// impl Relationship for Owns {
+///     fn target_component_type() -> Symbol {
+///         symbol_short!("ownedby")
+///     }
+///     fn get(&self) -> EntityId {
+///         self.0
+///     }
+///     fn from(entity: EntityId) -> Self {
+///         Self(entity)
+///     }
+/// }
 /// ```
-pub trait Relationship: Component + Sized {
-    /// The [`Component`] added to the "target" entities of this [`Relationship`], which contains the list of all "source"
-    /// entities that relate to the "target".
-    type RelationshipTarget: RelationshipTarget<Relationship = Self>;
-
-    /// Gets the [`Entity`] ID of the related entity.
-    fn get(&self) -> Entity;
-
-    /// Creates this [`Relationship`] from the given `entity`.
-    fn from(entity: Entity) -> Self;
-
-    /// Changes the current [`Entity`] ID of the entity containing the [`RelationshipTarget`] to another one.
-    ///
-    /// This is useful for updating the relationship without overwriting other fields stored in `Self`.
-    ///
-    /// # Warning
-    ///
-    /// This should generally not be called by user code, as modifying the related entity could invalidate the
-    /// relationship. If this method is used, then the hooks [`on_replace`](Relationship::on_replace) have to
-    /// run before and [`on_insert`](Relationship::on_insert) after it.
-    /// This happens automatically when this method is called with [`EntityWorldMut::modify_component`].
-    ///
-    /// Prefer to use regular means of insertions when possible.
-    fn set_risky(&mut self, entity: Entity);
-
-    /// The `on_insert` component hook that maintains the [`Relationship`] / [`RelationshipTarget`] connection.
-    fn on_insert(
-        mut world: DeferredWorld,
-        HookContext {
-            entity,
-            caller,
-            relationship_hook_mode,
-            ..
-        }: HookContext,
-    ) {
-        match relationship_hook_mode {
-            RelationshipHookMode::Run => {}
-            RelationshipHookMode::Skip => return,
-            RelationshipHookMode::RunIfNotLinked => {
-                if <Self::RelationshipTarget as RelationshipTarget>::LINKED_SPAWN {
-                    return;
-                }
-            }
-        }
-        let target_entity = world.entity(entity).get::<Self>().unwrap().get();
-        if target_entity == entity {
-            warn!(
-                "{}The {}({target_entity:?}) relationship on entity {entity:?} points to itself. The invalid {} relationship has been removed.",
-                caller.map(|location|format!("{location}: ")).unwrap_or_default(),
-                DebugName::type_name::<Self>(),
-                DebugName::type_name::<Self>()
-            );
-            world.commands().entity(entity).remove::<Self>();
-            return;
-        }
-        if let Ok(mut entity_commands) = world.commands().get_entity(target_entity) {
-            // Deferring is necessary for batch mode
-            entity_commands
-                .entry::<Self::RelationshipTarget>()
-                .and_modify(move |mut relationship_target| {
-                    relationship_target.collection_mut_risky().add(entity);
-                })
-                .or_insert_with(|| {
-                    let mut target = Self::RelationshipTarget::with_capacity(1);
-                    target.collection_mut_risky().add(entity);
-                    target
-                });
-        } else {
-            warn!(
-                "{}The {}({target_entity:?}) relationship on entity {entity:?} relates to an entity that does not exist. The invalid {} relationship has been removed.",
-                caller.map(|location|format!("{location}: ")).unwrap_or_default(),
-                DebugName::type_name::<Self>(),
-                DebugName::type_name::<Self>()
-            );
-            world.commands().entity(entity).remove::<Self>();
-        }
-    }
+pub trait Relationship: ComponentTrait + Sized {
+    /// Component type of the [`RelatedEntities`] collection kept on the target
+    fn target_component_type() -> Symbol;
 
-    /// The `on_replace` component hook that maintains the [`Relationship`] / [`RelationshipTarget`] connection.
-    // note: think of this as "on_drop"
-    fn on_replace(
-        mut world: DeferredWorld,
-        HookContext {
-            entity,
-            relationship_hook_mode,
-            ..
-        }: HookContext,
-    ) {
-        match relationship_hook_mode {
-            RelationshipHookMode::Run => {}
-            RelationshipHookMode::Skip => return,
-            RelationshipHookMode::RunIfNotLinked => {
-                if <Self::RelationshipTarget as RelationshipTarget>::LINKED_SPAWN {
-                    return;
-                }
-            }
-        }
-        let target_entity = world.entity(entity).get::<Self>().unwrap().get();
-        if let Ok(mut target_entity_mut) = world.get_entity_mut(target_entity) {
-            if let Some(mut relationship_target) =
-                target_entity_mut.get_mut::<Self::RelationshipTarget>()
-            {
-                relationship_target.collection_mut_risky().remove(entity);
-                if relationship_target.len() == 0 {
-                    let command = |mut entity: EntityWorldMut| {
-                        // this "remove" operation must check emptiness because in the event that an identical
-                        // relationship is inserted on top, this despawn would result in the removal of that identical
-                        // relationship ... not what we want!
-                        if entity
-                            .get::<Self::RelationshipTarget>()
-                            .is_some_and(RelationshipTarget::is_empty)
-                        {
-                            entity.remove::<Self::RelationshipTarget>();
-                        }
-                    };
-
-                    world
-                        .commands()
-                        .queue_silenced(command.with_entity(target_entity));
-                }
-            }
-        }
+    /// Get the target entity of this relationship
+    fn get(&self) -> EntityId;
+
+    /// Create this relationship pointing at `entity`
+    fn from(entity: EntityId) -> Self;
+
+    /// What happens to the sources when the target is despawned
+    fn despawn_policy() -> DespawnPolicy {
+        DespawnPolicy::Detach
     }
 }
 
-/// The iterator type for the source entities in a [`RelationshipTarget`] collection,
-/// as defined in the [`RelationshipSourceCollection`] trait.
-pub type SourceIter<'w, R> =
-    <<R as RelationshipTarget>::Collection as RelationshipSourceCollection>::SourceIter<'w>;
-
-/// A [`Component`] containing the collection of entities that relate to this [`Entity`] via the associated `Relationship` type.
-/// See the [`Relationship`] documentation for more information.
-pub trait RelationshipTarget: Component<Mutability = Mutable> + Sized {
-    /// If this is true, when despawning or cloning (when [linked cloning is enabled](crate::entity::EntityClonerBuilder::linked_cloning)), the related entities targeting this entity will also be despawned or cloned.
-    ///
-    /// For example, this is set to `true` for Bevy's built-in parent-child relation, defined by [`ChildOf`](crate::prelude::ChildOf) and [`Children`](crate::prelude::Children).
-    /// This means that when a parent is despawned, any children targeting that parent are also despawned (and the same applies to cloning).
-    ///
-    /// To get around this behavior, you can first break the relationship between entities, and *then* despawn or clone.
-    /// This defaults to false when derived.
-    const LINKED_SPAWN: bool;
-    /// The [`Relationship`] that populates this [`RelationshipTarget`] collection.
-    type Relationship: Relationship<RelationshipTarget = Self>;
-    /// The collection type that stores the "source" entities for this [`RelationshipTarget`] component.
-    ///
-    /// Check the list of types which implement [`RelationshipSourceCollection`] for the data structures that can be used inside of your component.
-    /// If you need a new collection type, you can implement the [`RelationshipSourceCollection`] trait
-    /// for a type you own which wraps the collection you want to use (to avoid the orphan rule),
-    /// or open an issue on the Bevy repository to request first-party support for your collection type.
-    type Collection: RelationshipSourceCollection;
-
-    /// Returns a reference to the stored [`RelationshipTarget::Collection`].
-    fn collection(&self) -> &Self::Collection;
-    /// Returns a mutable reference to the stored [`RelationshipTarget::Collection`].
-    ///
-    /// # Warning
-    /// This should generally not be called by user code, as modifying the internal collection could invalidate the relationship.
-    /// The collection should not contain duplicates.
-    fn collection_mut_risky(&mut self) -> &mut Self::Collection;
-
-    /// Creates a new [`RelationshipTarget`] from the given [`RelationshipTarget::Collection`].
-    ///
-    /// # Warning
-    /// This should generally not be called by user code, as constructing the internal collection could invalidate the relationship.
-    /// The collection should not contain duplicates.
-    fn from_collection_risky(collection: Self::Collection) -> Self;
-
-    /// The `on_replace` component hook that maintains the [`Relationship`] / [`RelationshipTarget`] connection.
-    // note: think of this as "on_drop"
-    fn on_replace(mut world: DeferredWorld, HookContext { entity, .. }: HookContext) {
-        let (entities, mut commands) = world.entities_and_commands();
-        let relationship_target = entities.get(entity).unwrap().get::<Self>().unwrap();
-        for source_entity in relationship_target.iter() {
-            commands
-                .entity(source_entity)
-                .try_remove::<Self::Relationship>();
-        }
-    }
+/// Type-erased description of a registered [`Relationship`]
+#[derive(Debug, Clone)]
+pub struct RelationshipKind {
+    relationship: Symbol,
+    target: Symbol,
+    policy: DespawnPolicy,
+    target_of: fn(&Env, &Bytes) -> Option<EntityId>,
+    with_target: fn(&Env, EntityId) -> Bytes,
+}
 
-    /// The `on_despawn` component hook that despawns entities stored in an entity's [`RelationshipTarget`] when
-    /// that entity is despawned.
-    // note: think of this as "on_drop"
-    fn on_despawn(mut world: DeferredWorld, HookContext { entity, .. }: HookContext) {
-        let (entities, mut commands) = world.entities_and_commands();
-        let relationship_target = entities.get(entity).unwrap().get::<Self>().unwrap();
-        for source_entity in relationship_target.iter() {
-            commands.entity(source_entity).try_despawn();
+impl RelationshipKind {
+    /// Describe the relationship type `R`
+    pub fn of<R: Relationship>() -> Self {
+        Self {
+            relationship: R::component_type(),
+            target: R::target_component_type(),
+            policy: R::despawn_policy(),
+            target_of: |env, data| R::deserialize(env, data).map(|r| r.get()),
+            with_target: |env, target| R::from(target).serialize(env),
         }
     }
 
-    /// Creates this [`RelationshipTarget`] with the given pre-allocated entity capacity.
-    fn with_capacity(capacity: usize) -> Self {
-        let collection =
-            <Self::Collection as RelationshipSourceCollection>::with_capacity(capacity);
-        Self::from_collection_risky(collection)
+    /// Component type stored on the source entity
+    pub fn relationship(&self) -> &Symbol {
+        &self.relationship
     }
 
-    /// Iterates the entities stored in this collection.
-    #[inline]
-    fn iter(&self) -> SourceIter<'_, Self> {
-        self.collection().iter()
+    /// Component type of the collection stored on the target entity
+    pub fn target(&self) -> &Symbol {
+        &self.target
     }
 
-    /// Returns the number of entities in this collection.
-    #[inline]
-    fn len(&self) -> usize {
-        self.collection().len()
+    /// Policy applied to sources when the target is despawned
+    pub fn policy(&self) -> DespawnPolicy {
+        self.policy
     }
 
-    /// Returns true if this entity collection is empty.
-    #[inline]
-    fn is_empty(&self) -> bool {
-        self.collection().is_empty()
+    /// Decode the target entity from serialized relationship component data
+    pub fn target_of(&self, env: &Env, data: &Bytes) -> Option<EntityId> {
+        (self.target_of)(env, data)
     }
-}
 
-/// The "clone behavior" for [`RelationshipTarget`]. This actually creates an empty
-/// [`RelationshipTarget`] instance with space reserved for the number of targets in the
-/// original instance. The [`RelationshipTarget`] will then be populated with the proper components
-/// when the corresponding [`Relationship`] sources of truth are inserted. Cloning the actual entities
-/// in the original [`RelationshipTarget`] would result in duplicates, so we don't do that!
-///
-/// This will also queue up clones of the relationship sources if the [`EntityCloner`](crate::entity::EntityCloner) is configured
-/// to spawn recursively.
-pub fn clone_relationship_target<T: RelationshipTarget>(
-    source: &SourceComponent,
-    context: &mut ComponentCloneCtx,
-) {
-    if let Some(component) = source.read::<T>() {
-        let mut cloned = T::with_capacity(component.len());
-        if context.linked_cloning() && T::LINKED_SPAWN {
-            let collection = cloned.collection_mut_risky();
-            for entity in component.iter() {
-                collection.add(entity);
-                context.queue_entity_clone(entity);
-            }
-        }
-        context.write_target_component(cloned);
+    /// Serialize a relationship component pointing at `target`
+    pub fn with_target(&self, env: &Env, target: EntityId) -> Bytes {
+        (self.with_target)(env, target)
     }
 }
 
-/// Configures the conditions under which the Relationship insert/replace hooks will be run.
-#[derive(Copy, Clone, Debug)]
-pub enum RelationshipHookMode {
-    /// Relationship insert/replace hooks will always run
-    Run,
-    /// Relationship insert/replace hooks will run if [`RelationshipTarget::LINKED_SPAWN`] is false
-    RunIfNotLinked,
-    /// Relationship insert/replace hooks will always be skipped
-    Skip,
-}
-
 #[cfg(test)]
 mod tests {
-    use crate::prelude::{ChildOf, Children};
+    use super::*;
+    use crate::component::Component;
     use crate::world::World;
-    use crate::{component::Component, entity::Entity};
-    use alloc::vec::Vec;
+    use soroban_sdk::{symbol_short, Env};
 
-    #[test]
-    fn custom_relationship() {
-        #[derive(Component)]
-        #[relationship(relationship_target = LikedBy)]
-        struct Likes(pub Entity);
+    struct Owns(EntityId);
 
-        #[derive(Component)]
-        #[relationship_target(relationship = Likes)]
-        struct LikedBy(Vec<Entity>);
+    impl ComponentTrait for Owns {
+        fn component_type() -> Symbol {
+            symbol_short!("owns")
+        }
+        fn serialize(&self, env: &Env) -> Bytes {
+            self.0.to_bytes(env)
+        }
+        fn deserialize(_env: &Env, data: &Bytes) -> Option<Self> {
+            EntityId::from_bytes(data, 0).map(Self)
+        }
+    }
 
-        let mut world = World::new();
-        let a = world.spawn_empty().id();
-        let b = world.spawn(Likes(a)).id();
-        let c = world.spawn(Likes(a)).id();
-        assert_eq!(world.entity(a).get::<LikedBy>().unwrap().0, &[b, c]);
+    impl Relationship for Owns {
+        fn target_component_type() -> Symbol {
+            symbol_short!("ownedby")
+        }
+        fn get(&self) -> EntityId {
+            self.0
+        }
+        fn from(entity: EntityId) -> Self {
+            Self(entity)
+        }
     }
 
-    #[test]
-    fn self_relationship_fails() {
-        #[derive(Component)]
-        #[relationship(relationship_target = RelTarget)]
-        struct Rel(Entity);
+    struct MemberOf(EntityId);
+
+    impl ComponentTrait for MemberOf {
+        fn component_type() -> Symbol {
+            symbol_short!("memberof")
+        }
+        fn serialize(&self, env: &Env) -> Bytes {
+            self.0.to_bytes(env)
+        }
+        fn deserialize(_env: &Env, data: &Bytes) -> Option<Self> {
+            EntityId::from_bytes(data, 0).map(Self)
+        }
+    }
 
-        #[derive(Component)]
-        #[relationship_target(relationship = Rel)]
-        struct RelTarget(Vec<Entity>);
+    impl Relationship for MemberOf {
+        fn target_component_type() -> Symbol {
+            symbol_short!("members")
+        }
+        fn get(&self) -> EntityId {
+            self.0
+        }
+        fn from(entity: EntityId) -> Self {
+            Self(entity)
+        }
+        fn despawn_policy() -> DespawnPolicy {
+            DespawnPolicy::Forbid
+        }
+    }
 
+    fn owns_world() -> World {
         let mut world = World::new();
-        let a = world.spawn_empty().id();
-        world.entity_mut(a).insert(Rel(a));
-        assert!(!world.entity(a).contains::<Rel>());
-        assert!(!world.entity(a).contains::<RelTarget>());
+        world.register_relationship::<Owns>();
+        world
     }
 
     #[test]
-    fn relationship_with_missing_target_fails() {
-        #[derive(Component)]
-        #[relationship(relationship_target = RelTarget)]
-        struct Rel(Entity);
-
-        #[derive(Component)]
-        #[relationship_target(relationship = Rel)]
-        struct RelTarget(Vec<Entity>);
+    fn test_insert_maintains_target_collection() {
+        let mut world = owns_world();
+        let chest = world.spawn_empty().id();
+        let alice = world.spawn_empty().id();
+        let bob = world.spawn_empty().id();
+
+        world.add_typed_component(alice, &Owns(chest));
+        world.add_typed_component(bob, &Owns(chest));
+
+        let owners = world.related::<Owns>(chest);
+        assert_eq!(owners.len(), 2);
+        assert!(owners.contains(alice));
+        assert!(owners.contains(bob));
+        assert_eq!(world.relationship_target::<Owns>(alice), Some(chest));
+    }
 
-        let mut world = World::new();
-        let a = world.spawn_empty().id();
-        world.despawn(a);
-        let b = world.spawn(Rel(a)).id();
-        assert!(!world.entity(b).contains::<Rel>());
-        assert!(!world.entity(b).contains::<RelTarget>());
+    #[test]
+    fn test_retarget_and_remove() {
+        let mut world = owns_world();
+        let chest = world.spawn_empty().id();
+        let sword = world.spawn_empty().id();
+        let alice = world.spawn_empty().id();
+
+        world.add_typed_component(alice, &Owns(chest));
+        world.add_typed_component(alice, &Owns(sword));
+        assert!(world.related::<Owns>(chest).is_empty());
+        assert!(!world.has_component(chest, &symbol_short!("ownedby")));
+        assert!(world.related::<Owns>(sword).contains(alice));
+
+        assert!(world.remove_component_from_entity(alice, &Owns::component_type()));
+        assert!(world.related::<Owns>(sword).is_empty());
+        assert!(!world.has_component(sword, &symbol_short!("ownedby")));
     }
 
     #[test]
-    fn relationship_with_multiple_non_target_fields_compiles() {
-        #[derive(Component)]
-        #[relationship(relationship_target=Target)]
-        #[expect(dead_code, reason = "test struct")]
-        struct Source {
-            #[relationship]
-            target: Entity,
-            foo: u8,
-            bar: u8,
-        }
+    fn test_invalid_targets_are_rejected() {
+        let mut world = owns_world();
+        let alice = world.spawn_empty().id();
 
-        #[derive(Component)]
-        #[relationship_target(relationship=Source)]
-        struct Target(Vec<Entity>);
+        world.add_typed_component(alice, &Owns(alice));
+        assert!(!world.has_component(alice, &Owns::component_type()));
 
-        // No assert necessary, looking to make sure compilation works with the macros
+        world.add_typed_component(alice, &Owns(EntityId::new(99, 0)));
+        assert!(!world.has_component(alice, &Owns::component_type()));
     }
-    #[test]
-    fn relationship_target_with_multiple_non_target_fields_compiles() {
-        #[derive(Component)]
-        #[relationship(relationship_target=Target)]
-        struct Source(Entity);
-
-        #[derive(Component)]
-        #[relationship_target(relationship=Source)]
-        #[expect(dead_code, reason = "test struct")]
-        struct Target {
-            #[relationship]
-            target: Vec<Entity>,
-            foo: u8,
-            bar: u8,
-        }
 
-        // No assert necessary, looking to make sure compilation works with the macros
+    #[test]
+    fn test_despawn_source_updates_target() {
+        let mut world = owns_world();
+        let chest = world.spawn_empty().id();
+        let alice = world.spawn_empty().id();
+        world.add_typed_component(alice, &Owns(chest));
+
+        assert!(world.despawn(alice));
+        assert!(world.related::<Owns>(chest).is_empty());
     }
 
     #[test]
-    fn parent_child_relationship_with_custom_relationship() {
-        #[derive(Component)]
-        #[relationship(relationship_target = RelTarget)]
-        struct Rel(Entity);
-
-        #[derive(Component)]
-        #[relationship_target(relationship = Rel)]
-        struct RelTarget(Entity);
-
-        let mut world = World::new();
-
-        // Rel on Parent
-        // Despawn Parent
-        let mut commands = world.commands();
-        let child = commands.spawn_empty().id();
-        let parent = commands.spawn(Rel(child)).add_child(child).id();
-        commands.entity(parent).despawn();
-        world.flush();
-
-        assert!(world.get_entity(child).is_err());
-        assert!(world.get_entity(parent).is_err());
-
-        // Rel on Parent
-        // Despawn Child
-        let mut commands = world.commands();
-        let child = commands.spawn_empty().id();
-        let parent = commands.spawn(Rel(child)).add_child(child).id();
-        commands.entity(child).despawn();
-        world.flush();
-
-        assert!(world.get_entity(child).is_err());
-        assert!(!world.entity(parent).contains::<Rel>());
-
-        // Rel on Child
-        // Despawn Parent
-        let mut commands = world.commands();
-        let parent = commands.spawn_empty().id();
-        let child = commands.spawn((ChildOf(parent), Rel(parent))).id();
-        commands.entity(parent).despawn();
-        world.flush();
-
-        assert!(world.get_entity(child).is_err());
-        assert!(world.get_entity(parent).is_err());
-
-        // Rel on Child
-        // Despawn Child
-        let mut commands = world.commands();
-        let parent = commands.spawn_empty().id();
-        let child = commands.spawn((ChildOf(parent), Rel(parent))).id();
-        commands.entity(child).despawn();
-        world.flush();
-
-        assert!(world.get_entity(child).is_err());
-        assert!(!world.entity(parent).contains::<RelTarget>());
+    fn test_despawn_target_detaches_sources() {
+        let mut world = owns_world();
+        let chest = world.spawn_empty().id();
+        let alice = world.spawn_empty().id();
+        world.add_typed_component(alice, &Owns(chest));
+
+        assert!(world.despawn(chest));
+        assert!(world.exists(alice));
+        assert!(!world.has_component(alice, &Owns::component_type()));
+        assert_eq!(world.relationship_target::<Owns>(alice), None);
     }
 
     #[test]
-    fn spawn_batch_with_relationship() {
+    fn test_despawn_target_forbidden_while_related() {
         let mut world = World::new();
-        let parent = world.spawn_empty().id();
-        let children = world
-            .spawn_batch((0..10).map(|_| ChildOf(parent)))
-            .collect::<Vec<_>>();
-
-        for &child in &children {
-            assert!(world
-                .get::<ChildOf>(child)
-                .is_some_and(|child_of| child_of.parent() == parent));
-        }
-        assert!(world
-            .get::<Children>(parent)
-            .is_some_and(|children| children.len() == 10));
+        world.register_relationship::<MemberOf>();
+        let guild = world.spawn_empty().id();
+        let alice = world.spawn_empty().id();
+        world.add_typed_component(alice, &MemberOf(guild));
+
+        assert!(!world.despawn(guild));
+        assert!(world.exists(guild));
+
+        world.remove_component_from_entity(alice, &MemberOf::component_type());
+        assert!(world.despawn(guild));
     }
 
     #[test]
-    fn insert_batch_with_relationship() {
+    fn test_unregistered_component_is_plain_data() {
         let mut world = World::new();
-        let parent = world.spawn_empty().id();
-        let child = world.spawn_empty().id();
-        world.insert_batch([(child, ChildOf(parent))]);
-        world.flush();
-
-        assert!(world.get::<ChildOf>(child).is_some());
-        assert!(world.get::<Children>(parent).is_some());
+        let chest = world.spawn_empty().id();
+        let alice = world.spawn_empty().id();
+        let env = world.env().clone();
+        world.add_component_to_entity(
+            alice,
+            Component::new(Owns::component_type(), chest.to_bytes(&env)),
+        );
+
+        assert!(world.has_component(alice, &Owns::component_type()));
+        assert!(world.related::<Owns>(chest).is_empty());
     }
 }
@@ -71,7 +71,7 @@ impl ResourceTrait for GameState {
         bytes.append(&game_over_bytes);
         bytes
     }
-    fn deserialize(env: &Env, data: &Bytes) -> Option<Self> {
+    fn deserialize(_env: &Env, data: &Bytes) -> Option<Self> {
         if data.len() != 9 {
             return None;
         }
@@ -0,0 +1,379 @@
+//! Deterministic random numbers for game logic.
+//!
+//! [`GameRng`] is a seeded generator stored as a [`World`] resource, so the
+//! same seed and the same sequence of calls always produce the same game,
+//! whether on-chain, in tests or when replaying a match. Seed it from the
+//! ledger's PRNG with [`GameRng::from_prng`] for unpredictable games, or from
+//! a fixed value with [`GameRng::from_seed`] for reproducible ones.
+//!
+//! Each system should draw from its own [`GameRng::stream`], identified by a
+//! label: streams are independent, so adding a draw in one system does not
+//! shift the numbers every other system sees.
+//!
+//! ```ignore
+//! let mut rng = world.get_typed_resource::<GameRng>().unwrap();
+//! let gap = rng.stream("pipes").range_i32(40, 150);
+//! world.set_typed_resource(&rng);
+//! ```
+
+use crate::resource::ResourceTrait;
+use alloc::vec::Vec as StdVec;
+use soroban_sdk::{symbol_short, Bytes, Env, IntoVal, Symbol, TryFromVal, Val, Vec};
+
+const GOLDEN_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// One SplitMix64 step: advance `state` and return the next output
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(GOLDEN_GAMMA);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// FNV-1a hash of a stream label
+fn label_hash(label: &str) -> u64 {
+    label.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// Random number helpers shared by [`GameRng`] and its streams
+pub trait Rng {
+    /// Get the next 64 random bits
+    fn next_u64(&mut self) -> u64;
+
+    /// Get the next 32 random bits
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    /// Get a uniformly distributed value in `low..high`
+    ///
+    /// Returns `low` if the range is empty.
+    fn range_u64(&mut self, low: u64, high: u64) -> u64 {
+        if high <= low {
+            return low;
+        }
+        let span = high - low;
+        // Reject the top values that would make some results more likely
+        let zone = u64::MAX - (u64::MAX - span + 1) % span;
+        loop {
+            let value = self.next_u64();
+            if value <= zone {
+                return low + value % span;
+            }
+        }
+    }
+
+    /// Get a uniformly distributed value in `low..high`
+    ///
+    /// Returns `low` if the range is empty.
+    fn range_u32(&mut self, low: u32, high: u32) -> u32 {
+        self.range_u64(low as u64, high as u64) as u32
+    }
+
+    /// Get a uniformly distributed value in `low..high`
+    ///
+    /// Returns `low` if the range is empty.
+    fn range_i64(&mut self, low: i64, high: i64) -> i64 {
+        if high <= low {
+            return low;
+        }
+        let span = high.abs_diff(low);
+        low.wrapping_add(self.range_u64(0, span) as i64)
+    }
+
+    /// Get a uniformly distributed value in `low..high`
+    ///
+    /// Returns `low` if the range is empty.
+    fn range_i32(&mut self, low: i32, high: i32) -> i32 {
+        self.range_i64(low as i64, high as i64) as i32
+    }
+
+    /// Return `true` with a probability of `numerator / denominator`
+    fn chance(&mut self, numerator: u32, denominator: u32) -> bool {
+        denominator != 0 && self.range_u32(0, denominator) < numerator
+    }
+
+    /// Pick an index with a probability proportional to its weight
+    ///
+    /// Returns `None` if every weight is zero.
+    fn weighted_index(&mut self, weights: &[u32]) -> Option<usize> {
+        let total: u64 = weights.iter().map(|weight| *weight as u64).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut roll = self.range_u64(0, total);
+        for (index, weight) in weights.iter().enumerate() {
+            if roll < *weight as u64 {
+                return Some(index);
+            }
+            roll -= *weight as u64;
+        }
+        None
+    }
+
+    /// Pick a random element of `items`
+    fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            return None;
+        }
+        items.get(self.range_u64(0, items.len() as u64) as usize)
+    }
+
+    /// Shuffle `items` in place
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.range_u64(0, i as u64 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+
+    /// Shuffle a Soroban `Vec` in place
+    fn shuffle_vec<T>(&mut self, items: &mut Vec<T>)
+    where
+        T: IntoVal<Env, Val> + TryFromVal<Env, Val>,
+    {
+        for i in (1..items.len()).rev() {
+            let j = self.range_u32(0, i + 1);
+            if i != j {
+                let a = items.get_unchecked(i);
+                let b = items.get_unchecked(j);
+                items.set(i, b);
+                items.set(j, a);
+            }
+        }
+    }
+}
+
+/// Seeded random number generator resource
+///
+/// Uses SplitMix64, which keeps its whole state in one `u64` and is cheap to
+/// run in a contract. It is not suitable for anything an attacker must not
+/// predict once the seed is known.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameRng {
+    seed: u64,
+    state: u64,
+    streams: StdVec<(u64, u64)>,
+}
+
+impl GameRng {
+    /// Create a generator from an explicit seed
+    pub fn from_seed(seed: u64) -> Self {
+        Self {
+            seed,
+            state: seed,
+            streams: StdVec::new(),
+        }
+    }
+
+    /// Create a generator seeded from the ledger's PRNG
+    ///
+    /// Must be called while a contract is executing.
+    pub fn from_prng(env: &Env) -> Self {
+        Self::from_seed(env.prng().gen())
+    }
+
+    /// Get the seed the generator was created with
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Get the independent stream named `label`, creating it on first use
+    ///
+    /// A stream's sequence depends only on the seed and its label, not on
+    /// how many numbers other streams have drawn.
+    pub fn stream(&mut self, label: &str) -> RngStream<'_> {
+        let key = label_hash(label);
+        let index = match self.streams.iter().position(|(hash, _)| *hash == key) {
+            Some(index) => index,
+            None => {
+                let mut state = self.seed ^ key;
+                let state = splitmix64(&mut state);
+                self.streams.push((key, state));
+                self.streams.len() - 1
+            }
+        };
+        RngStream {
+            state: &mut self.streams[index].1,
+        }
+    }
+}
+
+impl Rng for GameRng {
+    fn next_u64(&mut self) -> u64 {
+        splitmix64(&mut self.state)
+    }
+}
+
+impl ResourceTrait for GameRng {
+    fn resource_type() -> Symbol {
+        symbol_short!("gamerng")
+    }
+    fn serialize(&self, env: &Env) -> Bytes {
+        let mut bytes = Bytes::new(env);
+        bytes.extend_from_array(&self.seed.to_be_bytes());
+        bytes.extend_from_array(&self.state.to_be_bytes());
+        bytes.extend_from_array(&(self.streams.len() as u32).to_be_bytes());
+        for (hash, state) in self.streams.iter() {
+            bytes.extend_from_array(&hash.to_be_bytes());
+            bytes.extend_from_array(&state.to_be_bytes());
+        }
+        bytes
+    }
+    fn deserialize(_env: &Env, data: &Bytes) -> Option<Self> {
+        let read_u64 = |offset: u32| -> Option<u64> {
+            let mut buf = [0u8; 8];
+            data.slice(offset..offset.checked_add(8)?)
+                .copy_into_slice(&mut buf);
+            Some(u64::from_be_bytes(buf))
+        };
+        if data.len() < 20 {
+            return None;
+        }
+        let mut count = [0u8; 4];
+        data.slice(16..20).copy_into_slice(&mut count);
+        let count = u32::from_be_bytes(count);
+        if data.len() != count.checked_mul(16)?.checked_add(20)? {
+            return None;
+        }
+        let mut streams = StdVec::with_capacity(count as usize);
+        for i in 0..count {
+            let offset = 20 + i * 16;
+            streams.push((read_u64(offset)?, read_u64(offset + 8)?));
+        }
+        Some(Self {
+            seed: read_u64(0)?,
+            state: read_u64(8)?,
+            streams,
+        })
+    }
+}
+
+/// An independent sequence of random numbers borrowed from a [`GameRng`]
+#[derive(Debug)]
+pub struct RngStream<'a> {
+    state: &'a mut u64,
+}
+
+impl Rng for RngStream<'_> {
+    fn next_u64(&mut self) -> u64 {
+        splitmix64(self.state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::World;
+    use soroban_sdk::contract;
+
+    #[contract]
+    struct RngTestContract;
+
+    #[test]
+    fn test_same_seed_same_sequence() {
+        let mut a = GameRng::from_seed(42);
+        let mut b = GameRng::from_seed(42);
+        let mut c = GameRng::from_seed(43);
+        let rolls_a: StdVec<u64> = (0..8).map(|_| a.next_u64()).collect();
+        let rolls_b: StdVec<u64> = (0..8).map(|_| b.next_u64()).collect();
+        let rolls_c: StdVec<u64> = (0..8).map(|_| c.next_u64()).collect();
+        assert_eq!(rolls_a, rolls_b);
+        assert_ne!(rolls_a, rolls_c);
+    }
+
+    #[test]
+    fn test_streams_are_independent() {
+        let mut a = GameRng::from_seed(7);
+        let mut b = GameRng::from_seed(7);
+
+        // Extra draws on other streams do not shift the "food" stream
+        a.stream("enemies").next_u64();
+        a.next_u64();
+        let food_a: StdVec<u32> = (0..4).map(|_| a.stream("food").next_u32()).collect();
+        let food_b: StdVec<u32> = (0..4).map(|_| b.stream("food").next_u32()).collect();
+        assert_eq!(food_a, food_b);
+
+        let enemies = b.stream("enemies").next_u32();
+        assert_ne!(b.stream("food").next_u32(), enemies);
+    }
+
+    #[test]
+    fn test_ranges() {
+        let mut rng = GameRng::from_seed(1);
+        for _ in 0..200 {
+            let gap = rng.range_i32(40, 150);
+            assert!((40..150).contains(&gap));
+            let offset = rng.range_i64(-5, 5);
+            assert!((-5..5).contains(&offset));
+        }
+        assert_eq!(rng.range_u32(3, 3), 3);
+        assert_eq!(rng.range_i32(i32::MIN, i32::MIN + 1), i32::MIN);
+        assert!(!rng.chance(0, 10));
+        assert!(rng.chance(10, 10));
+    }
+
+    #[test]
+    fn test_weighted_index() {
+        let mut rng = GameRng::from_seed(9);
+        let mut counts = [0u32; 3];
+        for _ in 0..300 {
+            counts[rng.weighted_index(&[1, 0, 3]).unwrap()] += 1;
+        }
+        assert_eq!(counts[1], 0);
+        assert!(counts[2] > counts[0]);
+        assert_eq!(rng.weighted_index(&[0, 0]), None);
+        assert_eq!(rng.choose::<u8>(&[]), None);
+    }
+
+    #[test]
+    fn test_shuffle_is_deterministic_permutation() {
+        let env = Env::default();
+        let mut deck = [1, 2, 3, 4, 5, 6, 7, 8];
+        GameRng::from_seed(5).shuffle(&mut deck);
+        let mut sorted = deck;
+        sorted.sort();
+        assert_eq!(sorted, [1, 2, 3, 4, 5, 6, 7, 8]);
+
+        let mut vec = Vec::from_array(&env, [1u32, 2, 3, 4, 5, 6, 7, 8]);
+        GameRng::from_seed(5).shuffle_vec(&mut vec);
+        let shuffled: StdVec<u32> = vec.iter().collect();
+        assert_eq!(shuffled, deck);
+    }
+
+    #[test]
+    fn test_persists_as_world_resource() {
+        let env = Env::default();
+        let mut world = World::with_env(&env);
+        let mut rng = GameRng::from_seed(11);
+        rng.stream("spawn").next_u64();
+        world.set_typed_resource(&rng);
+
+        let mut restored = world.get_typed_resource::<GameRng>().unwrap();
+        assert_eq!(restored, rng);
+        assert_eq!(
+            restored.stream("spawn").next_u64(),
+            rng.stream("spawn").next_u64()
+        );
+
+        world.set_typed_resource(&restored);
+        assert_eq!(world.resources.len(), 1);
+    }
+
+    #[test]
+    fn test_from_prng() {
+        let env = Env::default();
+        let contract_id = env.register(RngTestContract, ());
+        let seeded = |seed: [u8; 32]| {
+            env.as_contract(&contract_id, || {
+                env.prng().seed(Bytes::from_array(&env, &seed));
+                GameRng::from_prng(&env).seed()
+            })
+        };
+        assert_eq!(seeded([1; 32]), seeded([1; 32]));
+        assert_ne!(seeded([1; 32]), seeded([2; 32]));
+    }
+}
@@ -0,0 +1,420 @@
+use super::Schedule;
+use crate::entity::EntityId;
+use crate::resource::ResourceTrait;
+use crate::world::World;
+use soroban_sdk::{symbol_short, Bytes, Env, Symbol, Vec};
+
+/// Resource limiting the work a guarded tick does in one invocation, and
+/// recording where the tick stopped so the next invocation resumes there
+///
+/// Contracts cannot read how much of the Soroban budget is left, so the guard
+/// counts work units instead: the cost of each system given to
+/// [`Schedule::add_system_with_cost`] and the per-entity cost of
+/// [`World::for_each_guarded`] batches. Calibrate them with
+/// [`Schedule::run_profiled`] so the limit stays below the transaction budget.
+///
+/// The guard lives in the world's resources, so the cursor is saved and loaded
+/// along with the world.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BudgetGuard {
+    limit: u64,
+    spent: u64,
+    next_system: u32,
+    next_entity: u32,
+    suspended: bool,
+    /// Whether the running system was the first charged in this invocation
+    leading: bool,
+}
+
+impl BudgetGuard {
+    /// Create a guard allowing `limit` work units per invocation
+    pub fn new(limit: u64) -> Self {
+        Self {
+            limit,
+            spent: 0,
+            next_system: 0,
+            next_entity: 0,
+            suspended: false,
+            leading: false,
+        }
+    }
+
+    /// Get the work units allowed per invocation
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// Get the work units spent in this invocation
+    pub fn spent(&self) -> u64 {
+        self.spent
+    }
+
+    /// Get the work units left in this invocation
+    pub fn remaining(&self) -> u64 {
+        self.limit.saturating_sub(self.spent)
+    }
+
+    /// Get the index of the system the next guarded run starts at
+    pub fn next_system(&self) -> u32 {
+        self.next_system
+    }
+
+    /// Get the offset of the entity that system resumes its batches at
+    pub fn next_entity(&self) -> u32 {
+        self.next_entity
+    }
+
+    /// Check whether the last guarded run stopped before finishing its tick
+    pub fn is_resuming(&self) -> bool {
+        self.next_system > 0 || self.next_entity > 0
+    }
+
+    /// Spend `units` if they fit in what is left, or if nothing has been spent
+    /// yet so every invocation makes progress
+    pub fn try_charge(&mut self, units: u64) -> bool {
+        if self.spent > 0 && self.spent.saturating_add(units) > self.limit {
+            return false;
+        }
+        self.spent = self.spent.saturating_add(units);
+        true
+    }
+}
+
+impl ResourceTrait for BudgetGuard {
+    fn resource_type() -> Symbol {
+        symbol_short!("tickguard")
+    }
+    fn serialize(&self, env: &Env) -> Bytes {
+        let mut bytes = Bytes::from_array(env, &self.limit.to_be_bytes());
+        bytes.extend_from_array(&self.spent.to_be_bytes());
+        bytes.extend_from_array(&self.next_system.to_be_bytes());
+        bytes.extend_from_array(&self.next_entity.to_be_bytes());
+        bytes.push_back(self.suspended as u8);
+        bytes.push_back(self.leading as u8);
+        bytes
+    }
+    fn deserialize(_env: &Env, data: &Bytes) -> Option<Self> {
+        // Guards saved before `leading` was kept are 25 bytes long
+        if data.len() != 25 && data.len() != 26 {
+            return None;
+        }
+        let mut buf = [0u8; 26];
+        data.copy_into_slice(&mut buf[..data.len() as usize]);
+        let flag = |at: usize| match buf[at] {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        };
+        let u64_at = |at: usize| u64::from_be_bytes(buf[at..at + 8].try_into().unwrap());
+        let u32_at = |at: usize| u32::from_be_bytes(buf[at..at + 4].try_into().unwrap());
+        Some(Self {
+            limit: u64_at(0),
+            spent: u64_at(8),
+            next_system: u32_at(16),
+            next_entity: u32_at(20),
+            suspended: flag(24)?,
+            leading: flag(25)?,
+        })
+    }
+}
+
+/// How a guarded run of a [`Schedule`] ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TickOutcome {
+    /// Every remaining system of the tick ran
+    Completed,
+    /// The budget ran out before `system` finished; the next guarded run
+    /// resumes it
+    Suspended { system: &'static str },
+}
+
+impl TickOutcome {
+    /// Check whether the tick finished
+    pub fn is_completed(&self) -> bool {
+        matches!(self, Self::Completed)
+    }
+}
+
+impl Schedule {
+    /// Run the systems of the current tick until the world's [`BudgetGuard`]
+    /// runs out
+    ///
+    /// The run starts at the system the previous guarded run stopped at, or at
+    /// the first one once a tick has completed. A system is only started if its
+    /// cost fits in what is left, except for the first one of an invocation.
+    /// Without a guard, every system runs.
+    pub fn run_guarded(&mut self, world: &mut World) -> TickOutcome {
+        let Some(mut guard) = world.get_typed_resource::<BudgetGuard>() else {
+            self.run(world);
+            return TickOutcome::Completed;
+        };
+        guard.spent = 0;
+        guard.suspended = false;
+
+        let start = (guard.next_system as usize).min(self.systems.len());
+        for (index, system) in self.systems.iter_mut().enumerate().skip(start) {
+            guard.next_system = index as u32;
+            guard.leading = guard.spent == 0;
+            if !guard.try_charge(system.cost) {
+                world.set_typed_resource(&guard);
+                return TickOutcome::Suspended {
+                    system: system.name,
+                };
+            }
+            world.set_typed_resource(&guard);
+            (system.run)(world);
+            guard = world.get_typed_resource().unwrap_or(guard);
+            if guard.suspended {
+                return TickOutcome::Suspended {
+                    system: system.name,
+                };
+            }
+            guard.next_entity = 0;
+        }
+
+        guard.next_system = 0;
+        world.set_typed_resource(&guard);
+        TickOutcome::Completed
+    }
+}
+
+impl World {
+    /// Call `f` on `entities` in batches of `batch_size`, charging
+    /// `cost_per_entity` work units per entity to the [`BudgetGuard`]
+    ///
+    /// Stops before a batch that does not fit in what is left and records the
+    /// offset to resume at, so the next guarded run of the schedule picks the
+    /// system up where it stopped. The first batch always runs in the first
+    /// system of an invocation, or when nothing has been spent yet, so every
+    /// invocation makes progress; after earlier systems it is checked too.
+    /// `entities` must list the same entities in the same order on every
+    /// invocation, as [`World::query_entities`] does while no entity is
+    /// spawned or despawned, and a system should make at most one guarded
+    /// loop. Returns `false` if the loop stopped early; without a guard, every
+    /// entity is visited.
+    pub fn for_each_guarded(
+        &mut self,
+        entities: &Vec<EntityId>,
+        batch_size: u32,
+        cost_per_entity: u64,
+        mut f: impl FnMut(&mut World, EntityId),
+    ) -> bool {
+        let Some(mut guard) = self.get_typed_resource::<BudgetGuard>() else {
+            for entity_id in entities.iter() {
+                f(self, entity_id);
+            }
+            return true;
+        };
+        let batch_size = batch_size.max(1);
+        let mut offset = guard.next_entity.min(entities.len());
+        let mut first = true;
+        while offset < entities.len() {
+            let end = offset.saturating_add(batch_size).min(entities.len());
+            let cost = u64::from(end - offset).saturating_mul(cost_per_entity);
+            if first && (guard.leading || guard.spent == 0) {
+                guard.spent = guard.spent.saturating_add(cost);
+            } else if !guard.try_charge(cost) {
+                guard.next_entity = offset;
+                guard.suspended = true;
+                self.set_typed_resource(&guard);
+                return false;
+            }
+            first = false;
+            for index in offset..end {
+                f(self, entities.get_unchecked(index));
+            }
+            offset = end;
+        }
+        guard.next_entity = 0;
+        self.set_typed_resource(&guard);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::ComponentTrait;
+    use crate::health::Health;
+    use crate::persistence::WorldStore;
+    use crate::resource::GameState;
+    use crate::system::IntoSystem;
+    use soroban_sdk::contract;
+
+    #[contract]
+    struct GuardTestContract;
+
+    fn score(world: &World) -> i32 {
+        world.get_typed_resource::<GameState>().unwrap().score
+    }
+
+    fn scoring_schedule() -> Schedule {
+        let mut schedule = Schedule::new();
+        for (name, points) in [("ones", 1), ("tens", 10), ("hundreds", 100)] {
+            let add = move |world: &mut World, _: ()| {
+                let mut state = world.get_typed_resource::<GameState>().unwrap();
+                state.increment_score(points);
+                world.set_typed_resource(&state);
+            };
+            schedule.add_system_with_cost(name, 4, add.into_system());
+        }
+        schedule
+    }
+
+    #[test]
+    fn test_guard_roundtrip() {
+        let env = Env::default();
+        let mut guard = BudgetGuard::new(500);
+        assert!(guard.try_charge(600));
+        assert!(!guard.try_charge(1));
+        guard.next_system = 2;
+        guard.next_entity = 7;
+        assert_eq!(
+            BudgetGuard::deserialize(&env, &guard.serialize(&env)),
+            Some(guard)
+        );
+        assert_eq!(BudgetGuard::deserialize(&env, &Bytes::new(&env)), None);
+    }
+
+    #[test]
+    fn test_batches_after_a_spent_budget_wait() {
+        let mut world = World::new();
+        for _ in 0..50 {
+            let entity_id = world.spawn_empty().id();
+            world.add_typed_component(entity_id, &Health(1));
+        }
+        world.set_typed_resource(&BudgetGuard::new(10));
+        let mut schedule = Schedule::new();
+        schedule.add_system_with_cost("plan", 10, (|_: &mut World, _: ()| {}).into_system());
+        let regenerate = |world: &mut World, _: ()| {
+            let entities = world.query_entities(&[Health::component_type()]);
+            world.for_each_guarded(&entities, 50, 1, |world, entity_id| {
+                world.add_typed_component(entity_id, &Health(2));
+            });
+        };
+        schedule.add_system("regenerate", regenerate.into_system());
+
+        // The first system used the whole budget, so the loop waits
+        assert_eq!(
+            schedule.run_guarded(&mut world),
+            TickOutcome::Suspended {
+                system: "regenerate"
+            }
+        );
+        let guard = world.get_typed_resource::<BudgetGuard>().unwrap();
+        assert_eq!((guard.spent(), guard.next_system()), (10, 1));
+        let healed = |world: &World| {
+            world
+                .query_entities(&[Health::component_type()])
+                .iter()
+                .filter(|entity_id| {
+                    world.get_typed_component::<Health>(*entity_id) == Some(Health(2))
+                })
+                .count()
+        };
+        assert_eq!(healed(&world), 0);
+
+        // and runs first in the next invocation
+        assert!(schedule.run_guarded(&mut world).is_completed());
+        assert_eq!(healed(&world), 50);
+    }
+
+    #[test]
+    fn test_run_guarded_stops_between_systems() {
+        let mut world = World::new();
+        world.set_typed_resource(&GameState::new());
+        let mut schedule = scoring_schedule();
+
+        // Without a guard, the whole tick runs
+        assert_eq!(schedule.run_guarded(&mut world), TickOutcome::Completed);
+        assert_eq!(score(&world), 111);
+
+        world.set_typed_resource(&BudgetGuard::new(10));
+        assert_eq!(
+            schedule.run_guarded(&mut world),
+            TickOutcome::Suspended { system: "hundreds" }
+        );
+        assert_eq!(score(&world), 122);
+        let guard = world.get_typed_resource::<BudgetGuard>().unwrap();
+        assert_eq!((guard.next_system(), guard.spent()), (2, 8));
+        assert!(guard.is_resuming());
+
+        assert!(schedule.run_guarded(&mut world).is_completed());
+        assert_eq!(score(&world), 222);
+        assert!(!world
+            .get_typed_resource::<BudgetGuard>()
+            .unwrap()
+            .is_resuming());
+
+        // A guard too small for any system still runs one per invocation
+        world.set_typed_resource(&BudgetGuard::new(1));
+        for _ in 0..2 {
+            assert!(!schedule.run_guarded(&mut world).is_completed());
+        }
+        assert!(schedule.run_guarded(&mut world).is_completed());
+        assert_eq!(score(&world), 333);
+    }
+
+    #[test]
+    fn test_batches_resume_across_invocations() {
+        let env = Env::default();
+        let contract_id = env.register(GuardTestContract, ());
+        let name = symbol_short!("horde");
+        env.as_contract(&contract_id, || {
+            let mut world = World::with_env(&env);
+            for _ in 0..10 {
+                let entity_id = world.spawn_empty().id();
+                world.add_typed_component(entity_id, &Health(1));
+            }
+            world.set_typed_resource(&BudgetGuard::new(4));
+            WorldStore::new(&env, name.clone()).save(&world);
+        });
+
+        let regenerate = |world: &mut World, _: ()| {
+            let entities = world.query_entities(&[Health::component_type()]);
+            world.for_each_guarded(&entities, 3, 1, |world, entity_id| {
+                let health = world.get_typed_component::<Health>(entity_id).unwrap();
+                world.add_typed_component(entity_id, &Health(health.0 + 1));
+            });
+        };
+        let mut outcomes = alloc::vec::Vec::new();
+        for _ in 0..5 {
+            env.as_contract(&contract_id, || {
+                // Each invocation loads the world, runs the tick and saves it
+                let mut store = WorldStore::new(&env, name.clone());
+                let mut world = World::with_env(&env);
+                assert!(store.load(&mut world));
+                let mut schedule = Schedule::new();
+                schedule.add_system("regenerate", regenerate.into_system());
+                outcomes.push(schedule.run_guarded(&mut world));
+                store.save(&world);
+            });
+        }
+        let suspended = TickOutcome::Suspended {
+            system: "regenerate",
+        };
+        // Batches of 3, 3 and 3 + 1 finish the first tick
+        assert_eq!(
+            outcomes,
+            [
+                suspended,
+                suspended,
+                TickOutcome::Completed,
+                suspended,
+                suspended
+            ]
+        );
+
+        env.as_contract(&contract_id, || {
+            let mut world = World::with_env(&env);
+            assert!(WorldStore::new(&env, name.clone()).load(&mut world));
+            let healths: alloc::vec::Vec<i32> = world
+                .query_entities(&[Health::component_type()])
+                .iter()
+                .map(|entity_id| world.get_typed_component::<Health>(entity_id).unwrap().0)
+                .collect();
+            // One full tick, then two batches of the next one
+            assert_eq!(healths, [3, 3, 3, 3, 3, 3, 2, 2, 2, 2]);
+        });
+    }
+}
@@ -1,1287 +1,329 @@
-//! Contains APIs for ordering systems and executing them on a [`World`](crate::world::World)
-
-mod auto_insert_apply_deferred;
-mod condition;
-mod config;
-mod executor;
-mod pass;
-mod schedule;
-mod set;
-mod stepping;
-
-use self::graph::*;
-pub use self::{condition::*, config::*, executor::*, schedule::*, set::*};
-pub use pass::ScheduleBuildPass;
-
-pub use self::graph::NodeId;
-
-/// An implementation of a graph data structure.
-pub mod graph;
-
-/// Included optional schedule build passes.
-pub mod passes {
-    pub use crate::schedule::auto_insert_apply_deferred::*;
+//! Ordered lists of systems run against a [`World`].
+//!
+//! A [`Schedule`] runs its systems one after the other, in the order they were
+//! added. In tests, [`Schedule::run_profiled`] also measures the CPU
+//! instructions and memory bytes each system charges to the Soroban budget, so
+//! the system burning the budget can be found and held to a
+//! [`SystemBudget`].
+//!
+//! [`Schedule::run_guarded`] instead stops between systems, or between entity
+//! batches, before a [`BudgetGuard`] runs out, so a tick too big for one
+//! transaction is finished over several invocations.
+
+use crate::system::System;
+use crate::world::World;
+use alloc::boxed::Box;
+use alloc::vec::Vec as StdVec;
+
+mod guard;
+
+pub use guard::{BudgetGuard, TickOutcome};
+
+struct ScheduledSystem {
+    name: &'static str,
+    /// Work units charged to a [`BudgetGuard`] before each run
+    cost: u64,
+    run: Box<dyn FnMut(&mut World)>,
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    #[cfg(feature = "trace")]
-    use alloc::string::ToString;
-    use alloc::{vec, vec::Vec};
-    use core::sync::atomic::{AtomicU32, Ordering};
-
-    pub use crate::{
-        prelude::World,
-        resource::Resource,
-        schedule::{Schedule, SystemSet},
-        system::{Res, ResMut},
-    };
+/// Systems run in order against a [`World`]
+#[derive(Default)]
+pub struct Schedule {
+    systems: StdVec<ScheduledSystem>,
+}
 
-    #[derive(SystemSet, Clone, Debug, PartialEq, Eq, Hash)]
-    enum TestSystems {
-        A,
-        B,
-        C,
-        D,
-        X,
+impl Schedule {
+    /// Create an empty schedule
+    pub fn new() -> Self {
+        Self::default()
     }
 
-    #[derive(Resource, Default)]
-    struct SystemOrder(Vec<u32>);
-
-    #[derive(Resource, Default)]
-    struct RunConditionBool(bool);
-
-    #[derive(Resource, Default)]
-    struct Counter(AtomicU32);
-
-    fn make_exclusive_system(tag: u32) -> impl FnMut(&mut World) {
-        move |world| world.resource_mut::<SystemOrder>().0.push(tag)
+    /// Add `system` under `name`, to run after the systems already added
+    ///
+    /// The system's output is dropped; systems whose output matters can be run
+    /// by hand.
+    pub fn add_system<S>(&mut self, name: &'static str, system: S) -> &mut Self
+    where
+        S: System<In = ()> + 'static,
+    {
+        self.add_system_with_cost(name, 0, system)
     }
 
-    fn make_function_system(tag: u32) -> impl FnMut(ResMut<SystemOrder>) {
-        move |mut resource: ResMut<SystemOrder>| resource.0.push(tag)
+    /// Add `system` under `name`, charging `cost` work units to the
+    /// [`BudgetGuard`] before each guarded run
+    pub fn add_system_with_cost<S>(
+        &mut self,
+        name: &'static str,
+        cost: u64,
+        mut system: S,
+    ) -> &mut Self
+    where
+        S: System<In = ()> + 'static,
+    {
+        self.systems.push(ScheduledSystem {
+            name,
+            cost,
+            run: Box::new(move |world| {
+                system.run(world, ());
+            }),
+        });
+        self
     }
 
-    fn named_system(mut resource: ResMut<SystemOrder>) {
-        resource.0.push(u32::MAX);
+    /// Add `system` under `name` and return the schedule
+    pub fn with_system<S>(mut self, name: &'static str, system: S) -> Self
+    where
+        S: System<In = ()> + 'static,
+    {
+        self.add_system(name, system);
+        self
     }
 
-    fn named_exclusive_system(world: &mut World) {
-        world.resource_mut::<SystemOrder>().0.push(u32::MAX);
+    /// Get the names of the systems, in the order they run
+    pub fn system_names(&self) -> StdVec<&'static str> {
+        self.systems.iter().map(|system| system.name).collect()
     }
 
-    fn counting_system(counter: Res<Counter>) {
-        counter.0.fetch_add(1, Ordering::Relaxed);
+    /// Get the number of systems
+    pub fn len(&self) -> usize {
+        self.systems.len()
     }
 
-    mod system_execution {
-        use super::*;
-
-        #[test]
-        fn run_system() {
-            let mut world = World::default();
-            let mut schedule = Schedule::default();
-
-            world.init_resource::<SystemOrder>();
-
-            schedule.add_systems(make_function_system(0));
-            schedule.run(&mut world);
-
-            assert_eq!(world.resource::<SystemOrder>().0, vec![0]);
-        }
-
-        #[test]
-        fn run_exclusive_system() {
-            let mut world = World::default();
-            let mut schedule = Schedule::default();
-
-            world.init_resource::<SystemOrder>();
-
-            schedule.add_systems(make_exclusive_system(0));
-            schedule.run(&mut world);
-
-            assert_eq!(world.resource::<SystemOrder>().0, vec![0]);
-        }
-
-        #[test]
-        #[cfg(not(miri))]
-        fn parallel_execution() {
-            use alloc::sync::Arc;
-            use bevy_tasks::{ComputeTaskPool, TaskPool};
-            use std::sync::Barrier;
-
-            let mut world = World::default();
-            let mut schedule = Schedule::default();
-            let thread_count = ComputeTaskPool::get_or_init(TaskPool::default).thread_num();
-
-            let barrier = Arc::new(Barrier::new(thread_count));
-
-            for _ in 0..thread_count {
-                let inner = barrier.clone();
-                schedule.add_systems(move || {
-                    inner.wait();
-                });
-            }
+    /// Check whether the schedule has no systems
+    pub fn is_empty(&self) -> bool {
+        self.systems.is_empty()
+    }
 
-            schedule.run(&mut world);
+    /// Run every system once, in order
+    pub fn run(&mut self, world: &mut World) {
+        for system in self.systems.iter_mut() {
+            (system.run)(world);
         }
     }
 
-    mod system_ordering {
-        use super::*;
-
-        #[test]
-        fn order_systems() {
-            let mut world = World::default();
-            let mut schedule = Schedule::default();
-
-            world.init_resource::<SystemOrder>();
-
-            schedule.add_systems((
-                named_system,
-                make_function_system(1).before(named_system),
-                make_function_system(0)
-                    .after(named_system)
-                    .in_set(TestSystems::A),
-            ));
-            schedule.run(&mut world);
-
-            assert_eq!(world.resource::<SystemOrder>().0, vec![1, u32::MAX, 0]);
-
-            world.insert_resource(SystemOrder::default());
-
-            assert_eq!(world.resource::<SystemOrder>().0, vec![]);
-
-            // modify the schedule after it's been initialized and test ordering with sets
-            schedule.configure_sets(TestSystems::A.after(named_system));
-            schedule.add_systems((
-                make_function_system(3)
-                    .before(TestSystems::A)
-                    .after(named_system),
-                make_function_system(4).after(TestSystems::A),
-            ));
-            schedule.run(&mut world);
-
-            assert_eq!(
-                world.resource::<SystemOrder>().0,
-                vec![1, u32::MAX, 3, 0, 4]
-            );
+    /// Run every system once, in order, measuring what each charges to the
+    /// budget of the world's environment
+    #[cfg(any(test, feature = "testutils"))]
+    pub fn run_profiled(&mut self, world: &mut World) -> ScheduleProfile {
+        let budget = world.env().cost_estimate().budget();
+        let mut costs = StdVec::with_capacity(self.systems.len());
+        for system in self.systems.iter_mut() {
+            let (cpu_before, memory_before) =
+                (budget.cpu_instruction_cost(), budget.memory_bytes_cost());
+            (system.run)(world);
+            costs.push(SystemCost {
+                name: system.name,
+                cpu_instructions: budget.cpu_instruction_cost().saturating_sub(cpu_before),
+                memory_bytes: budget.memory_bytes_cost().saturating_sub(memory_before),
+            });
         }
+        ScheduleProfile { costs }
+    }
+}
 
-        #[test]
-        fn order_exclusive_systems() {
-            let mut world = World::default();
-            let mut schedule = Schedule::default();
-
-            world.init_resource::<SystemOrder>();
-
-            schedule.add_systems((
-                named_exclusive_system,
-                make_exclusive_system(1).before(named_exclusive_system),
-                make_exclusive_system(0).after(named_exclusive_system),
-            ));
-            schedule.run(&mut world);
+/// Budget a system is allowed to use in one run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SystemBudget {
+    pub cpu_instructions: u64,
+    pub memory_bytes: u64,
+}
 
-            assert_eq!(world.resource::<SystemOrder>().0, vec![1, u32::MAX, 0]);
+impl SystemBudget {
+    pub fn new(cpu_instructions: u64, memory_bytes: u64) -> Self {
+        Self {
+            cpu_instructions,
+            memory_bytes,
         }
+    }
+}
 
-        #[test]
-        fn add_systems_correct_order() {
-            let mut world = World::new();
-            let mut schedule = Schedule::default();
-
-            world.init_resource::<SystemOrder>();
-
-            schedule.add_systems(
-                (
-                    make_function_system(0),
-                    make_function_system(1),
-                    make_exclusive_system(2),
-                    make_function_system(3),
-                )
-                    .chain(),
-            );
+/// Budget used by one system in a profiled run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SystemCost {
+    pub name: &'static str,
+    pub cpu_instructions: u64,
+    pub memory_bytes: u64,
+}
 
-            schedule.run(&mut world);
-            assert_eq!(world.resource::<SystemOrder>().0, vec![0, 1, 2, 3]);
-        }
+/// Budget used by each system of a schedule in a profiled run
+///
+/// Displays as a table with one row per system, in the order they ran.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduleProfile {
+    costs: StdVec<SystemCost>,
+}
 
-        #[test]
-        fn add_systems_correct_order_nested() {
-            let mut world = World::new();
-            let mut schedule = Schedule::default();
+impl ScheduleProfile {
+    /// Get the cost of each system, in the order they ran
+    pub fn costs(&self) -> &[SystemCost] {
+        &self.costs
+    }
 
-            world.init_resource::<SystemOrder>();
+    /// Get the cost of the system named `name`
+    pub fn get(&self, name: &str) -> Option<&SystemCost> {
+        self.costs.iter().find(|cost| cost.name == name)
+    }
 
-            schedule.add_systems(
-                (
-                    (make_function_system(0), make_function_system(1)).chain(),
-                    make_function_system(2),
-                    (make_function_system(3), make_function_system(4)).chain(),
-                    (
-                        make_function_system(5),
-                        (make_function_system(6), make_function_system(7)),
-                    ),
-                    (
-                        (make_function_system(8), make_function_system(9)).chain(),
-                        make_function_system(10),
-                    ),
-                )
-                    .chain(),
-            );
+    /// Get the CPU instructions used by all systems
+    pub fn total_cpu_instructions(&self) -> u64 {
+        self.costs.iter().map(|cost| cost.cpu_instructions).sum()
+    }
 
-            schedule.run(&mut world);
-            let order = &world.resource::<SystemOrder>().0;
-            assert_eq!(
-                &order[0..5],
-                &[0, 1, 2, 3, 4],
-                "first five items should be exactly ordered"
-            );
-            let unordered = &order[5..8];
-            assert!(
-                unordered.contains(&5) && unordered.contains(&6) && unordered.contains(&7),
-                "unordered must be 5, 6, and 7 in any order"
-            );
-            let partially_ordered = &order[8..11];
-            assert!(
-                partially_ordered == [8, 9, 10] || partially_ordered == [10, 8, 9],
-                "partially_ordered must be [8, 9, 10] or [10, 8, 9]"
-            );
-            assert_eq!(order.len(), 11, "must have exactly 11 order entries");
-        }
+    /// Get the memory bytes used by all systems
+    pub fn total_memory_bytes(&self) -> u64 {
+        self.costs.iter().map(|cost| cost.memory_bytes).sum()
     }
 
-    mod conditions {
+    /// Get the system that used the most CPU instructions
+    pub fn most_expensive(&self) -> Option<&SystemCost> {
+        self.costs.iter().max_by_key(|cost| cost.cpu_instructions)
+    }
 
-        use crate::{
-            change_detection::DetectChanges,
-            error::{ignore, DefaultErrorHandler, Result},
+    /// Panic, printing the profile, if the system named `name` did not run or
+    /// used more than `budget`
+    #[track_caller]
+    pub fn assert_within(&self, name: &str, budget: SystemBudget) {
+        let Some(cost) = self.get(name) else {
+            panic!("system `{name}` did not run\n{self}");
         };
-
-        use super::*;
-
-        #[test]
-        fn system_with_condition_bool() {
-            let mut world = World::default();
-            let mut schedule = Schedule::default();
-
-            world.init_resource::<RunConditionBool>();
-            world.init_resource::<SystemOrder>();
-
-            schedule.add_systems(
-                make_function_system(0).run_if(|condition: Res<RunConditionBool>| condition.0),
-            );
-
-            schedule.run(&mut world);
-            assert_eq!(world.resource::<SystemOrder>().0, vec![]);
-
-            world.resource_mut::<RunConditionBool>().0 = true;
-            schedule.run(&mut world);
-            assert_eq!(world.resource::<SystemOrder>().0, vec![0]);
-        }
-
-        #[test]
-        fn system_with_condition_result_bool() {
-            let mut world = World::default();
-            world.insert_resource(DefaultErrorHandler(ignore));
-            let mut schedule = Schedule::default();
-
-            world.init_resource::<SystemOrder>();
-
-            schedule.add_systems((
-                make_function_system(0).run_if(|| -> Result<bool> { Err(core::fmt::Error.into()) }),
-                make_function_system(1).run_if(|| -> Result<bool> { Ok(false) }),
-            ));
-
-            schedule.run(&mut world);
-            assert_eq!(world.resource::<SystemOrder>().0, vec![]);
-
-            schedule.add_systems(make_function_system(2).run_if(|| -> Result<bool> { Ok(true) }));
-
-            schedule.run(&mut world);
-            assert_eq!(world.resource::<SystemOrder>().0, vec![2]);
-        }
-
-        #[test]
-        fn systems_with_distributive_condition() {
-            let mut world = World::default();
-            let mut schedule = Schedule::default();
-
-            world.insert_resource(RunConditionBool(true));
-            world.init_resource::<SystemOrder>();
-
-            fn change_condition(mut condition: ResMut<RunConditionBool>) {
-                condition.0 = false;
-            }
-
-            schedule.add_systems(
-                (
-                    make_function_system(0),
-                    change_condition,
-                    make_function_system(1),
-                )
-                    .chain()
-                    .distributive_run_if(|condition: Res<RunConditionBool>| condition.0),
-            );
-
-            schedule.run(&mut world);
-            assert_eq!(world.resource::<SystemOrder>().0, vec![0]);
-        }
-
-        #[test]
-        fn run_exclusive_system_with_condition() {
-            let mut world = World::default();
-            let mut schedule = Schedule::default();
-
-            world.init_resource::<RunConditionBool>();
-            world.init_resource::<SystemOrder>();
-
-            schedule.add_systems(
-                make_exclusive_system(0).run_if(|condition: Res<RunConditionBool>| condition.0),
+        if cost.cpu_instructions > budget.cpu_instructions
+            || cost.memory_bytes > budget.memory_bytes
+        {
+            panic!(
+                "system `{name}` used {} CPU instructions and {} memory bytes, over its budget of {} and {}\n{self}",
+                cost.cpu_instructions, cost.memory_bytes, budget.cpu_instructions, budget.memory_bytes,
             );
-
-            schedule.run(&mut world);
-            assert_eq!(world.resource::<SystemOrder>().0, vec![]);
-
-            world.resource_mut::<RunConditionBool>().0 = true;
-            schedule.run(&mut world);
-            assert_eq!(world.resource::<SystemOrder>().0, vec![0]);
-        }
-
-        #[test]
-        fn multiple_conditions_on_system() {
-            let mut world = World::default();
-            let mut schedule = Schedule::default();
-
-            world.init_resource::<Counter>();
-
-            schedule.add_systems((
-                counting_system.run_if(|| false).run_if(|| false),
-                counting_system.run_if(|| true).run_if(|| false),
-                counting_system.run_if(|| false).run_if(|| true),
-                counting_system.run_if(|| true).run_if(|| true),
-            ));
-
-            schedule.run(&mut world);
-            assert_eq!(world.resource::<Counter>().0.load(Ordering::Relaxed), 1);
-        }
-
-        #[test]
-        fn multiple_conditions_on_system_sets() {
-            let mut world = World::default();
-            let mut schedule = Schedule::default();
-
-            world.init_resource::<Counter>();
-
-            schedule.configure_sets(TestSystems::A.run_if(|| false).run_if(|| false));
-            schedule.add_systems(counting_system.in_set(TestSystems::A));
-            schedule.configure_sets(TestSystems::B.run_if(|| true).run_if(|| false));
-            schedule.add_systems(counting_system.in_set(TestSystems::B));
-            schedule.configure_sets(TestSystems::C.run_if(|| false).run_if(|| true));
-            schedule.add_systems(counting_system.in_set(TestSystems::C));
-            schedule.configure_sets(TestSystems::D.run_if(|| true).run_if(|| true));
-            schedule.add_systems(counting_system.in_set(TestSystems::D));
-
-            schedule.run(&mut world);
-            assert_eq!(world.resource::<Counter>().0.load(Ordering::Relaxed), 1);
-        }
-
-        #[test]
-        fn systems_nested_in_system_sets() {
-            let mut world = World::default();
-            let mut schedule = Schedule::default();
-
-            world.init_resource::<Counter>();
-
-            schedule.configure_sets(TestSystems::A.run_if(|| false));
-            schedule.add_systems(counting_system.in_set(TestSystems::A).run_if(|| false));
-            schedule.configure_sets(TestSystems::B.run_if(|| true));
-            schedule.add_systems(counting_system.in_set(TestSystems::B).run_if(|| false));
-            schedule.configure_sets(TestSystems::C.run_if(|| false));
-            schedule.add_systems(counting_system.in_set(TestSystems::C).run_if(|| true));
-            schedule.configure_sets(TestSystems::D.run_if(|| true));
-            schedule.add_systems(counting_system.in_set(TestSystems::D).run_if(|| true));
-
-            schedule.run(&mut world);
-            assert_eq!(world.resource::<Counter>().0.load(Ordering::Relaxed), 1);
-        }
-
-        #[test]
-        fn system_conditions_and_change_detection() {
-            #[derive(Resource, Default)]
-            struct Bool2(pub bool);
-
-            let mut world = World::default();
-            world.init_resource::<Counter>();
-            world.init_resource::<RunConditionBool>();
-            world.init_resource::<Bool2>();
-            let mut schedule = Schedule::default();
-
-            schedule.add_systems(
-                counting_system
-                    .run_if(|res1: Res<RunConditionBool>| res1.is_changed())
-                    .run_if(|res2: Res<Bool2>| res2.is_changed()),
-            );
-
-            // both resource were just added.
-            schedule.run(&mut world);
-            assert_eq!(world.resource::<Counter>().0.load(Ordering::Relaxed), 1);
-
-            // nothing has changed
-            schedule.run(&mut world);
-            assert_eq!(world.resource::<Counter>().0.load(Ordering::Relaxed), 1);
-
-            // RunConditionBool has changed, but counting_system did not run
-            world.get_resource_mut::<RunConditionBool>().unwrap().0 = false;
-            schedule.run(&mut world);
-            assert_eq!(world.resource::<Counter>().0.load(Ordering::Relaxed), 1);
-
-            // internal state for the bool2 run criteria was updated in the
-            // previous run, so system still does not run
-            world.get_resource_mut::<Bool2>().unwrap().0 = false;
-            schedule.run(&mut world);
-            assert_eq!(world.resource::<Counter>().0.load(Ordering::Relaxed), 1);
-
-            // internal state for bool2 was updated, so system still does not run
-            world.get_resource_mut::<RunConditionBool>().unwrap().0 = false;
-            schedule.run(&mut world);
-            assert_eq!(world.resource::<Counter>().0.load(Ordering::Relaxed), 1);
-
-            // now check that it works correctly changing Bool2 first and then RunConditionBool
-            world.get_resource_mut::<Bool2>().unwrap().0 = false;
-            world.get_resource_mut::<RunConditionBool>().unwrap().0 = false;
-            schedule.run(&mut world);
-            assert_eq!(world.resource::<Counter>().0.load(Ordering::Relaxed), 2);
-        }
-
-        #[test]
-        fn system_set_conditions_and_change_detection() {
-            #[derive(Resource, Default)]
-            struct Bool2(pub bool);
-
-            let mut world = World::default();
-            world.init_resource::<Counter>();
-            world.init_resource::<RunConditionBool>();
-            world.init_resource::<Bool2>();
-            let mut schedule = Schedule::default();
-
-            schedule.configure_sets(
-                TestSystems::A
-                    .run_if(|res1: Res<RunConditionBool>| res1.is_changed())
-                    .run_if(|res2: Res<Bool2>| res2.is_changed()),
-            );
-
-            schedule.add_systems(counting_system.in_set(TestSystems::A));
-
-            // both resource were just added.
-            schedule.run(&mut world);
-            assert_eq!(world.resource::<Counter>().0.load(Ordering::Relaxed), 1);
-
-            // nothing has changed
-            schedule.run(&mut world);
-            assert_eq!(world.resource::<Counter>().0.load(Ordering::Relaxed), 1);
-
-            // RunConditionBool has changed, but counting_system did not run
-            world.get_resource_mut::<RunConditionBool>().unwrap().0 = false;
-            schedule.run(&mut world);
-            assert_eq!(world.resource::<Counter>().0.load(Ordering::Relaxed), 1);
-
-            // internal state for the bool2 run criteria was updated in the
-            // previous run, so system still does not run
-            world.get_resource_mut::<Bool2>().unwrap().0 = false;
-            schedule.run(&mut world);
-            assert_eq!(world.resource::<Counter>().0.load(Ordering::Relaxed), 1);
-
-            // internal state for bool2 was updated, so system still does not run
-            world.get_resource_mut::<RunConditionBool>().unwrap().0 = false;
-            schedule.run(&mut world);
-            assert_eq!(world.resource::<Counter>().0.load(Ordering::Relaxed), 1);
-
-            // the system only runs when both are changed on the same run
-            world.get_resource_mut::<Bool2>().unwrap().0 = false;
-            world.get_resource_mut::<RunConditionBool>().unwrap().0 = false;
-            schedule.run(&mut world);
-            assert_eq!(world.resource::<Counter>().0.load(Ordering::Relaxed), 2);
-        }
-
-        #[test]
-        fn mixed_conditions_and_change_detection() {
-            #[derive(Resource, Default)]
-            struct Bool2(pub bool);
-
-            let mut world = World::default();
-            world.init_resource::<Counter>();
-            world.init_resource::<RunConditionBool>();
-            world.init_resource::<Bool2>();
-            let mut schedule = Schedule::default();
-
-            schedule.configure_sets(
-                TestSystems::A.run_if(|res1: Res<RunConditionBool>| res1.is_changed()),
-            );
-
-            schedule.add_systems(
-                counting_system
-                    .run_if(|res2: Res<Bool2>| res2.is_changed())
-                    .in_set(TestSystems::A),
-            );
-
-            // both resource were just added.
-            schedule.run(&mut world);
-            assert_eq!(world.resource::<Counter>().0.load(Ordering::Relaxed), 1);
-
-            // nothing has changed
-            schedule.run(&mut world);
-            assert_eq!(world.resource::<Counter>().0.load(Ordering::Relaxed), 1);
-
-            // RunConditionBool has changed, but counting_system did not run
-            world.get_resource_mut::<RunConditionBool>().unwrap().0 = false;
-            schedule.run(&mut world);
-            assert_eq!(world.resource::<Counter>().0.load(Ordering::Relaxed), 1);
-
-            // we now only change bool2 and the system also should not run
-            world.get_resource_mut::<Bool2>().unwrap().0 = false;
-            schedule.run(&mut world);
-            assert_eq!(world.resource::<Counter>().0.load(Ordering::Relaxed), 1);
-
-            // internal state for the bool2 run criteria was updated in the
-            // previous run, so system still does not run
-            world.get_resource_mut::<RunConditionBool>().unwrap().0 = false;
-            schedule.run(&mut world);
-            assert_eq!(world.resource::<Counter>().0.load(Ordering::Relaxed), 1);
-
-            // the system only runs when both are changed on the same run
-            world.get_resource_mut::<Bool2>().unwrap().0 = false;
-            world.get_resource_mut::<RunConditionBool>().unwrap().0 = false;
-            schedule.run(&mut world);
-            assert_eq!(world.resource::<Counter>().0.load(Ordering::Relaxed), 2);
         }
     }
+}
 
-    mod schedule_build_errors {
-        use super::*;
-
-        #[test]
-        #[should_panic]
-        fn dependency_loop() {
-            let mut schedule = Schedule::default();
-            schedule.configure_sets(TestSystems::X.after(TestSystems::X));
-        }
-
-        #[test]
-        fn dependency_cycle() {
-            let mut world = World::new();
-            let mut schedule = Schedule::default();
-
-            schedule.configure_sets(TestSystems::A.after(TestSystems::B));
-            schedule.configure_sets(TestSystems::B.after(TestSystems::A));
-
-            let result = schedule.initialize(&mut world);
-            assert!(matches!(
-                result,
-                Err(ScheduleBuildError::DependencyCycle(_))
-            ));
-
-            fn foo() {}
-            fn bar() {}
-
-            let mut world = World::new();
-            let mut schedule = Schedule::default();
-
-            schedule.add_systems((foo.after(bar), bar.after(foo)));
-            let result = schedule.initialize(&mut world);
-            assert!(matches!(
-                result,
-                Err(ScheduleBuildError::DependencyCycle(_))
-            ));
-        }
-
-        #[test]
-        #[should_panic]
-        fn hierarchy_loop() {
-            let mut schedule = Schedule::default();
-            schedule.configure_sets(TestSystems::X.in_set(TestSystems::X));
-        }
-
-        #[test]
-        fn hierarchy_cycle() {
-            let mut world = World::new();
-            let mut schedule = Schedule::default();
-
-            schedule.configure_sets(TestSystems::A.in_set(TestSystems::B));
-            schedule.configure_sets(TestSystems::B.in_set(TestSystems::A));
-
-            let result = schedule.initialize(&mut world);
-            assert!(matches!(result, Err(ScheduleBuildError::HierarchyCycle(_))));
-        }
-
-        #[test]
-        fn system_type_set_ambiguity() {
-            // Define some systems.
-            fn foo() {}
-            fn bar() {}
-
-            let mut world = World::new();
-            let mut schedule = Schedule::default();
-
-            // Schedule `bar` to run after `foo`.
-            schedule.add_systems((foo, bar.after(foo)));
-
-            // There's only one `foo`, so it's fine.
-            let result = schedule.initialize(&mut world);
-            assert!(result.is_ok());
-
-            // Schedule another `foo`.
-            schedule.add_systems(foo);
-
-            // When there are multiple instances of `foo`, dependencies on
-            // `foo` are no longer allowed. Too much ambiguity.
-            let result = schedule.initialize(&mut world);
-            assert!(matches!(
-                result,
-                Err(ScheduleBuildError::SystemTypeSetAmbiguity(_))
-            ));
-
-            // same goes for `ambiguous_with`
-            let mut schedule = Schedule::default();
-            schedule.add_systems(foo);
-            schedule.add_systems(bar.ambiguous_with(foo));
-            let result = schedule.initialize(&mut world);
-            assert!(result.is_ok());
-            schedule.add_systems(foo);
-            let result = schedule.initialize(&mut world);
-            assert!(matches!(
-                result,
-                Err(ScheduleBuildError::SystemTypeSetAmbiguity(_))
-            ));
-        }
-
-        #[test]
-        #[should_panic]
-        fn configure_system_type_set() {
-            fn foo() {}
-            let mut schedule = Schedule::default();
-            schedule.configure_sets(foo.into_system_set());
-        }
-
-        #[test]
-        fn hierarchy_redundancy() {
-            let mut world = World::new();
-            let mut schedule = Schedule::default();
-
-            schedule.set_build_settings(ScheduleBuildSettings {
-                hierarchy_detection: LogLevel::Error,
-                ..Default::default()
-            });
-
-            // Add `A`.
-            schedule.configure_sets(TestSystems::A);
-
-            // Add `B` as child of `A`.
-            schedule.configure_sets(TestSystems::B.in_set(TestSystems::A));
-
-            // Add `X` as child of both `A` and `B`.
-            schedule.configure_sets(TestSystems::X.in_set(TestSystems::A).in_set(TestSystems::B));
-
-            // `X` cannot be the `A`'s child and grandchild at the same time.
-            let result = schedule.initialize(&mut world);
-            assert!(matches!(
-                result,
-                Err(ScheduleBuildError::HierarchyRedundancy(_))
-            ));
-        }
-
-        #[test]
-        fn cross_dependency() {
-            let mut world = World::new();
-            let mut schedule = Schedule::default();
-
-            // Add `B` and give it both kinds of relationships with `A`.
-            schedule.configure_sets(TestSystems::B.in_set(TestSystems::A));
-            schedule.configure_sets(TestSystems::B.after(TestSystems::A));
-            let result = schedule.initialize(&mut world);
-            assert!(matches!(
-                result,
-                Err(ScheduleBuildError::CrossDependency(_, _))
-            ));
-        }
-
-        #[test]
-        fn sets_have_order_but_intersect() {
-            let mut world = World::new();
-            let mut schedule = Schedule::default();
-
-            fn foo() {}
-
-            // Add `foo` to both `A` and `C`.
-            schedule.add_systems(foo.in_set(TestSystems::A).in_set(TestSystems::C));
-
-            // Order `A -> B -> C`.
-            schedule.configure_sets((
-                TestSystems::A,
-                TestSystems::B.after(TestSystems::A),
-                TestSystems::C.after(TestSystems::B),
-            ));
-
-            let result = schedule.initialize(&mut world);
-            // `foo` can't be in both `A` and `C` because they can't run at the same time.
-            assert!(matches!(
-                result,
-                Err(ScheduleBuildError::SetsHaveOrderButIntersect(_, _))
-            ));
-        }
-
-        #[test]
-        fn ambiguity() {
-            #[derive(Resource)]
-            struct X;
-
-            fn res_ref(_x: Res<X>) {}
-            fn res_mut(_x: ResMut<X>) {}
-
-            let mut world = World::new();
-            let mut schedule = Schedule::default();
-
-            schedule.set_build_settings(ScheduleBuildSettings {
-                ambiguity_detection: LogLevel::Error,
-                ..Default::default()
-            });
-
-            schedule.add_systems((res_ref, res_mut));
-            let result = schedule.initialize(&mut world);
-            assert!(matches!(result, Err(ScheduleBuildError::Ambiguity(_))));
-        }
+impl core::fmt::Display for ScheduleProfile {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let width = self
+            .costs
+            .iter()
+            .map(|cost| cost.name.len())
+            .chain([6])
+            .max()
+            .unwrap_or(6);
+        writeln!(
+            f,
+            "{:<width$} {:>16} {:>16}",
+            "system", "cpu insns", "mem bytes"
+        )?;
+        for cost in self.costs.iter() {
+            writeln!(
+                f,
+                "{:<width$} {:>16} {:>16}",
+                cost.name, cost.cpu_instructions, cost.memory_bytes
+            )?;
+        }
+        write!(
+            f,
+            "{:<width$} {:>16} {:>16}",
+            "total",
+            self.total_cpu_instructions(),
+            self.total_memory_bytes()
+        )
     }
+}
 
-    mod system_ambiguity {
-        #[cfg(feature = "trace")]
-        use alloc::collections::BTreeSet;
-
-        use super::*;
-        use crate::prelude::*;
-
-        #[derive(Resource)]
-        struct R;
-
-        #[derive(Component)]
-        struct A;
-
-        #[derive(Component)]
-        struct B;
-
-        #[derive(Event, BufferedEvent)]
-        struct E;
-
-        #[derive(Resource, Component)]
-        struct RC;
-
-        fn empty_system() {}
-        fn res_system(_res: Res<R>) {}
-        fn resmut_system(_res: ResMut<R>) {}
-        fn nonsend_system(_ns: NonSend<R>) {}
-        fn nonsendmut_system(_ns: NonSendMut<R>) {}
-        fn read_component_system(_query: Query<&A>) {}
-        fn write_component_system(_query: Query<&mut A>) {}
-        fn with_filtered_component_system(_query: Query<&mut A, With<B>>) {}
-        fn without_filtered_component_system(_query: Query<&mut A, Without<B>>) {}
-        fn entity_ref_system(_query: Query<EntityRef>) {}
-        fn entity_mut_system(_query: Query<EntityMut>) {}
-        fn event_reader_system(_reader: EventReader<E>) {}
-        fn event_writer_system(_writer: EventWriter<E>) {}
-        fn event_resource_system(_events: ResMut<Events<E>>) {}
-        fn read_world_system(_world: &World) {}
-        fn write_world_system(_world: &mut World) {}
-
-        // Tests for conflict detection
-
-        #[test]
-        fn one_of_everything() {
-            let mut world = World::new();
-            world.insert_resource(R);
-            world.spawn(A);
-            world.init_resource::<Events<E>>();
-
-            let mut schedule = Schedule::default();
-            schedule
-                // nonsendmut system deliberately conflicts with resmut system
-                .add_systems((resmut_system, write_component_system, event_writer_system));
-
-            let _ = schedule.initialize(&mut world);
-
-            assert_eq!(schedule.graph().conflicting_systems().len(), 0);
-        }
-
-        #[test]
-        fn read_only() {
-            let mut world = World::new();
-            world.insert_resource(R);
-            world.spawn(A);
-            world.init_resource::<Events<E>>();
-
-            let mut schedule = Schedule::default();
-            schedule.add_systems((
-                empty_system,
-                empty_system,
-                res_system,
-                res_system,
-                nonsend_system,
-                nonsend_system,
-                read_component_system,
-                read_component_system,
-                entity_ref_system,
-                entity_ref_system,
-                event_reader_system,
-                event_reader_system,
-                read_world_system,
-                read_world_system,
-            ));
-
-            let _ = schedule.initialize(&mut world);
-
-            assert_eq!(schedule.graph().conflicting_systems().len(), 0);
-        }
-
-        #[test]
-        fn read_world() {
-            let mut world = World::new();
-            world.insert_resource(R);
-            world.spawn(A);
-            world.init_resource::<Events<E>>();
-
-            let mut schedule = Schedule::default();
-            schedule.add_systems((
-                resmut_system,
-                write_component_system,
-                event_writer_system,
-                read_world_system,
-            ));
-
-            let _ = schedule.initialize(&mut world);
-
-            assert_eq!(schedule.graph().conflicting_systems().len(), 3);
-        }
-
-        #[test]
-        fn resources() {
-            let mut world = World::new();
-            world.insert_resource(R);
-
-            let mut schedule = Schedule::default();
-            schedule.add_systems((resmut_system, res_system));
-
-            let _ = schedule.initialize(&mut world);
-
-            assert_eq!(schedule.graph().conflicting_systems().len(), 1);
-        }
-
-        #[test]
-        fn nonsend() {
-            let mut world = World::new();
-            world.insert_resource(R);
-
-            let mut schedule = Schedule::default();
-            schedule.add_systems((nonsendmut_system, nonsend_system));
-
-            let _ = schedule.initialize(&mut world);
-
-            assert_eq!(schedule.graph().conflicting_systems().len(), 1);
-        }
-
-        #[test]
-        fn components() {
-            let mut world = World::new();
-            world.spawn(A);
-
-            let mut schedule = Schedule::default();
-            schedule.add_systems((read_component_system, write_component_system));
-
-            let _ = schedule.initialize(&mut world);
-
-            assert_eq!(schedule.graph().conflicting_systems().len(), 1);
-        }
-
-        #[test]
-        fn filtered_components() {
-            let mut world = World::new();
-            world.spawn(A);
-
-            let mut schedule = Schedule::default();
-            schedule.add_systems((
-                with_filtered_component_system,
-                without_filtered_component_system,
-            ));
-
-            let _ = schedule.initialize(&mut world);
-
-            assert_eq!(schedule.graph().conflicting_systems().len(), 0);
-        }
-
-        #[test]
-        fn events() {
-            let mut world = World::new();
-            world.init_resource::<Events<E>>();
-
-            let mut schedule = Schedule::default();
-            schedule.add_systems((
-                // All of these systems clash
-                event_reader_system,
-                event_writer_system,
-                event_resource_system,
-            ));
-
-            let _ = schedule.initialize(&mut world);
-
-            assert_eq!(schedule.graph().conflicting_systems().len(), 3);
-        }
-
-        /// Test that when a struct is both a Resource and a Component, they do not
-        /// conflict with each other.
-        #[test]
-        fn shared_resource_mut_component() {
-            let mut world = World::new();
-            world.insert_resource(RC);
-
-            let mut schedule = Schedule::default();
-            schedule.add_systems((|_: ResMut<RC>| {}, |_: Query<&mut RC>| {}));
-
-            let _ = schedule.initialize(&mut world);
-
-            assert_eq!(schedule.graph().conflicting_systems().len(), 0);
-        }
-
-        #[test]
-        fn resource_mut_and_entity_ref() {
-            let mut world = World::new();
-            world.insert_resource(R);
-
-            let mut schedule = Schedule::default();
-            schedule.add_systems((resmut_system, entity_ref_system));
-
-            let _ = schedule.initialize(&mut world);
-
-            assert_eq!(schedule.graph().conflicting_systems().len(), 0);
-        }
-
-        #[test]
-        fn resource_and_entity_mut() {
-            let mut world = World::new();
-            world.insert_resource(R);
-
-            let mut schedule = Schedule::default();
-            schedule.add_systems((res_system, nonsend_system, entity_mut_system));
-
-            let _ = schedule.initialize(&mut world);
-
-            assert_eq!(schedule.graph().conflicting_systems().len(), 0);
-        }
-
-        #[test]
-        fn write_component_and_entity_ref() {
-            let mut world = World::new();
-            world.insert_resource(R);
-
-            let mut schedule = Schedule::default();
-            schedule.add_systems((write_component_system, entity_ref_system));
-
-            let _ = schedule.initialize(&mut world);
-
-            assert_eq!(schedule.graph().conflicting_systems().len(), 1);
-        }
-
-        #[test]
-        fn read_component_and_entity_mut() {
-            let mut world = World::new();
-            world.insert_resource(R);
-
-            let mut schedule = Schedule::default();
-            schedule.add_systems((read_component_system, entity_mut_system));
-
-            let _ = schedule.initialize(&mut world);
-
-            assert_eq!(schedule.graph().conflicting_systems().len(), 1);
-        }
-
-        #[test]
-        fn exclusive() {
-            let mut world = World::new();
-            world.insert_resource(R);
-            world.spawn(A);
-            world.init_resource::<Events<E>>();
-
-            let mut schedule = Schedule::default();
-            schedule.add_systems((
-                // All 3 of these conflict with each other
-                write_world_system,
-                write_world_system,
-                res_system,
-            ));
-
-            let _ = schedule.initialize(&mut world);
-
-            assert_eq!(schedule.graph().conflicting_systems().len(), 3);
-        }
-
-        // Tests for silencing and resolving ambiguities
-        #[test]
-        fn before_and_after() {
-            let mut world = World::new();
-            world.init_resource::<Events<E>>();
-
-            let mut schedule = Schedule::default();
-            schedule.add_systems((
-                event_reader_system.before(event_writer_system),
-                event_writer_system,
-                event_resource_system.after(event_writer_system),
-            ));
-
-            let _ = schedule.initialize(&mut world);
-
-            assert_eq!(schedule.graph().conflicting_systems().len(), 0);
-        }
-
-        #[test]
-        fn ignore_all_ambiguities() {
-            let mut world = World::new();
-            world.insert_resource(R);
-
-            let mut schedule = Schedule::default();
-            schedule.add_systems((
-                resmut_system.ambiguous_with_all(),
-                res_system,
-                nonsend_system,
-            ));
-
-            let _ = schedule.initialize(&mut world);
-
-            assert_eq!(schedule.graph().conflicting_systems().len(), 0);
-        }
-
-        #[test]
-        fn ambiguous_with_label() {
-            let mut world = World::new();
-            world.insert_resource(R);
-
-            #[derive(SystemSet, Hash, PartialEq, Eq, Debug, Clone)]
-            struct IgnoreMe;
-
-            let mut schedule = Schedule::default();
-            schedule.add_systems((
-                resmut_system.ambiguous_with(IgnoreMe),
-                res_system.in_set(IgnoreMe),
-                nonsend_system.in_set(IgnoreMe),
-            ));
-
-            let _ = schedule.initialize(&mut world);
-
-            assert_eq!(schedule.graph().conflicting_systems().len(), 0);
-        }
-
-        #[test]
-        fn ambiguous_with_system() {
-            let mut world = World::new();
-
-            let mut schedule = Schedule::default();
-            schedule.add_systems((
-                write_component_system.ambiguous_with(read_component_system),
-                read_component_system,
-            ));
-            let _ = schedule.initialize(&mut world);
-
-            assert_eq!(schedule.graph().conflicting_systems().len(), 0);
-        }
-
-        #[derive(ScheduleLabel, Hash, PartialEq, Eq, Debug, Clone)]
-        struct TestSchedule;
-
-        // Tests that the correct ambiguities were reported in the correct order.
-        #[test]
-        #[cfg(feature = "trace")]
-        fn correct_ambiguities() {
-            fn system_a(_res: ResMut<R>) {}
-            fn system_b(_res: ResMut<R>) {}
-            fn system_c(_res: ResMut<R>) {}
-            fn system_d(_res: ResMut<R>) {}
-            fn system_e(_res: ResMut<R>) {}
-
-            let mut world = World::new();
-            world.insert_resource(R);
-
-            let mut schedule = Schedule::new(TestSchedule);
-            schedule.add_systems((
-                system_a,
-                system_b,
-                system_c.ambiguous_with_all(),
-                system_d.ambiguous_with(system_b),
-                system_e.after(system_a),
-            ));
-
-            schedule.graph_mut().initialize(&mut world);
-            let _ = schedule.graph_mut().build_schedule(
-                &mut world,
-                TestSchedule.intern(),
-                &BTreeSet::new(),
-            );
-
-            let ambiguities: Vec<_> = schedule
-                .graph()
-                .conflicts_to_string(schedule.graph().conflicting_systems(), world.components())
-                .map(|item| {
-                    (
-                        item.0,
-                        item.1,
-                        item.2
-                            .into_iter()
-                            .map(|name| name.to_string())
-                            .collect::<Vec<_>>(),
-                    )
-                })
-                .collect();
-
-            let expected = &[
-                (
-                    "system_d".to_string(),
-                    "system_a".to_string(),
-                    vec!["bevy_ecs::schedule::tests::system_ambiguity::R".into()],
-                ),
-                (
-                    "system_d".to_string(),
-                    "system_e".to_string(),
-                    vec!["bevy_ecs::schedule::tests::system_ambiguity::R".into()],
-                ),
-                (
-                    "system_b".to_string(),
-                    "system_a".to_string(),
-                    vec!["bevy_ecs::schedule::tests::system_ambiguity::R".into()],
-                ),
-                (
-                    "system_b".to_string(),
-                    "system_e".to_string(),
-                    vec!["bevy_ecs::schedule::tests::system_ambiguity::R".into()],
-                ),
-            ];
-
-            // ordering isn't stable so do this
-            for entry in expected {
-                assert!(ambiguities.contains(entry));
-            }
-        }
-
-        // Test that anonymous set names work properly
-        // Related issue https://github.com/bevyengine/bevy/issues/9641
-        #[test]
-        #[cfg(feature = "trace")]
-        fn anonymous_set_name() {
-            let mut schedule = Schedule::new(TestSchedule);
-            schedule.add_systems((resmut_system, resmut_system).run_if(|| true));
-
-            let mut world = World::new();
-            schedule.graph_mut().initialize(&mut world);
-            let _ = schedule.graph_mut().build_schedule(
-                &mut world,
-                TestSchedule.intern(),
-                &BTreeSet::new(),
-            );
-
-            let ambiguities: Vec<_> = schedule
-                .graph()
-                .conflicts_to_string(schedule.graph().conflicting_systems(), world.components())
-                .map(|item| {
-                    (
-                        item.0,
-                        item.1,
-                        item.2
-                            .into_iter()
-                            .map(|name| name.to_string())
-                            .collect::<Vec<_>>(),
-                    )
-                })
-                .collect();
-
-            assert_eq!(
-                ambiguities[0],
-                (
-                    "resmut_system (in set (resmut_system, resmut_system))".to_string(),
-                    "resmut_system (in set (resmut_system, resmut_system))".to_string(),
-                    vec!["bevy_ecs::schedule::tests::system_ambiguity::R".into()],
-                )
-            );
-        }
-
-        #[test]
-        fn ignore_component_resource_ambiguities() {
-            let mut world = World::new();
-            world.insert_resource(R);
-            world.allow_ambiguous_resource::<R>();
-            let mut schedule = Schedule::new(TestSchedule);
-
-            // check resource
-            schedule.add_systems((resmut_system, res_system));
-            schedule.initialize(&mut world).unwrap();
-            assert!(schedule.graph().conflicting_systems().is_empty());
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::ComponentTrait;
+    use crate::components::{Position, Velocity};
+    use crate::health::{Health, HealthSystem};
+    use crate::system::IntoSystem;
+    use crate::systems::MovementSystem;
+    use alloc::string::ToString;
+    use soroban_sdk::Env;
 
-            // check components
-            world.allow_ambiguous_component::<A>();
-            schedule.add_systems((write_component_system, read_component_system));
-            schedule.initialize(&mut world).unwrap();
-            assert!(schedule.graph().conflicting_systems().is_empty());
+    fn spawn_movers(world: &mut World, count: i32) {
+        for i in 0..count {
+            let entity_id = world.spawn_empty().id();
+            world.add_typed_component(entity_id, &Position::new(i, 0));
+            world.add_typed_component(entity_id, &Velocity::new(1, 1));
+            world.add_typed_component(entity_id, &Health(10));
         }
     }
 
-    #[cfg(feature = "bevy_debug_stepping")]
-    mod stepping {
-        use super::*;
-        use bevy_ecs::system::SystemState;
-
-        #[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
-        pub struct TestSchedule;
-
-        macro_rules! assert_executor_supports_stepping {
-            ($executor:expr) => {
-                // create a test schedule
-                let mut schedule = Schedule::new(TestSchedule);
-                schedule
-                    .set_executor_kind($executor)
-                    .add_systems(|| -> () { panic!("Executor ignored Stepping") });
-
-                // Add our schedule to stepping & and enable stepping; this should
-                // prevent any systems in the schedule from running
-                let mut stepping = Stepping::default();
-                stepping.add_schedule(TestSchedule).enable();
-
-                // create a world, and add the stepping resource
-                let mut world = World::default();
-                world.insert_resource(stepping);
-
-                // start a new frame by running ihe begin_frame() system
-                let mut system_state: SystemState<Option<ResMut<Stepping>>> =
-                    SystemState::new(&mut world);
-                let res = system_state.get_mut(&mut world);
-                Stepping::begin_frame(res);
+    fn spawn_marker(world: &mut World, _input: ()) {
+        let entity_id = world.spawn_empty().id();
+        world.add_typed_component(entity_id, &Health(1));
+    }
 
-                // now run the schedule; this will panic if the executor doesn't
-                // handle stepping
-                schedule.run(&mut world);
-            };
-        }
+    #[test]
+    fn test_systems_run_in_order() {
+        let mut world = World::new();
+        spawn_movers(&mut world, 2);
+        let mut schedule = Schedule::new()
+            .with_system("movement", MovementSystem::new())
+            .with_system("spawn", spawn_marker.into_system());
+        schedule.add_system("health", HealthSystem::new());
+        assert_eq!(schedule.system_names(), ["movement", "spawn", "health"]);
+        assert_eq!(schedule.len(), 3);
+
+        schedule.run(&mut world);
+        schedule.run(&mut world);
+        assert_eq!(world.entity_count(), 4);
+        let first = world.query_entities(&[Velocity::component_type()]).get(0);
+        assert_eq!(
+            world.get_typed_component::<Position>(first.unwrap()),
+            Some(Position::new(2, 2))
+        );
+    }
 
-        /// verify the [`SimpleExecutor`] supports stepping
-        #[test]
-        #[expect(deprecated, reason = "We still need to test this.")]
-        fn simple_executor() {
-            assert_executor_supports_stepping!(ExecutorKind::Simple);
-        }
+    #[test]
+    fn test_profile_reports_each_system() {
+        let env = Env::default();
+        env.cost_estimate().budget().reset_unlimited();
+        let mut world = World::with_env(&env);
+        spawn_movers(&mut world, 8);
+        let mut schedule = Schedule::new()
+            .with_system("movement", MovementSystem::new())
+            .with_system("health", HealthSystem::new());
+
+        let profile = schedule.run_profiled(&mut world);
+        let movement = profile.get("movement").unwrap();
+        assert!(movement.cpu_instructions > 0 && movement.memory_bytes > 0);
+        assert_eq!(profile.costs().len(), 2);
+        assert_eq!(
+            profile.total_cpu_instructions(),
+            movement.cpu_instructions + profile.get("health").unwrap().cpu_instructions
+        );
+        // Moving eight entities costs more than draining an empty event queue
+        assert_eq!(profile.most_expensive().unwrap().name, "movement");
+
+        let table = profile.to_string();
+        let lines: StdVec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("system"));
+        assert!(lines[1].starts_with("movement"));
+        assert!(lines[3].starts_with("total"));
+
+        profile.assert_within("movement", SystemBudget::new(u64::MAX, u64::MAX));
+    }
 
-        /// verify the [`SingleThreadedExecutor`] supports stepping
-        #[test]
-        fn single_threaded_executor() {
-            assert_executor_supports_stepping!(ExecutorKind::SingleThreaded);
-        }
+    #[test]
+    #[should_panic(expected = "system `movement` used")]
+    fn test_assert_within_fails_over_budget() {
+        let env = Env::default();
+        env.cost_estimate().budget().reset_unlimited();
+        let mut world = World::with_env(&env);
+        spawn_movers(&mut world, 4);
+        let mut schedule = Schedule::new().with_system("movement", MovementSystem::new());
+        schedule
+            .run_profiled(&mut world)
+            .assert_within("movement", SystemBudget::new(1_000, u64::MAX));
+    }
 
-        /// verify the [`MultiThreadedExecutor`] supports stepping
-        #[test]
-        fn multi_threaded_executor() {
-            assert_executor_supports_stepping!(ExecutorKind::MultiThreaded);
-        }
+    #[test]
+    #[should_panic(expected = "system `physics` did not run")]
+    fn test_assert_within_fails_for_unknown_systems() {
+        let mut schedule = Schedule::new();
+        schedule
+            .run_profiled(&mut World::new())
+            .assert_within("physics", SystemBudget::new(0, 0));
     }
 }
@@ -0,0 +1,244 @@
+//! Several independent games hosted by one contract.
+//!
+//! [`GameSession::create`] allocates a session id, never reused within the
+//! contract, and marks the session open under an entry of its own. Each
+//! session saves its world with [`GameSession::store`], whose entries are keyed
+//! by the session id, so sessions keep their own entities and resources.
+//! Closing a session removes its entry along with its world.
+//!
+//! Open sessions are listed a page of ids at a time with
+//! [`GameSession::list`], so no entry grows with the number of sessions.
+
+use crate::persistence::{WorldStore, DEFAULT_TTL_EXTEND_TO, DEFAULT_TTL_THRESHOLD};
+use crate::upgrade::Upgrader;
+use soroban_sdk::{contracttype, symbol_short, Env, Symbol, Vec};
+
+/// Id of a game hosted by the current contract
+#[contracttype]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct GameSession(u32);
+
+impl GameSession {
+    fn next_key() -> (Symbol, Symbol) {
+        (symbol_short!("session"), symbol_short!("next"))
+    }
+
+    fn open_key(&self) -> (Symbol, u32) {
+        (symbol_short!("session"), self.0)
+    }
+
+    /// Get the session's id
+    pub fn id(&self) -> u32 {
+        self.0
+    }
+
+    /// Allocate a new session and mark it open
+    pub fn create(env: &Env) -> Self {
+        let instance = env.storage().instance();
+        let id = Self::next_id(env);
+        instance.set(&Self::next_key(), &(id + 1));
+        let session = Self(id);
+        env.storage().persistent().set(&session.open_key(), &true);
+        session.extend_ttl(env);
+        session
+    }
+
+    /// Get the open session with id `id`
+    pub fn open(env: &Env, id: u32) -> Option<Self> {
+        let session = Self(id);
+        session.is_open(env).then_some(session)
+    }
+
+    /// Get the id the next session will be given, one past the newest id
+    pub fn next_id(env: &Env) -> u32 {
+        env.storage().instance().get(&Self::next_key()).unwrap_or(1)
+    }
+
+    /// Get the open sessions among the `count` ids starting at `start`, oldest
+    /// first
+    ///
+    /// Page through every open session by starting at 1 and moving `start`
+    /// on by `count` until it reaches [`GameSession::next_id`].
+    pub fn list(env: &Env, start: u32, count: u32) -> Vec<GameSession> {
+        let end = start.saturating_add(count).min(Self::next_id(env));
+        let mut open = Vec::new(env);
+        // Listing is read-only, so it leaves the sessions' TTLs alone
+        let storage = env.storage().persistent();
+        for id in start.max(1)..end {
+            let session = Self(id);
+            if storage.has(&session.open_key()) {
+                open.push_back(session);
+            }
+        }
+        open
+    }
+
+    /// Check whether the session is open, keeping it alive if it is
+    pub fn is_open(&self, env: &Env) -> bool {
+        let open = env.storage().persistent().has(&self.open_key());
+        if open {
+            self.extend_ttl(env);
+        }
+        open
+    }
+
+    /// Get the store the session's world is saved with, or `None` if the
+    /// session is not open
+    pub fn store(&self, env: &Env) -> Option<WorldStore> {
+        self.named_store(env, symbol_short!("session"))
+    }
+
+    /// Get the store of another world saved in the session as `name`, or
+    /// `None` if the session is not open
+    pub fn named_store(&self, env: &Env, name: Symbol) -> Option<WorldStore> {
+        self.is_open(env)
+            .then(|| WorldStore::new(env, name).in_session(*self))
+    }
+
+    /// Close the session and remove its world, returning `false` if it was
+    /// not open
+    ///
+    /// Worlds saved in the session under names of their own must be removed
+    /// with [`WorldStore::remove`] before the session is closed.
+    pub fn close(&self, env: &Env) -> bool {
+        let storage = env.storage().persistent();
+        if !storage.has(&self.open_key()) {
+            return false;
+        }
+        storage.remove(&self.open_key());
+        let mut store = WorldStore::new(env, symbol_short!("session")).in_session(*self);
+        store.remove();
+        Upgrader::clear_format(env, &store);
+        true
+    }
+
+    fn extend_ttl(&self, env: &Env) {
+        let extend_to = DEFAULT_TTL_EXTEND_TO.min(env.storage().max_ttl());
+        env.storage()
+            .persistent()
+            .extend_ttl(&self.open_key(), DEFAULT_TTL_THRESHOLD, extend_to);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resource::GameState;
+    use crate::world::World;
+    use soroban_sdk::contract;
+    use soroban_sdk::testutils::{storage::Persistent as _, Ledger};
+
+    #[contract]
+    struct SessionTestContract;
+
+    /// Save a world with one entity and `score` in the session
+    fn start_match(env: &Env, score: i32) -> GameSession {
+        let session = GameSession::create(env);
+        let mut world = World::with_env(env);
+        world.spawn_empty();
+        let mut state = GameState::new();
+        state.increment_score(score);
+        world.set_typed_resource(&state);
+        session.store(env).unwrap().save(&world);
+        session
+    }
+
+    fn score(env: &Env, session: GameSession) -> Option<i32> {
+        let mut world = World::with_env(env);
+        session
+            .store(env)?
+            .load(&mut world)
+            .then(|| world.get_typed_resource::<GameState>().unwrap().score)
+    }
+
+    #[test]
+    fn test_sessions_keep_separate_worlds() {
+        let env = Env::default();
+        let contract_id = env.register(SessionTestContract, ());
+        env.as_contract(&contract_id, || {
+            let first = start_match(&env, 10);
+            let second = start_match(&env, 20);
+            assert_eq!((first.id(), second.id()), (1, 2));
+            assert_eq!(GameSession::next_id(&env), 3);
+            assert_eq!(
+                GameSession::list(&env, 1, 10),
+                Vec::from_array(&env, [first, second])
+            );
+            assert_eq!(
+                GameSession::list(&env, 2, 10),
+                Vec::from_array(&env, [second])
+            );
+            assert_eq!(
+                GameSession::list(&env, 0, 2),
+                Vec::from_array(&env, [first])
+            );
+            assert_eq!(score(&env, first), Some(10));
+            assert_eq!(score(&env, second), Some(20));
+
+            // Saving one session's world leaves the other's alone
+            let mut world = World::with_env(&env);
+            assert!(first.store(&env).unwrap().load(&mut world));
+            world.spawn_empty();
+            world.set_typed_resource(&GameState::new());
+            first.store(&env).unwrap().save(&world);
+            assert_eq!(score(&env, first), Some(0));
+            assert_eq!(score(&env, second), Some(20));
+            let mut other = World::with_env(&env);
+            assert!(second.store(&env).unwrap().load(&mut other));
+            assert_eq!(other.entity_count(), 1);
+            // Worlds outside of sessions are apart as well
+            assert!(!WorldStore::exists(&env, &symbol_short!("session")));
+        });
+    }
+
+    #[test]
+    fn test_closing_a_session() {
+        let env = Env::default();
+        let contract_id = env.register(SessionTestContract, ());
+        env.as_contract(&contract_id, || {
+            let first = start_match(&env, 10);
+            let second = start_match(&env, 20);
+            assert!(first.close(&env));
+            assert!(!first.close(&env));
+            assert_eq!(GameSession::open(&env, first.id()), None);
+            assert_eq!(GameSession::open(&env, second.id()), Some(second));
+            assert_eq!(
+                GameSession::list(&env, 1, 10),
+                Vec::from_array(&env, [second])
+            );
+            assert!(first.store(&env).is_none());
+            assert!(GameSession(42).store(&env).is_none());
+            let removed = WorldStore::new(&env, symbol_short!("session")).in_session(first);
+            assert!(!removed.is_saved());
+            assert_eq!(score(&env, first), None);
+            assert_eq!(score(&env, second), Some(20));
+
+            // Ids of closed sessions are not handed out again
+            assert_eq!(GameSession::create(&env).id(), 3);
+        });
+    }
+
+    #[test]
+    fn test_reading_keeps_a_session_alive() {
+        let env = Env::default();
+        let contract_id = env.register(SessionTestContract, ());
+        let session = env.as_contract(&contract_id, || GameSession::create(&env));
+        let ttl = || {
+            env.as_contract(&contract_id, || {
+                env.storage().persistent().get_ttl(&session.open_key())
+            })
+        };
+        assert_eq!(ttl(), DEFAULT_TTL_EXTEND_TO);
+
+        env.ledger()
+            .set_sequence_number(DEFAULT_TTL_EXTEND_TO - 1_000);
+        assert_eq!(ttl(), 1_000);
+        // Listing leaves the TTL alone
+        env.as_contract(&contract_id, || {
+            assert_eq!(GameSession::list(&env, 1, 1).len(), 1);
+        });
+        assert_eq!(ttl(), 1_000);
+        env.as_contract(&contract_id, || assert!(session.is_open(&env)));
+        assert_eq!(ttl(), DEFAULT_TTL_EXTEND_TO);
+    }
+}
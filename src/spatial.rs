@@ -0,0 +1,368 @@
+//! Uniform grid index over entity positions.
+//!
+//! Once [`World::enable_spatial_hash`] is called, the world files every entity
+//! with a [`Position`] under the grid cell containing it, and keeps the index
+//! current as positions are added, changed or removed and entities despawn.
+//! Neighborhood lookups then only visit nearby cells instead of every entity,
+//! which is what keeps the collision broad phase affordable with many bodies.
+
+use crate::component::{Component, ComponentTrait};
+use crate::components::Position;
+use crate::entity::EntityId;
+use crate::world::World;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec as StdVec;
+
+/// Coordinates of a grid cell
+pub type Cell = (i32, i32);
+
+/// Index from grid cells to the entities positioned in them
+#[derive(Debug, Clone)]
+pub struct SpatialHash {
+    cell_size: i32,
+    cells: BTreeMap<Cell, StdVec<EntityId>>,
+    positions: BTreeMap<EntityId, Position>,
+}
+
+impl SpatialHash {
+    /// Create an empty index with square cells of `cell_size` world units
+    ///
+    /// Sizes below 1 are raised to 1.
+    pub fn new(cell_size: i32) -> Self {
+        Self {
+            cell_size: cell_size.max(1),
+            cells: BTreeMap::new(),
+            positions: BTreeMap::new(),
+        }
+    }
+
+    /// Get the side length of a cell
+    pub fn cell_size(&self) -> i32 {
+        self.cell_size
+    }
+
+    /// Get the number of indexed entities
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Check whether no entity is indexed
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Get the cell containing `position`
+    pub fn cell_of(&self, position: &Position) -> Cell {
+        (
+            position.x.div_euclid(self.cell_size),
+            position.y.div_euclid(self.cell_size),
+        )
+    }
+
+    /// Get the indexed position of `entity_id`
+    pub fn position_of(&self, entity_id: EntityId) -> Option<Position> {
+        self.positions.get(&entity_id).copied()
+    }
+
+    /// Record that `entity_id` is at `position`, replacing its previous entry
+    pub fn insert(&mut self, entity_id: EntityId, position: Position) {
+        self.remove(entity_id);
+        let entities = self.cells.entry(self.cell_of(&position)).or_default();
+        if let Err(index) = entities.binary_search(&entity_id) {
+            entities.insert(index, entity_id);
+        }
+        self.positions.insert(entity_id, position);
+    }
+
+    /// Forget `entity_id`, returning its indexed position
+    pub fn remove(&mut self, entity_id: EntityId) -> Option<Position> {
+        let position = self.positions.remove(&entity_id)?;
+        let cell = self.cell_of(&position);
+        if let Some(entities) = self.cells.get_mut(&cell) {
+            entities.retain(|indexed| *indexed != entity_id);
+            if entities.is_empty() {
+                self.cells.remove(&cell);
+            }
+        }
+        Some(position)
+    }
+
+    /// Get the entities in `cell`, ordered by id
+    pub fn entities_in_cell(&self, cell: Cell) -> StdVec<EntityId> {
+        self.cells.get(&cell).cloned().unwrap_or_default()
+    }
+
+    /// Get the entities positioned inside the rectangle from `min` to `max`,
+    /// both corners included, ordered by id
+    pub fn entities_in_rect(&self, min: &Position, max: &Position) -> StdVec<EntityId> {
+        let mut found = StdVec::new();
+        if min.x > max.x || min.y > max.y {
+            return found;
+        }
+        let inside = |position: &Position| {
+            (min.x..=max.x).contains(&position.x) && (min.y..=max.y).contains(&position.y)
+        };
+        let (min_cell, max_cell) = (self.cell_of(min), self.cell_of(max));
+        let spanned = (max_cell.0 as i64 - min_cell.0 as i64 + 1)
+            * (max_cell.1 as i64 - min_cell.1 as i64 + 1);
+
+        if spanned > self.cells.len() as i64 {
+            // Cheaper to look at every occupied cell than at every spanned one
+            found.extend(
+                self.positions
+                    .iter()
+                    .filter(|(_, position)| inside(position))
+                    .map(|(entity_id, _)| *entity_id),
+            );
+            return found;
+        }
+        for cell_x in min_cell.0..=max_cell.0 {
+            for cell_y in min_cell.1..=max_cell.1 {
+                if let Some(entities) = self.cells.get(&(cell_x, cell_y)) {
+                    found.extend(
+                        entities
+                            .iter()
+                            .filter(|entity_id| inside(&self.positions[entity_id])),
+                    );
+                }
+            }
+        }
+        found.sort();
+        found
+    }
+
+    /// Find the entity closest to `position`, skipping `exclude`
+    ///
+    /// Ties go to the lowest entity id.
+    pub fn nearest(&self, position: &Position, exclude: Option<EntityId>) -> Option<EntityId> {
+        // Squared offsets between `i32` positions overflow `i64` once summed
+        let distance_squared = |other: &Position| {
+            let dx = other.x as i128 - position.x as i128;
+            let dy = other.y as i128 - position.y as i128;
+            dx * dx + dy * dy
+        };
+        let mut best: Option<(i128, EntityId)> = None;
+        let consider = |entity_id: EntityId, best: &mut Option<(i128, EntityId)>| {
+            if Some(entity_id) == exclude {
+                return;
+            }
+            let candidate = (distance_squared(&self.positions[&entity_id]), entity_id);
+            if best.map_or(true, |best| candidate < best) {
+                *best = Some(candidate);
+            }
+        };
+
+        let center = self.cell_of(position);
+        let mut ring: i64 = 0;
+        loop {
+            // A ring of cells this wide costs more than scanning every occupied cell
+            if 8 * ring > self.cells.len() as i64 {
+                for entity_id in self.positions.keys() {
+                    consider(*entity_id, &mut best);
+                }
+                return best.map(|(_, entity_id)| entity_id);
+            }
+            for (cell_x, cell_y) in ring_cells(center, ring) {
+                if let Some(entities) = self.cells.get(&(cell_x, cell_y)) {
+                    for entity_id in entities.iter() {
+                        consider(*entity_id, &mut best);
+                    }
+                }
+            }
+            // Cells beyond this ring are more than `ring` cells away
+            let reach = ring as i128 * self.cell_size as i128;
+            if let Some((distance, _)) = best {
+                if distance <= reach * reach {
+                    return best.map(|(_, entity_id)| entity_id);
+                }
+            }
+            ring += 1;
+        }
+    }
+}
+
+/// Cells at Chebyshev distance `ring` from `center`
+fn ring_cells(center: Cell, ring: i64) -> impl Iterator<Item = Cell> {
+    let (center_x, center_y) = (center.0 as i64, center.1 as i64);
+    (center_x - ring..=center_x + ring)
+        .flat_map(move |x| (center_y - ring..=center_y + ring).map(move |y| (x, y)))
+        .filter(move |(x, y)| (x - center_x).abs() == ring || (y - center_y).abs() == ring)
+        .filter_map(|(x, y)| Some((i32::try_from(x).ok()?, i32::try_from(y).ok()?)))
+}
+
+impl World {
+    /// Index every entity's [`Position`] in a grid with cells of `cell_size`
+    ///
+    /// Entities already positioned are indexed right away. Enabling the index
+    /// again rebuilds it with the new cell size.
+    pub fn enable_spatial_hash(&mut self, cell_size: i32) {
+        let mut spatial = SpatialHash::new(cell_size);
+        for entity in self.iter_entities() {
+            if let Some(position) = self.get_typed_component::<Position>(entity.id()) {
+                spatial.insert(entity.id(), position);
+            }
+        }
+        self.spatial = Some(spatial);
+    }
+
+    /// Stop maintaining the spatial index and drop it
+    pub fn disable_spatial_hash(&mut self) {
+        self.spatial = None;
+    }
+
+    /// Get the spatial index, if enabled
+    pub fn spatial_hash(&self) -> Option<&SpatialHash> {
+        self.spatial.as_ref()
+    }
+
+    /// Update the spatial index before a [`Position`] component is stored
+    ///
+    /// Returns `false` if the position cannot be decoded and must not be stored.
+    pub(crate) fn index_position(&mut self, entity_id: EntityId, component: &Component) -> bool {
+        if self.spatial.is_none() {
+            return true;
+        }
+        let Some(position) = Position::deserialize(self.env(), component.data()) else {
+            return false;
+        };
+        if let Some(spatial) = self.spatial.as_mut() {
+            spatial.insert(entity_id, position);
+        }
+        true
+    }
+
+    /// Remove `entity_id` from the spatial index
+    pub(crate) fn unindex_position(&mut self, entity_id: EntityId) {
+        if let Some(spatial) = self.spatial.as_mut() {
+            spatial.remove(entity_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::Velocity;
+    use crate::system::System;
+    use crate::systems::MovementSystem;
+
+    fn spawn_at(world: &mut World, x: i32, y: i32) -> EntityId {
+        let entity_id = world.spawn_empty().id();
+        world.add_typed_component(entity_id, &Position::new(x, y));
+        entity_id
+    }
+
+    #[test]
+    fn test_cells_and_rects() {
+        let mut spatial = SpatialHash::new(10);
+        let a = EntityId::new(1, 0);
+        let b = EntityId::new(2, 0);
+        let c = EntityId::new(3, 0);
+        spatial.insert(b, Position::new(15, 15));
+        spatial.insert(a, Position::new(12, 19));
+        spatial.insert(c, Position::new(-1, -1));
+
+        assert_eq!(spatial.cell_of(&Position::new(-1, -1)), (-1, -1));
+        assert_eq!(spatial.entities_in_cell((1, 1)), [a, b]);
+        assert_eq!(spatial.entities_in_cell((-1, -1)), [c]);
+        assert_eq!(
+            spatial.entities_in_rect(&Position::new(-5, -5), &Position::new(14, 20)),
+            [a, c]
+        );
+        // Spans far more cells than are occupied
+        assert_eq!(
+            spatial.entities_in_rect(
+                &Position::new(-10_000, -10_000),
+                &Position::new(10_000, 10_000)
+            ),
+            [a, b, c]
+        );
+
+        spatial.insert(a, Position::new(100, 100));
+        assert_eq!(spatial.entities_in_cell((1, 1)), [b]);
+        assert_eq!(spatial.remove(c), Some(Position::new(-1, -1)));
+        assert!(spatial.entities_in_cell((-1, -1)).is_empty());
+        assert_eq!(spatial.len(), 2);
+    }
+
+    #[test]
+    fn test_nearest() {
+        let mut spatial = SpatialHash::new(8);
+        assert_eq!(spatial.nearest(&Position::new(0, 0), None), None);
+
+        let near = EntityId::new(1, 0);
+        let far = EntityId::new(2, 0);
+        let tied = EntityId::new(3, 0);
+        spatial.insert(near, Position::new(9, 0));
+        spatial.insert(far, Position::new(200, 200));
+        spatial.insert(tied, Position::new(0, 9));
+
+        assert_eq!(spatial.nearest(&Position::new(0, 0), None), Some(near));
+        assert_eq!(
+            spatial.nearest(&Position::new(0, 0), Some(near)),
+            Some(tied)
+        );
+        assert_eq!(spatial.nearest(&Position::new(190, 190), None), Some(far));
+        // Closer across a cell boundary than the entity sharing the cell
+        spatial.insert(tied, Position::new(7, 7));
+        assert_eq!(spatial.nearest(&Position::new(8, 1), None), Some(near));
+        // Distances across the whole coordinate range do not overflow
+        let mut spatial = SpatialHash::new(1);
+        let low = EntityId::new(4, 0);
+        let high = EntityId::new(5, 0);
+        spatial.insert(low, Position::new(i32::MIN, i32::MIN));
+        spatial.insert(high, Position::new(i32::MAX, i32::MAX));
+        assert_eq!(
+            spatial.nearest(&Position::new(i32::MAX, i32::MAX), Some(high)),
+            Some(low)
+        );
+        assert_eq!(
+            spatial.nearest(&Position::new(i32::MIN, i32::MAX), None),
+            Some(low)
+        );
+    }
+
+    #[test]
+    fn test_world_keeps_index_current() {
+        let mut world = World::new();
+        let before = spawn_at(&mut world, 5, 5);
+        world.enable_spatial_hash(16);
+        let spatial = world.spatial_hash().unwrap();
+        assert_eq!(spatial.entities_in_cell((0, 0)), [before]);
+
+        let mover = spawn_at(&mut world, 30, 0);
+        world.add_typed_component(mover, &Velocity::new(10, 0));
+        MovementSystem::new().run(&mut world, ());
+        let spatial = world.spatial_hash().unwrap();
+        assert!(spatial.entities_in_cell((1, 0)).is_empty());
+        assert_eq!(spatial.entities_in_cell((2, 0)), [mover]);
+
+        world.remove_component_from_entity(before, &Position::component_type());
+        assert!(world
+            .spatial_hash()
+            .unwrap()
+            .entities_in_cell((0, 0))
+            .is_empty());
+        world.despawn(mover);
+        assert!(world.spatial_hash().unwrap().is_empty());
+
+        let batch = world.spawn_batch(
+            [Position::new(1, 1), Position::new(40, 40)]
+                .iter()
+                .map(|position| {
+                    let mut bundle = soroban_sdk::Vec::new(world.env());
+                    bundle.push_back(Component::new(
+                        Position::component_type(),
+                        position.serialize(world.env()),
+                    ));
+                    bundle
+                })
+                .collect::<StdVec<_>>(),
+        );
+        let spatial = world.spatial_hash().unwrap();
+        assert_eq!(
+            spatial.entities_in_rect(&Position::new(0, 0), &Position::new(50, 50)),
+            batch.iter().collect::<StdVec<_>>()
+        );
+    }
+}
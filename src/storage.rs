@@ -6,17 +6,23 @@ use soroban_sdk::{contracttype, Bytes, Env, Symbol, Vec};
 #[derive(Debug, Clone)]
 pub struct Storage {
     pub entity_ids: Vec<u64>,
+    pub generations: Vec<u32>,
     pub component_types: Vec<Symbol>,
     pub component_data: Vec<Bytes>,
 }
 
 impl Storage {
     pub fn new() -> Self {
-        let env = soroban_sdk::Env::default();
+        Self::with_env(&soroban_sdk::Env::default())
+    }
+
+    /// Create an empty storage whose columns live in `env`
+    pub fn with_env(env: &Env) -> Self {
         Self {
-            entity_ids: Vec::new(&env),
-            component_types: Vec::new(&env),
-            component_data: Vec::new(&env),
+            entity_ids: Vec::new(env),
+            generations: Vec::new(env),
+            component_types: Vec::new(env),
+            component_data: Vec::new(env),
         }
     }
 
@@ -24,6 +30,19 @@ impl Storage {
     pub fn add_component(&mut self, entity_id: EntityId, component: Component) {
         self.remove_component(entity_id, component.component_type().clone());
         self.entity_ids.push_back(entity_id.id());
+        self.generations.push_back(entity_id.generation());
+        self.component_types
+            .push_back(component.component_type().clone());
+        self.component_data.push_back(component.data().clone());
+    }
+
+    /// Add a component the entity is known not to have yet
+    ///
+    /// Skips the scan for an existing entry that [`Storage::add_component`]
+    /// performs, so storing a duplicate leaves both entries in place.
+    pub fn append_component(&mut self, entity_id: EntityId, component: Component) {
+        self.entity_ids.push_back(entity_id.id());
+        self.generations.push_back(entity_id.generation());
         self.component_types
             .push_back(component.component_type().clone());
         self.component_data.push_back(component.data().clone());
@@ -31,24 +50,28 @@ impl Storage {
 
     /// Remove a component from storage
     pub fn remove_component(&mut self, entity_id: EntityId, component_type: Symbol) -> bool {
+        let env = self.entity_ids.env().clone();
         let mut found = false;
-        let mut new_entity_ids = Vec::new(&soroban_sdk::Env::default());
-        let mut new_component_types = Vec::new(&soroban_sdk::Env::default());
-        let mut new_component_data = Vec::new(&soroban_sdk::Env::default());
+        let mut new_entity_ids = Vec::new(&env);
+        let mut new_generations = Vec::new(&env);
+        let mut new_component_types = Vec::new(&env);
+        let mut new_component_data = Vec::new(&env);
         for i in 0..self.entity_ids.len() {
             let eid = self.entity_ids.get(i).unwrap();
             let ctype = self.component_types.get(i).unwrap();
             let cdata = self.component_data.get(i).unwrap();
-            if eid == entity_id.id() && ctype == component_type {
+            if self.holds(i, entity_id) && ctype == component_type {
                 found = true;
             } else {
                 new_entity_ids.push_back(eid);
+                new_generations.push_back(self.generations.get(i).unwrap());
                 new_component_types.push_back(ctype.clone());
                 new_component_data.push_back(cdata.clone());
             }
         }
         if found {
             self.entity_ids = new_entity_ids;
+            self.generations = new_generations;
             self.component_types = new_component_types;
             self.component_data = new_component_data;
         }
@@ -58,10 +81,9 @@ impl Storage {
     /// Get a component from storage
     pub fn get_component(&self, entity_id: EntityId, component_type: Symbol) -> Option<Component> {
         for i in 0..self.entity_ids.len() {
-            let eid = self.entity_ids.get(i).unwrap();
             let ctype = self.component_types.get(i).unwrap();
             let cdata = self.component_data.get(i).unwrap();
-            if eid == entity_id.id() && ctype == component_type {
+            if self.holds(i, entity_id) && ctype == component_type {
                 return Some(Component::new(ctype.clone(), cdata.clone()));
             }
         }
@@ -71,9 +93,8 @@ impl Storage {
     /// Check if a component exists in storage
     pub fn has_component(&self, entity_id: EntityId, component_type: Symbol) -> bool {
         for i in 0..self.entity_ids.len() {
-            let eid = self.entity_ids.get(i).unwrap();
             let ctype = self.component_types.get(i).unwrap();
-            if eid == entity_id.id() && ctype == component_type {
+            if self.holds(i, entity_id) && ctype == component_type {
                 return true;
             }
         }
@@ -82,22 +103,28 @@ impl Storage {
 
     /// Get all components for an entity
     pub fn get_entity_components(&self, entity_id: EntityId) -> Vec<Component> {
-        let env = soroban_sdk::Env::default();
-        let mut components = Vec::new(&env);
+        let mut components = Vec::new(self.entity_ids.env());
         for i in 0..self.entity_ids.len() {
-            let eid = self.entity_ids.get(i).unwrap();
             let ctype = self.component_types.get(i).unwrap();
             let cdata = self.component_data.get(i).unwrap();
-            if eid == entity_id.id() {
+            if self.holds(i, entity_id) {
                 components.push_back(Component::new(ctype.clone(), cdata.clone()));
             }
         }
         components
     }
 
+    /// Check whether the entry at `index` belongs to `entity_id`, generation
+    /// included
+    fn holds(&self, index: u32, entity_id: EntityId) -> bool {
+        self.entity_ids.get(index) == Some(entity_id.id())
+            && self.generations.get(index) == Some(entity_id.generation())
+    }
+
     pub fn clear(&mut self) {
-        let env = soroban_sdk::Env::default();
+        let env = self.entity_ids.env().clone();
         self.entity_ids = Vec::new(&env);
+        self.generations = Vec::new(&env);
         self.component_types = Vec::new(&env);
         self.component_data = Vec::new(&env);
     }
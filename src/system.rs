@@ -1,8 +1,6 @@
-use crate::component::Component;
-use crate::entity::{Entity, EntityId};
-use crate::event::{DamageEvent, EventTrait};
+use crate::entity::EntityId;
 use crate::world::World;
-use soroban_sdk::{symbol_short, Symbol, Vec};
+use soroban_sdk::{Symbol, Vec};
 
 /// A system in the ECS world
 pub trait System {
@@ -32,12 +30,22 @@ pub trait SystemParam {
 /// A query for entities with specific components
 pub struct Query {
     component_types: Vec<Symbol>,
+    include_disabled: bool,
 }
 
 impl Query {
     /// Create a new query
     pub fn new(component_types: Vec<Symbol>) -> Self {
-        Self { component_types }
+        Self {
+            component_types,
+            include_disabled: false,
+        }
+    }
+
+    /// Match entities with disabling components as well
+    pub fn include_disabled(mut self) -> Self {
+        self.include_disabled = true;
+        self
     }
 
     /// Add a component type to the query
@@ -51,9 +59,15 @@ impl Query {
         // Convert Vec<Symbol> to &[Symbol] by creating a slice
         // This is a limitation of the Soroban SDK - we can't easily convert Vec to slice
         // For now, we'll use a different approach
-        let env = soroban_sdk::Env::default();
-        let mut results = Vec::new(&env);
+        let mut results = Vec::new(world.env());
         for entity in world.iter_entities() {
+            if !self.include_disabled
+                && !world
+                    .default_query_filters()
+                    .allows(&entity, |ctype| self.component_types.contains(ctype))
+            {
+                continue;
+            }
             let mut has_all_components = true;
             for i in 0..self.component_types.len() {
                 let ctype = self.component_types.get(i).unwrap();
@@ -183,7 +197,7 @@ impl SystemParam for QueryParam {
     type Param = Self;
     type Fetch = Vec<EntityId>;
 
-    fn fetch(world: &World) -> Self::Fetch {
+    fn fetch(_world: &World) -> Self::Fetch {
         // This is a simplified implementation
         // In a real system, you'd have more sophisticated query execution
         let env = soroban_sdk::Env::default();
@@ -205,13 +219,18 @@ impl ResourceParam {
     pub fn new(resource_type: Symbol) -> Self {
         Self { resource_type }
     }
+
+    /// Get the type of the resource this parameter accesses
+    pub fn resource_type(&self) -> &Symbol {
+        &self.resource_type
+    }
 }
 
 impl SystemParam for ResourceParam {
     type Param = Self;
     type Fetch = Option<crate::resource::Resource>;
 
-    fn fetch(world: &World) -> Self::Fetch {
+    fn fetch(_world: &World) -> Self::Fetch {
         // This is a simplified implementation
         // In a real system, you'd have access to the parameter instance
         None
@@ -222,88 +241,10 @@ impl SystemParam for ResourceParam {
     }
 }
 
-// Example systems
-/// Movement system for updating entity positions
-pub struct MovementSystem;
-
-impl System for MovementSystem {
-    type In = ();
-    type Out = ();
-
-    fn run(&mut self, world: &mut World, _input: Self::In) -> Self::Out {
-        // Example: Find all entities with position and velocity components
-        let entities_with_movement =
-            world.query_entities(&[symbol_short!("position"), symbol_short!("velocity")]);
-
-        for i in 0..entities_with_movement.len() {
-            let entity_id = entities_with_movement.get(i).unwrap();
-            // In a real implementation, you'd:
-            // 1. Get the position and velocity components
-            // 2. Update the position based on velocity
-            // 3. Apply any constraints (bounds, collision, etc.)
-            // For now, we'll just mark that we processed this entity
-        }
-    }
-}
-
-/// Collision detection system
-pub struct CollisionSystem;
-
-impl System for CollisionSystem {
-    type In = ();
-    type Out = ();
-
-    fn run(&mut self, world: &mut World, _input: Self::In) -> Self::Out {
-        let entities_with_collision =
-            world.query_entities(&[symbol_short!("position"), symbol_short!("collision")]);
-
-        for i in 0..entities_with_collision.len() {
-            for j in (i + 1)..entities_with_collision.len() {
-                let entity_a = entities_with_collision.get(i).unwrap();
-                let entity_b = entities_with_collision.get(j).unwrap();
-                // Generate collision event
-                let collision_event = crate::event::CollisionEvent::new(
-                    entity_a.id(),
-                    entity_b.id(),
-                    symbol_short!("physical"),
-                );
-                let env = soroban_sdk::Env::default();
-                let event_data = collision_event.serialize(&env);
-                let event = crate::event::Event::new(symbol_short!("collision"), event_data);
-                world.send_event(event);
-            }
-        }
-    }
-}
-
-/// Health system for managing entity health
-pub struct HealthSystem;
-
-impl System for HealthSystem {
-    type In = ();
-    type Out = ();
-
-    fn run(&mut self, world: &mut World, _input: Self::In) -> Self::Out {
-        let damage_events = world.get_events(&symbol_short!("damage"));
-        let env = soroban_sdk::Env::default();
-        for i in 0..damage_events.len() {
-            let event = damage_events.get(i).unwrap();
-            if let Some(damage_event) = DamageEvent::deserialize(&env, event.data()) {
-                let target_entity = EntityId::new(damage_event.target_entity, 0);
-                // In a real implementation, you'd:
-                // 1. Get the health component from the target entity
-                // 2. Apply the damage
-                // 3. Check if the entity should be destroyed
-                // 4. Update the health component
-            }
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
-    use soroban_sdk::Env;
+    use soroban_sdk::{symbol_short, Env};
 
     #[test]
     fn test_query_creation() {
@@ -334,7 +275,7 @@ mod tests {
 
     #[test]
     fn test_function_system() {
-        let mut system = FunctionSystem::new(|world: &mut World, input: i32| {
+        let mut system = FunctionSystem::new(|_world: &mut World, input: i32| {
             // Simple system that just returns the input
             input
         });
@@ -343,22 +284,4 @@ mod tests {
         let result = system.run(&mut world, 42);
         assert_eq!(result, 42);
     }
-
-    #[test]
-    fn test_movement_system() {
-        let mut system = MovementSystem;
-        let mut world = World::new();
-
-        // This should run without errors
-        system.run(&mut world, ());
-    }
-
-    #[test]
-    fn test_collision_system() {
-        let mut system = CollisionSystem;
-        let mut world = World::new();
-
-        // This should run without errors
-        system.run(&mut world, ());
-    }
 }
@@ -1,12 +1,292 @@
-use crate::components::Position;
+//! Ready-made systems for common game mechanics.
 
-pub struct MovementSystem;
+use crate::component::ComponentTrait;
+use crate::components::{Acceleration, Position, Velocity};
+use crate::system::System;
+use crate::world::World;
+use soroban_sdk::{contracttype, symbol_short, Bytes, Env, Symbol};
+
+/// What happens to an entity that moves outside the [`Bounds`]
+#[contracttype]
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BoundsBehavior {
+    /// Stop at the edge, e.g. paddles and the player's ship
+    #[default]
+    Clamp = 0,
+    /// Reappear on the opposite edge, e.g. asteroids
+    Wrap = 1,
+    /// Reflect off the edge and reverse the velocity, e.g. a pong ball
+    Bounce = 2,
+    /// Despawn the entity, e.g. bullets leaving the screen
+    Despawn = 3,
+}
+
+/// Rectangle entities are kept in, `min` inclusive and `max` exclusive
+#[contracttype]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bounds {
+    pub min_x: i32,
+    pub min_y: i32,
+    pub max_x: i32,
+    pub max_y: i32,
+}
+
+impl Bounds {
+    pub fn new(min_x: i32, min_y: i32, max_x: i32, max_y: i32) -> Self {
+        Self {
+            min_x,
+            min_y,
+            max_x,
+            max_y,
+        }
+    }
+
+    /// Bounds from the origin to `width` by `height`
+    pub fn from_size(width: i32, height: i32) -> Self {
+        Self::new(0, 0, width, height)
+    }
+
+    /// Check if `position` lies inside the bounds
+    pub fn contains(&self, position: &Position) -> bool {
+        (self.min_x..self.max_x).contains(&position.x)
+            && (self.min_y..self.max_y).contains(&position.y)
+    }
+}
+
+/// Per-entity override of the [`MovementSystem`]'s bounds behavior
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bounded(pub BoundsBehavior);
+
+impl ComponentTrait for Bounded {
+    fn component_type() -> Symbol {
+        symbol_short!("bounded")
+    }
+    fn serialize(&self, env: &Env) -> Bytes {
+        Bytes::from_array(env, &[self.0 as u8])
+    }
+    fn deserialize(_env: &Env, data: &Bytes) -> Option<Self> {
+        if data.len() != 1 {
+            return None;
+        }
+        let behavior = match data.get(0)? {
+            0 => BoundsBehavior::Clamp,
+            1 => BoundsBehavior::Wrap,
+            2 => BoundsBehavior::Bounce,
+            3 => BoundsBehavior::Despawn,
+            _ => return None,
+        };
+        Some(Self(behavior))
+    }
+}
+
+/// Keep `value` in `min..max` according to `behavior`
+///
+/// Returns the new value and whether the velocity along this axis reverses,
+/// or `None` if the entity must be despawned.
+fn constrain(value: i32, min: i32, max: i32, behavior: BoundsBehavior) -> Option<(i32, bool)> {
+    if (min..max).contains(&value) || max <= min {
+        return Some((value, false));
+    }
+    let last = max - 1;
+    match behavior {
+        BoundsBehavior::Clamp => Some((value.clamp(min, last), false)),
+        BoundsBehavior::Wrap => {
+            let width = max as i64 - min as i64;
+            Some((
+                (min as i64 + (value as i64 - min as i64).rem_euclid(width)) as i32,
+                false,
+            ))
+        }
+        BoundsBehavior::Bounce => {
+            let reflected = if value < min {
+                2 * min as i64 - value as i64
+            } else {
+                2 * last as i64 - value as i64
+            };
+            Some(((reflected as i32).clamp(min, last), true))
+        }
+        BoundsBehavior::Despawn => None,
+    }
+}
+
+/// Integrates [`Acceleration`] into [`Velocity`] and [`Velocity`] into
+/// [`Position`] once per run
+///
+/// Entities need both a position and a velocity to move. When bounds are
+/// set, entities leaving them are handled by their [`Bounded`] component, or
+/// by the system's default behavior if they have none.
+#[derive(Debug, Clone, Default)]
+pub struct MovementSystem {
+    bounds: Option<Bounds>,
+    behavior: BoundsBehavior,
+}
 
 impl MovementSystem {
-    pub fn update(pos: &Position, dx: i32, dy: i32) -> Position {
-        Position {
-            x: (pos.x as i32 + dx).max(0) as u32,
-            y: (pos.y as i32 + dy).max(0) as u32,
+    /// Create a movement system without bounds
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep entities inside `bounds`, by default using `behavior`
+    pub fn with_bounds(bounds: Bounds, behavior: BoundsBehavior) -> Self {
+        Self {
+            bounds: Some(bounds),
+            behavior,
         }
     }
+
+    /// Get the bounds entities are kept in, if any
+    pub fn bounds(&self) -> Option<Bounds> {
+        self.bounds
+    }
+}
+
+impl System for MovementSystem {
+    type In = ();
+    type Out = ();
+
+    fn run(&mut self, world: &mut World, _input: Self::In) -> Self::Out {
+        let moving =
+            world.query_entities(&[Position::component_type(), Velocity::component_type()]);
+
+        for entity_id in moving.iter() {
+            let (Some(mut position), Some(mut velocity)) = (
+                world.get_typed_component::<Position>(entity_id),
+                world.get_typed_component::<Velocity>(entity_id),
+            ) else {
+                continue;
+            };
+            let original_velocity = velocity;
+
+            if let Some(acceleration) = world.get_typed_component::<Acceleration>(entity_id) {
+                velocity.x = velocity.x.saturating_add(acceleration.x);
+                velocity.y = velocity.y.saturating_add(acceleration.y);
+            }
+            position.x = position.x.saturating_add(velocity.x);
+            position.y = position.y.saturating_add(velocity.y);
+
+            if let Some(bounds) = self.bounds {
+                let behavior = world
+                    .get_typed_component::<Bounded>(entity_id)
+                    .map(|bounded| bounded.0)
+                    .unwrap_or(self.behavior);
+                let constrained = constrain(position.x, bounds.min_x, bounds.max_x, behavior)
+                    .zip(constrain(position.y, bounds.min_y, bounds.max_y, behavior));
+                let Some(((x, flip_x), (y, flip_y))) = constrained else {
+                    world.despawn(entity_id);
+                    continue;
+                };
+                position = Position::new(x, y);
+                if flip_x {
+                    velocity.x = -velocity.x;
+                }
+                if flip_y {
+                    velocity.y = -velocity.y;
+                }
+            }
+
+            world.add_typed_component(entity_id, &position);
+            if velocity != original_velocity {
+                world.add_typed_component(entity_id, &velocity);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::EntityId;
+
+    fn spawn_moving(world: &mut World, position: Position, velocity: Velocity) -> EntityId {
+        let entity_id = world.spawn_empty().id();
+        world.add_typed_component(entity_id, &position);
+        world.add_typed_component(entity_id, &velocity);
+        entity_id
+    }
+
+    fn position(world: &World, entity_id: EntityId) -> Position {
+        world.get_typed_component::<Position>(entity_id).unwrap()
+    }
+
+    #[test]
+    fn test_unbounded_movement_with_acceleration() {
+        let mut world = World::new();
+        let bird = spawn_moving(&mut world, Position::new(10, 50), Velocity::new(0, -3));
+        world.add_typed_component(bird, &Acceleration::new(0, 1));
+        let still = world.spawn_empty().id();
+        world.add_typed_component(still, &Position::new(1, 1));
+
+        let mut system = MovementSystem::new();
+        system.run(&mut world, ());
+        system.run(&mut world, ());
+
+        assert_eq!(position(&world, bird), Position::new(10, 47));
+        assert_eq!(
+            world.get_typed_component::<Velocity>(bird),
+            Some(Velocity::new(0, -1))
+        );
+        assert_eq!(position(&world, still), Position::new(1, 1));
+    }
+
+    #[test]
+    fn test_pong_paddles_clamp_and_ball_bounces() {
+        let mut world = World::new();
+        let paddle = spawn_moving(&mut world, Position::new(2, 44), Velocity::new(0, 2));
+        let ball = spawn_moving(&mut world, Position::new(50, 58), Velocity::new(1, 3));
+        world.add_typed_component(ball, &Bounded(BoundsBehavior::Bounce));
+
+        let mut system =
+            MovementSystem::with_bounds(Bounds::from_size(100, 60), BoundsBehavior::Clamp);
+        for _ in 0..10 {
+            system.run(&mut world, ());
+        }
+
+        assert_eq!(position(&world, paddle), Position::new(2, 59));
+        let ball_position = position(&world, ball);
+        assert!(ball_position.y < 60);
+        assert_eq!(ball_position.x, 60);
+    }
+
+    #[test]
+    fn test_bounce_reverses_velocity() {
+        let mut world = World::new();
+        let ball = spawn_moving(&mut world, Position::new(50, 58), Velocity::new(1, 3));
+        let mut system =
+            MovementSystem::with_bounds(Bounds::from_size(100, 60), BoundsBehavior::Bounce);
+        system.run(&mut world, ());
+        // 58 + 3 = 61 reflects off the last row, 59, to 57
+        assert_eq!(position(&world, ball), Position::new(51, 57));
+        assert_eq!(
+            world.get_typed_component::<Velocity>(ball),
+            Some(Velocity::new(1, -3))
+        );
+    }
+
+    #[test]
+    fn test_asteroids_wrap() {
+        let mut world = World::new();
+        let asteroid = spawn_moving(&mut world, Position::new(998, 1), Velocity::new(5, -3));
+        let mut system =
+            MovementSystem::with_bounds(Bounds::from_size(1000, 1000), BoundsBehavior::Wrap);
+        system.run(&mut world, ());
+        assert_eq!(position(&world, asteroid), Position::new(3, 998));
+    }
+
+    #[test]
+    fn test_space_invaders_bullets_despawn() {
+        let mut world = World::new();
+        let bounds = Bounds::from_size(40, 30);
+        let ship = spawn_moving(&mut world, Position::new(0, 28), Velocity::new(-1, 0));
+        let bullet = spawn_moving(&mut world, Position::new(10, 1), Velocity::new(0, -2));
+        world.add_typed_component(bullet, &Bounded(BoundsBehavior::Despawn));
+
+        let mut system = MovementSystem::with_bounds(bounds, BoundsBehavior::Clamp);
+        system.run(&mut world, ());
+
+        assert!(!world.exists(bullet));
+        assert_eq!(position(&world, ship), Position::new(0, 28));
+        assert!(bounds.contains(&position(&world, ship)));
+    }
 }
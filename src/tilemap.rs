@@ -0,0 +1,598 @@
+//! Tile-based maps.
+//!
+//! A [`TileMap`] stores one tile kind per cell of a `width` by `height` grid,
+//! bit-packed so a map of at most 2, 4 or 16 kinds takes 1, 2 or 4 bits per
+//! tile. Each kind can carry [`TileProperties`] such as being blocked or an
+//! encounter zone. The map is usually kept as a resource of the [`World`], and
+//! entities stand on the tile containing their [`Position`].
+//!
+//! Maps can be saved to contract storage in chunks of [`CHUNK_TILES`] tiles,
+//! so changing a few tiles only rewrites the chunks they are in.
+
+use crate::components::Position;
+use crate::entity::EntityId;
+use crate::resource::ResourceTrait;
+use crate::world::World;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec::Vec as StdVec;
+use core::ops::BitOr;
+use soroban_sdk::{symbol_short, Bytes, Env, Symbol, SymbolStr, TryFromVal};
+
+/// Number of tiles in a persisted chunk, in row-major order
+pub const CHUNK_TILES: u32 = 256;
+
+/// Set of flags shared by every tile of a kind
+///
+/// The low bits are used by the crate; games are free to use the others.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TileProperties(pub u8);
+
+impl TileProperties {
+    pub const NONE: Self = Self(0);
+    /// Entities cannot enter the tile
+    pub const BLOCKED: Self = Self(1);
+    /// Random encounters can happen on the tile
+    pub const ENCOUNTER: Self = Self(1 << 1);
+
+    /// Check whether all flags of `other` are set
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for TileProperties {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+/// Which tiles count as neighbors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connectivity {
+    /// Tiles sharing an edge
+    Four,
+    /// Tiles sharing an edge or a corner
+    Eight,
+}
+
+impl Connectivity {
+    /// Offsets from a tile to its neighbors, edges clockwise from north first
+    pub fn offsets(self) -> &'static [(i32, i32)] {
+        match self {
+            Connectivity::Four => &NEIGHBOR_OFFSETS[..4],
+            Connectivity::Eight => &NEIGHBOR_OFFSETS,
+        }
+    }
+}
+
+/// Offsets of the neighbors of a tile: edges clockwise from north, then corners
+const NEIGHBOR_OFFSETS: [(i32, i32); 8] = [
+    (0, -1),
+    (1, 0),
+    (0, 1),
+    (-1, 0),
+    (1, -1),
+    (1, 1),
+    (-1, 1),
+    (-1, -1),
+];
+
+/// Rectangular grid of bit-packed tile kinds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TileMap {
+    width: u32,
+    height: u32,
+    bits_per_tile: u8,
+    tile_size: i32,
+    tiles: StdVec<u8>,
+    properties: BTreeMap<u8, TileProperties>,
+    /// Chunks changed since the map was last saved or loaded
+    dirty: BTreeSet<u32>,
+    /// Name the map was last saved or loaded as, which `dirty` is relative to
+    saved_as: Option<SymbolStr>,
+}
+
+impl TileMap {
+    /// Create a map of `width` by `height` tiles of kind 0 that can hold tile
+    /// kinds up to `max_kind`
+    ///
+    /// The height is clamped so the map holds at most `u32::MAX` tiles.
+    pub fn new(width: u32, height: u32, max_kind: u8) -> Self {
+        let bits_per_tile = match max_kind {
+            0..=1 => 1,
+            2..=3 => 2,
+            4..=15 => 4,
+            _ => 8,
+        };
+        let height = match width {
+            0 => height,
+            _ => height.min(u32::MAX / width),
+        };
+        let mut map = Self {
+            width,
+            height,
+            bits_per_tile,
+            tile_size: 1,
+            tiles: StdVec::new(),
+            properties: BTreeMap::new(),
+            dirty: BTreeSet::new(),
+            saved_as: None,
+        };
+        map.tiles = alloc::vec![0; map.packed_len()];
+        // A new map has never been saved
+        map.dirty.extend(0..map.chunk_count());
+        map
+    }
+
+    /// Set how many world units a tile spans, 1 by default
+    ///
+    /// Sizes below 1 are raised to 1.
+    pub fn with_tile_size(mut self, tile_size: i32) -> Self {
+        self.tile_size = tile_size.max(1);
+        self
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Get how many world units a tile spans
+    pub fn tile_size(&self) -> i32 {
+        self.tile_size
+    }
+
+    /// Get the largest tile kind the map can hold
+    pub fn max_kind(&self) -> u8 {
+        (u16::MAX >> (16 - self.bits_per_tile)) as u8
+    }
+
+    fn tile_count(&self) -> u32 {
+        self.width * self.height
+    }
+
+    fn packed_len(&self) -> usize {
+        (self.tile_count() as usize * self.bits_per_tile as usize + 7) / 8
+    }
+
+    /// Get the number of chunks the map is saved in
+    pub fn chunk_count(&self) -> u32 {
+        let tiles = self.tile_count();
+        tiles / CHUNK_TILES + u32::from(tiles % CHUNK_TILES != 0)
+    }
+
+    /// Check whether (`x`, `y`) is on the map
+    pub fn in_bounds(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && (x as u32) < self.width && (y as u32) < self.height
+    }
+
+    fn index_of(&self, x: i32, y: i32) -> Option<u32> {
+        self.in_bounds(x, y)
+            .then(|| y as u32 * self.width + x as u32)
+    }
+
+    /// Get the kind of the tile at (`x`, `y`)
+    pub fn get(&self, x: i32, y: i32) -> Option<u8> {
+        let bit = self.index_of(x, y)? as usize * self.bits_per_tile as usize;
+        Some((self.tiles[bit / 8] >> (bit % 8)) & self.max_kind())
+    }
+
+    /// Set the kind of the tile at (`x`, `y`)
+    ///
+    /// Returns `false` if the tile is off the map or the kind is above
+    /// [`TileMap::max_kind`].
+    pub fn set(&mut self, x: i32, y: i32, kind: u8) -> bool {
+        let Some(index) = self.index_of(x, y) else {
+            return false;
+        };
+        if kind > self.max_kind() {
+            return false;
+        }
+        let (bit, mask) = (
+            index as usize * self.bits_per_tile as usize,
+            self.max_kind(),
+        );
+        let byte = &mut self.tiles[bit / 8];
+        *byte = (*byte & !(mask << (bit % 8))) | (kind << (bit % 8));
+        self.dirty.insert(index / CHUNK_TILES);
+        true
+    }
+
+    /// Set every tile in the rectangle from (`min_x`, `min_y`) to (`max_x`,
+    /// `max_y`), both corners included, to `kind`
+    pub fn fill(&mut self, min_x: i32, min_y: i32, max_x: i32, max_y: i32, kind: u8) {
+        for y in min_y.max(0)..=max_y.min(self.height as i32 - 1) {
+            for x in min_x.max(0)..=max_x.min(self.width as i32 - 1) {
+                self.set(x, y, kind);
+            }
+        }
+    }
+
+    /// Set the properties shared by all tiles of `kind`
+    pub fn set_properties(&mut self, kind: u8, properties: TileProperties) {
+        if properties == TileProperties::NONE {
+            self.properties.remove(&kind);
+        } else {
+            self.properties.insert(kind, properties);
+        }
+    }
+
+    /// Get the properties of tiles of `kind`
+    pub fn properties(&self, kind: u8) -> TileProperties {
+        self.properties.get(&kind).copied().unwrap_or_default()
+    }
+
+    /// Get the properties of the tile at (`x`, `y`)
+    pub fn tile_properties(&self, x: i32, y: i32) -> Option<TileProperties> {
+        self.get(x, y).map(|kind| self.properties(kind))
+    }
+
+    /// Check whether the tile at (`x`, `y`) is blocked; tiles off the map are
+    pub fn is_blocked(&self, x: i32, y: i32) -> bool {
+        self.tile_properties(x, y).map_or(true, |properties| {
+            properties.contains(TileProperties::BLOCKED)
+        })
+    }
+
+    /// Check whether the tile at (`x`, `y`) is an encounter zone
+    pub fn is_encounter_zone(&self, x: i32, y: i32) -> bool {
+        self.tile_properties(x, y)
+            .is_some_and(|properties| properties.contains(TileProperties::ENCOUNTER))
+    }
+
+    /// Iterate over the tiles next to (`x`, `y`) that are on the map
+    pub fn neighbors(
+        &self,
+        x: i32,
+        y: i32,
+        connectivity: Connectivity,
+    ) -> impl Iterator<Item = (i32, i32)> + '_ {
+        connectivity
+            .offsets()
+            .iter()
+            .filter_map(move |(dx, dy)| Some((x.checked_add(*dx)?, y.checked_add(*dy)?)))
+            .filter(|(x, y)| self.in_bounds(*x, *y))
+    }
+
+    /// Get the tile containing `position`
+    pub fn tile_at(&self, position: &Position) -> (i32, i32) {
+        (
+            position.x.div_euclid(self.tile_size),
+            position.y.div_euclid(self.tile_size),
+        )
+    }
+
+    /// Get the position of the top-left corner of the tile at (`x`, `y`)
+    pub fn tile_origin(&self, x: i32, y: i32) -> Position {
+        Position::new(
+            x.saturating_mul(self.tile_size),
+            y.saturating_mul(self.tile_size),
+        )
+    }
+
+    /// Get the number of chunks changed since the map was last saved or loaded
+    pub fn dirty_chunk_count(&self) -> u32 {
+        self.dirty.len() as u32
+    }
+
+    fn chunk_range(&self, chunk: u32) -> core::ops::Range<usize> {
+        let bytes_per_chunk = (CHUNK_TILES * self.bits_per_tile as u32 / 8) as usize;
+        let start = chunk as usize * bytes_per_chunk;
+        start..(start + bytes_per_chunk).min(self.tiles.len())
+    }
+
+    fn name_str(env: &Env, name: &Symbol) -> Option<SymbolStr> {
+        SymbolStr::try_from_val(env, &name.to_symbol_val()).ok()
+    }
+
+    fn header_key(name: &Symbol) -> (Symbol, Symbol) {
+        (symbol_short!("tilemap"), name.clone())
+    }
+
+    fn chunk_key(name: &Symbol, chunk: u32) -> (Symbol, Symbol, u32) {
+        (symbol_short!("tilechunk"), name.clone(), chunk)
+    }
+
+    /// Save the map as `name` to the current contract's persistent storage
+    ///
+    /// Only the chunks changed since the map was last saved or loaded as
+    /// `name` are written, along with the map's size and tile properties.
+    /// Saving under another name writes every chunk.
+    pub fn save(&mut self, env: &Env, name: &Symbol) {
+        let name_str = Self::name_str(env, name);
+        if name_str.is_none() || self.saved_as != name_str {
+            self.dirty.extend(0..self.chunk_count());
+        }
+        let storage = env.storage().persistent();
+        storage.set(&Self::header_key(name), &self.serialize_header(env));
+        for chunk in self.dirty.iter() {
+            let chunk_bytes = Bytes::from_slice(env, &self.tiles[self.chunk_range(*chunk)]);
+            storage.set(&Self::chunk_key(name, *chunk), &chunk_bytes);
+        }
+        self.dirty.clear();
+        self.saved_as = name_str;
+    }
+
+    /// Load the map saved as `name` from the current contract's persistent storage
+    pub fn load(env: &Env, name: &Symbol) -> Option<Self> {
+        let storage = env.storage().persistent();
+        let header: Bytes = storage.get(&Self::header_key(name))?;
+        let (mut map, _) = Self::deserialize_header(&header)?;
+        for chunk in 0..map.chunk_count() {
+            let chunk_bytes: Bytes = storage.get(&Self::chunk_key(name, chunk))?;
+            let range = map.chunk_range(chunk);
+            if chunk_bytes.len() as usize != range.len() {
+                return None;
+            }
+            chunk_bytes.copy_into_slice(&mut map.tiles[range]);
+        }
+        map.dirty.clear();
+        map.saved_as = Self::name_str(env, name);
+        Some(map)
+    }
+
+    fn serialize_header(&self, env: &Env) -> Bytes {
+        let mut bytes = Bytes::new(env);
+        bytes.extend_from_array(&self.width.to_be_bytes());
+        bytes.extend_from_array(&self.height.to_be_bytes());
+        bytes.extend_from_array(&self.tile_size.to_be_bytes());
+        bytes.push_back(self.bits_per_tile);
+        // All 256 kinds can have properties, one more than a byte counts
+        bytes.extend_from_array(&(self.properties.len() as u16).to_be_bytes());
+        for (kind, properties) in self.properties.iter() {
+            bytes.extend_from_array(&[*kind, properties.0]);
+        }
+        bytes
+    }
+
+    /// Decode a header into an empty map, returning it with the header length
+    fn deserialize_header(data: &Bytes) -> Option<(Self, u32)> {
+        if data.len() < 15 {
+            return None;
+        }
+        let mut buf = [0u8; 15];
+        data.slice(0..15).copy_into_slice(&mut buf);
+        let word = |offset: usize| {
+            [
+                buf[offset],
+                buf[offset + 1],
+                buf[offset + 2],
+                buf[offset + 3],
+            ]
+        };
+        let bits_per_tile = buf[12];
+        let max_kind = match bits_per_tile {
+            1 => 1,
+            2 => 3,
+            4 => 15,
+            8 => u8::MAX,
+            _ => return None,
+        };
+        let (width, height) = (u32::from_be_bytes(word(0)), u32::from_be_bytes(word(4)));
+        width.checked_mul(height)?;
+        let mut map =
+            Self::new(width, height, max_kind).with_tile_size(i32::from_be_bytes(word(8)));
+
+        let property_count = u16::from_be_bytes([buf[13], buf[14]]) as u32;
+        let header_len = 15 + property_count * 2;
+        if data.len() < header_len {
+            return None;
+        }
+        for i in 0..property_count {
+            let offset = 15 + i * 2;
+            map.set_properties(data.get(offset)?, TileProperties(data.get(offset + 1)?));
+        }
+        Some((map, header_len))
+    }
+}
+
+impl ResourceTrait for TileMap {
+    fn resource_type() -> Symbol {
+        symbol_short!("tilemap")
+    }
+    fn serialize(&self, env: &Env) -> Bytes {
+        let mut bytes = self.serialize_header(env);
+        bytes.extend_from_slice(&self.tiles);
+        bytes
+    }
+    fn deserialize(_env: &Env, data: &Bytes) -> Option<Self> {
+        let (mut map, header_len) = Self::deserialize_header(data)?;
+        if data.len() as usize != header_len as usize + map.tiles.len() {
+            return None;
+        }
+        data.slice(header_len..).copy_into_slice(&mut map.tiles);
+        map.dirty.clear();
+        Some(map)
+    }
+}
+
+impl World {
+    /// Get the entities whose [`Position`] lies on the tile at (`x`, `y`) of `map`
+    ///
+    /// Uses the spatial hash when it is enabled, and otherwise checks every
+    /// positioned entity. Entities are ordered by id.
+    pub fn entities_on_tile(&self, map: &TileMap, x: i32, y: i32) -> StdVec<EntityId> {
+        let min = map.tile_origin(x, y);
+        let max = Position::new(
+            min.x.saturating_add(map.tile_size() - 1),
+            min.y.saturating_add(map.tile_size() - 1),
+        );
+        if let Some(spatial) = self.spatial_hash() {
+            return spatial.entities_in_rect(&min, &max);
+        }
+        let mut found: StdVec<EntityId> = self
+            .iter_entities()
+            .map(|entity| entity.id())
+            .filter(|entity_id| {
+                self.get_typed_component::<Position>(*entity_id)
+                    .is_some_and(|position| map.tile_at(&position) == (x, y))
+            })
+            .collect();
+        found.sort();
+        found
+    }
+
+    /// Check whether any entity stands on the tile at (`x`, `y`) of `map`
+    pub fn is_tile_occupied(&self, map: &TileMap, x: i32, y: i32) -> bool {
+        !self.entities_on_tile(map, x, y).is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use soroban_sdk::{contract, Env};
+
+    #[contract]
+    struct TileMapTestContract;
+
+    const GRASS: u8 = 1;
+    const TREE: u8 = 2;
+    const WATER: u8 = 3;
+
+    fn route() -> TileMap {
+        let mut map = TileMap::new(20, 30, WATER);
+        map.set_properties(TREE, TileProperties::BLOCKED);
+        map.set_properties(WATER, TileProperties::BLOCKED);
+        map.set_properties(GRASS, TileProperties::ENCOUNTER);
+        map.fill(2, 2, 5, 4, GRASS);
+        map.set(0, 0, TREE);
+        map.set(19, 29, WATER);
+        map
+    }
+
+    #[test]
+    fn test_bit_packed_tiles() {
+        let map = route();
+        assert_eq!(map.max_kind(), 3);
+        // 600 tiles at 2 bits each
+        assert_eq!(map.tiles.len(), 150);
+        assert_eq!(map.get(0, 0), Some(TREE));
+        assert_eq!(map.get(1, 0), Some(0));
+        assert_eq!(map.get(5, 4), Some(GRASS));
+        assert_eq!(map.get(6, 4), Some(0));
+        assert_eq!(map.get(19, 29), Some(WATER));
+        assert_eq!(map.get(20, 0), None);
+        assert_eq!(map.get(-1, 0), None);
+
+        let mut map = map;
+        assert!(!map.set(1, 1, 4));
+        assert!(!map.set(0, 30, GRASS));
+        assert!(map.set(0, 0, 0));
+        assert_eq!(map.get(0, 0), Some(0));
+        assert_eq!(map.get(1, 0), Some(0));
+        assert_eq!(TileMap::new(3, 3, 200).max_kind(), u8::MAX);
+    }
+
+    #[test]
+    fn test_tile_properties() {
+        let map = route();
+        assert!(map.is_blocked(0, 0));
+        assert!(!map.is_blocked(1, 0));
+        assert!(map.is_blocked(-1, 0));
+        assert!(map.is_encounter_zone(3, 3));
+        assert!(!map.is_encounter_zone(1, 1));
+        let both = TileProperties::BLOCKED | TileProperties::ENCOUNTER;
+        assert!(both.contains(TileProperties::ENCOUNTER));
+        assert!(!TileProperties::BLOCKED.contains(both));
+    }
+
+    #[test]
+    fn test_neighbors() {
+        let map = TileMap::new(3, 3, 1);
+        let four: StdVec<_> = map.neighbors(1, 1, Connectivity::Four).collect();
+        assert_eq!(four, [(1, 0), (2, 1), (1, 2), (0, 1)]);
+        assert_eq!(map.neighbors(1, 1, Connectivity::Eight).count(), 8);
+        let corner: StdVec<_> = map.neighbors(0, 0, Connectivity::Eight).collect();
+        assert_eq!(corner, [(1, 0), (0, 1), (1, 1)]);
+    }
+
+    #[test]
+    fn test_resource_roundtrip() {
+        let env = Env::default();
+        let mut world = World::with_env(&env);
+        let map = route().with_tile_size(16);
+        world.set_typed_resource(&map);
+
+        let mut stored = world.get_typed_resource::<TileMap>().unwrap();
+        assert_eq!(stored.tiles, map.tiles);
+        assert_eq!(stored.properties, map.properties);
+        assert_eq!(stored.tile_size(), 16);
+        assert_eq!(stored.dirty_chunk_count(), 0);
+        stored.set(4, 4, TREE);
+        assert_eq!(stored.dirty_chunk_count(), 1);
+
+        // Every kind of an 8-bit map can have properties
+        let mut full = TileMap::new(2, 2, u8::MAX);
+        for kind in 0..=u8::MAX {
+            full.set_properties(kind, TileProperties::ENCOUNTER);
+        }
+        world.set_typed_resource(&full);
+        let stored = world.get_typed_resource::<TileMap>().unwrap();
+        assert_eq!(stored.properties(u8::MAX), TileProperties::ENCOUNTER);
+        assert_eq!(stored.properties, full.properties);
+    }
+
+    #[test]
+    fn test_chunked_persistence() {
+        let env = Env::default();
+        let contract_id = env.register(TileMapTestContract, ());
+        env.as_contract(&contract_id, || {
+            let name = symbol_short!("route1");
+            let mut map = route();
+            assert_eq!(map.chunk_count(), 3);
+            assert_eq!(map.dirty_chunk_count(), 3);
+            map.save(&env, &name);
+            assert_eq!(map.dirty_chunk_count(), 0);
+
+            map.set(10, 25, TREE);
+            assert_eq!(map.dirty_chunk_count(), 1);
+            map.save(&env, &name);
+
+            let loaded = TileMap::load(&env, &name).unwrap();
+            assert_eq!(loaded, map);
+            assert_eq!(loaded.get(10, 25), Some(TREE));
+            assert!(TileMap::load(&env, &symbol_short!("route2")).is_none());
+
+            // Saving a loaded map under another name writes all of it
+            let copy = symbol_short!("route2");
+            let mut loaded = TileMap::load(&env, &name).unwrap();
+            loaded.save(&env, &copy);
+            assert_eq!(TileMap::load(&env, &copy).unwrap().get(10, 25), Some(TREE));
+            // as does saving a map kept as a resource
+            let mut world = World::with_env(&env);
+            world.set_typed_resource(&map);
+            let mut resource = world.get_typed_resource::<TileMap>().unwrap();
+            resource.set(0, 0, TREE);
+            resource.save(&env, &symbol_short!("route3"));
+            let restored = TileMap::load(&env, &symbol_short!("route3")).unwrap();
+            assert_eq!(restored.get(0, 0), Some(TREE));
+            assert_eq!(restored.get(10, 25), Some(TREE));
+        });
+    }
+
+    #[test]
+    fn test_occupancy() {
+        let mut world = World::new();
+        let map = TileMap::new(10, 10, 1).with_tile_size(8);
+        let player = world.spawn_empty().id();
+        world.add_typed_component(player, &Position::new(17, 23));
+        let npc = world.spawn_empty().id();
+        world.add_typed_component(npc, &Position::new(23, 16));
+
+        assert_eq!(map.tile_at(&Position::new(17, 23)), (2, 2));
+        assert_eq!(world.entities_on_tile(&map, 2, 2), [player, npc]);
+        assert!(!world.is_tile_occupied(&map, 3, 2));
+
+        world.enable_spatial_hash(8);
+        assert_eq!(world.entities_on_tile(&map, 2, 2), [player, npc]);
+        world.add_typed_component(npc, &Position::new(24, 16));
+        assert_eq!(world.entities_on_tile(&map, 2, 2), [player]);
+        assert_eq!(world.entities_on_tile(&map, 3, 2), [npc]);
+    }
+}
@@ -0,0 +1,487 @@
+//! Timers, cooldowns and lifetimes.
+//!
+//! Each of these components counts down to a [`Deadline`], measured in one of
+//! three [`TimeSource`]s: ticks of the world, i.e. runs of [`TimerSystem`],
+//! ledger sequence numbers, or ledger close timestamps in seconds. Tick
+//! deadlines hold the number of ticks left; ledger deadlines hold the ledger
+//! sequence or timestamp they expire at.
+//!
+//! [`TimerSystem`] advances them: finished [`Timer`]s send a
+//! [`TimerFinishedEvent`], and entities whose [`Lifetime`] ran out despawn.
+
+use crate::component::ComponentTrait;
+use crate::entity::EntityId;
+use crate::event::TimerFinishedEvent;
+use crate::system::System;
+use crate::world::World;
+use soroban_sdk::{symbol_short, Bytes, Env, Symbol};
+
+/// What a [`Deadline`] is measured in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum TimeSource {
+    /// Runs of the [`TimerSystem`]
+    Ticks = 0,
+    /// Ledger sequence numbers
+    LedgerSequence = 1,
+    /// Ledger close timestamps, in seconds
+    LedgerTimestamp = 2,
+}
+
+impl TimeSource {
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::Ticks),
+            1 => Some(Self::LedgerSequence),
+            2 => Some(Self::LedgerTimestamp),
+            _ => None,
+        }
+    }
+
+    /// Get the current ledger sequence or timestamp; ticks have no absolute time
+    fn now(self, env: &Env) -> u64 {
+        match self {
+            Self::Ticks => 0,
+            Self::LedgerSequence => env.ledger().sequence() as u64,
+            Self::LedgerTimestamp => env.ledger().timestamp(),
+        }
+    }
+}
+
+/// Point in time something expires at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deadline {
+    pub source: TimeSource,
+    /// Ticks left, or the ledger sequence or timestamp of expiry
+    pub value: u64,
+}
+
+impl Deadline {
+    /// Size of an encoded deadline
+    pub const ENCODED_LEN: u32 = 9;
+
+    /// Deadline `ticks` runs of the [`TimerSystem`] from now
+    pub fn ticks(ticks: u64) -> Self {
+        Self {
+            source: TimeSource::Ticks,
+            value: ticks,
+        }
+    }
+
+    /// Deadline `amount` ticks, ledgers or seconds from now
+    pub fn after(env: &Env, source: TimeSource, amount: u64) -> Self {
+        Self {
+            source,
+            value: source.now(env).saturating_add(amount),
+        }
+    }
+
+    /// Check whether the deadline has passed
+    pub fn is_expired(&self, env: &Env) -> bool {
+        match self.source {
+            TimeSource::Ticks => self.value == 0,
+            source => source.now(env) >= self.value,
+        }
+    }
+
+    /// Get the ticks, ledgers or seconds left until the deadline
+    pub fn remaining(&self, env: &Env) -> u64 {
+        self.value.saturating_sub(self.source.now(env))
+    }
+
+    /// Count one tick down; ledger deadlines are unaffected
+    pub fn tick(&mut self) {
+        if self.source == TimeSource::Ticks {
+            self.value = self.value.saturating_sub(1);
+        }
+    }
+
+    /// Move the deadline `period` later, and past the current ledger if needed
+    fn reschedule(&mut self, env: &Env, period: u64) {
+        match self.source {
+            TimeSource::Ticks => self.value = period,
+            source => {
+                let now = source.now(env);
+                self.value = self.value.saturating_add(period);
+                if self.value <= now && period > 0 {
+                    // Skip the periods missed while no ledger ran the system
+                    let missed = (now - self.value) / period + 1;
+                    self.value = self.value.saturating_add(missed.saturating_mul(period));
+                }
+            }
+        }
+    }
+
+    fn write(&self, bytes: &mut Bytes) {
+        bytes.push_back(self.source as u8);
+        bytes.extend_from_array(&self.value.to_be_bytes());
+    }
+
+    fn read(data: &Bytes, offset: u32) -> Option<Self> {
+        let source = TimeSource::from_u8(data.get(offset)?)?;
+        Some(Self {
+            source,
+            value: read_u64(data, offset + 1)?,
+        })
+    }
+}
+
+fn read_u64(data: &Bytes, offset: u32) -> Option<u64> {
+    let mut buf = [0u8; 8];
+    if data.len() < offset.checked_add(8)? {
+        return None;
+    }
+    data.slice(offset..offset + 8).copy_into_slice(&mut buf);
+    Some(u64::from_be_bytes(buf))
+}
+
+/// Sends a [`TimerFinishedEvent`] when its deadline passes
+///
+/// One-shot timers are removed once they finish; repeating timers start over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timer {
+    pub deadline: Deadline,
+    /// Time between repeats in the deadline's source, or 0 for a one-shot timer
+    pub period: u64,
+}
+
+impl Timer {
+    /// Timer that finishes once, at `deadline`
+    pub fn once(deadline: Deadline) -> Self {
+        Self {
+            deadline,
+            period: 0,
+        }
+    }
+
+    /// Timer that finishes at `deadline` and then every `period`
+    pub fn repeating(deadline: Deadline, period: u64) -> Self {
+        Self { deadline, period }
+    }
+}
+
+impl ComponentTrait for Timer {
+    fn component_type() -> Symbol {
+        symbol_short!("timer")
+    }
+    fn serialize(&self, env: &Env) -> Bytes {
+        let mut bytes = Bytes::new(env);
+        self.deadline.write(&mut bytes);
+        bytes.extend_from_array(&self.period.to_be_bytes());
+        bytes
+    }
+    fn deserialize(_env: &Env, data: &Bytes) -> Option<Self> {
+        if data.len() != Deadline::ENCODED_LEN + 8 {
+            return None;
+        }
+        Some(Self {
+            deadline: Deadline::read(data, 0)?,
+            period: read_u64(data, Deadline::ENCODED_LEN)?,
+        })
+    }
+}
+
+/// Minimum time between two uses of an ability, e.g. firing a weapon
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cooldown {
+    /// Time a use blocks the next one for, in the source of `ready`
+    pub duration: u64,
+    /// When the next use is allowed
+    pub ready: Deadline,
+}
+
+impl Cooldown {
+    /// Cooldown of `duration` measured in `source`, ready to be used
+    pub fn new(source: TimeSource, duration: u64) -> Self {
+        Self {
+            duration,
+            ready: Deadline { source, value: 0 },
+        }
+    }
+
+    /// Check whether the ability can be used
+    pub fn is_ready(&self, env: &Env) -> bool {
+        self.ready.is_expired(env)
+    }
+
+    /// Use the ability if it is ready, starting the cooldown
+    ///
+    /// Returns `false` if the cooldown has not finished yet.
+    pub fn try_use(&mut self, env: &Env) -> bool {
+        if !self.is_ready(env) {
+            return false;
+        }
+        self.ready = Deadline::after(env, self.ready.source, self.duration);
+        true
+    }
+}
+
+impl ComponentTrait for Cooldown {
+    fn component_type() -> Symbol {
+        symbol_short!("cooldown")
+    }
+    fn serialize(&self, env: &Env) -> Bytes {
+        let mut bytes = Bytes::from_array(env, &self.duration.to_be_bytes());
+        self.ready.write(&mut bytes);
+        bytes
+    }
+    fn deserialize(_env: &Env, data: &Bytes) -> Option<Self> {
+        if data.len() != 8 + Deadline::ENCODED_LEN {
+            return None;
+        }
+        Some(Self {
+            duration: read_u64(data, 0)?,
+            ready: Deadline::read(data, 8)?,
+        })
+    }
+}
+
+/// Despawns the entity once its deadline passes, e.g. a bullet's time to live
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lifetime(pub Deadline);
+
+impl ComponentTrait for Lifetime {
+    fn component_type() -> Symbol {
+        symbol_short!("lifetime")
+    }
+    fn serialize(&self, env: &Env) -> Bytes {
+        let mut bytes = Bytes::new(env);
+        self.0.write(&mut bytes);
+        bytes
+    }
+    fn deserialize(_env: &Env, data: &Bytes) -> Option<Self> {
+        if data.len() != Deadline::ENCODED_LEN {
+            return None;
+        }
+        Deadline::read(data, 0).map(Self)
+    }
+}
+
+/// Advances [`Timer`], [`Cooldown`] and [`Lifetime`] components by one tick
+/// and checks ledger deadlines against the current ledger
+#[derive(Debug, Clone, Default)]
+pub struct TimerSystem;
+
+impl TimerSystem {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl System for TimerSystem {
+    type In = ();
+    type Out = ();
+
+    fn run(&mut self, world: &mut World, _input: Self::In) -> Self::Out {
+        let env = world.env().clone();
+
+        for entity_id in world.query_entities(&[Timer::component_type()]).iter() {
+            let Some(mut timer) = world.get_typed_component::<Timer>(entity_id) else {
+                continue;
+            };
+            timer.deadline.tick();
+            if !timer.deadline.is_expired(&env) {
+                if timer.deadline.source == TimeSource::Ticks {
+                    world.add_typed_component(entity_id, &timer);
+                }
+                continue;
+            }
+            world.send_typed_event(&TimerFinishedEvent::new(entity_id));
+            if timer.period == 0 {
+                world.remove_component_from_entity(entity_id, &Timer::component_type());
+            } else {
+                timer.deadline.reschedule(&env, timer.period);
+                world.add_typed_component(entity_id, &timer);
+            }
+        }
+
+        for entity_id in world.query_entities(&[Cooldown::component_type()]).iter() {
+            if let Some(mut cooldown) = world.get_typed_component::<Cooldown>(entity_id) {
+                if cooldown.ready.source == TimeSource::Ticks && cooldown.ready.value > 0 {
+                    cooldown.ready.tick();
+                    world.add_typed_component(entity_id, &cooldown);
+                }
+            }
+        }
+
+        let mut expired = alloc::vec::Vec::new();
+        for entity_id in world.query_entities(&[Lifetime::component_type()]).iter() {
+            let Some(mut lifetime) = world.get_typed_component::<Lifetime>(entity_id) else {
+                continue;
+            };
+            lifetime.0.tick();
+            if lifetime.0.is_expired(&env) {
+                expired.push(entity_id);
+            } else if lifetime.0.source == TimeSource::Ticks {
+                world.add_typed_component(entity_id, &lifetime);
+            }
+        }
+        for entity_id in expired {
+            world.despawn(entity_id);
+        }
+    }
+}
+
+impl World {
+    /// Use the ability guarded by the [`Cooldown`] of `entity_id` if it is ready
+    ///
+    /// Returns `false`, leaving the cooldown untouched, if the entity has no
+    /// cooldown or it has not finished yet.
+    pub fn try_use_cooldown(&mut self, entity_id: EntityId) -> bool {
+        let Some(mut cooldown) = self.get_typed_component::<Cooldown>(entity_id) else {
+            return false;
+        };
+        if !cooldown.try_use(self.env()) {
+            return false;
+        }
+        self.add_typed_component(entity_id, &cooldown);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use soroban_sdk::testutils::Ledger;
+
+    #[test]
+    fn test_component_roundtrip() {
+        let env = Env::default();
+        let timer = Timer::repeating(Deadline::after(&env, TimeSource::LedgerSequence, 5), 5);
+        assert_eq!(
+            Timer::deserialize(&env, &timer.serialize(&env)),
+            Some(timer)
+        );
+        let cooldown = Cooldown::new(TimeSource::LedgerTimestamp, 30);
+        assert_eq!(
+            Cooldown::deserialize(&env, &cooldown.serialize(&env)),
+            Some(cooldown)
+        );
+        let lifetime = Lifetime(Deadline::ticks(40));
+        assert_eq!(
+            Lifetime::deserialize(&env, &lifetime.serialize(&env)),
+            Some(lifetime)
+        );
+        let mut corrupt = lifetime.serialize(&env);
+        corrupt.set(0, 7);
+        assert_eq!(Lifetime::deserialize(&env, &corrupt), None);
+    }
+
+    #[test]
+    fn test_bullets_expire_after_their_ttl() {
+        let mut world = World::new();
+        let bullet = world.spawn_empty().id();
+        world.add_typed_component(bullet, &Lifetime(Deadline::ticks(3)));
+        let ship = world.spawn_empty().id();
+
+        let mut system = TimerSystem::new();
+        system.run(&mut world, ());
+        system.run(&mut world, ());
+        assert!(world.exists(bullet));
+        assert_eq!(
+            world.get_typed_component::<Lifetime>(bullet),
+            Some(Lifetime(Deadline::ticks(1)))
+        );
+        system.run(&mut world, ());
+        assert!(!world.exists(bullet));
+        assert!(world.exists(ship));
+    }
+
+    #[test]
+    fn test_timers_finish_and_repeat() {
+        let mut world = World::new();
+        let bomb = world.spawn_empty().id();
+        world.add_typed_component(bomb, &Timer::once(Deadline::ticks(2)));
+        let spawner = world.spawn_empty().id();
+        world.add_typed_component(spawner, &Timer::repeating(Deadline::ticks(1), 2));
+
+        let mut system = TimerSystem::new();
+        let mut finished = alloc::vec::Vec::new();
+        for _ in 0..5 {
+            system.run(&mut world, ());
+            let events = world.get_typed_events::<TimerFinishedEvent>();
+            finished.push(
+                events
+                    .iter()
+                    .map(|event| event.entity)
+                    .collect::<alloc::vec::Vec<_>>(),
+            );
+            world.clear_events();
+        }
+        assert_eq!(
+            finished,
+            [
+                alloc::vec![spawner],
+                alloc::vec![bomb],
+                alloc::vec![spawner],
+                alloc::vec![],
+                alloc::vec![spawner],
+            ]
+        );
+        assert!(!world.has_component(bomb, &Timer::component_type()));
+        assert!(world.has_component(spawner, &Timer::component_type()));
+    }
+
+    #[test]
+    fn test_ledger_deadlines() {
+        let env = Env::default();
+        env.ledger().set_sequence_number(100);
+        env.ledger().set_timestamp(1_000);
+        let mut world = World::with_env(&env);
+        let egg = world.spawn_empty().id();
+        world.add_typed_component(
+            egg,
+            &Timer::repeating(Deadline::after(&env, TimeSource::LedgerSequence, 10), 10),
+        );
+        let buff = world.spawn_empty().id();
+        world.add_typed_component(
+            buff,
+            &Lifetime(Deadline::after(&env, TimeSource::LedgerTimestamp, 60)),
+        );
+
+        let mut system = TimerSystem::new();
+        system.run(&mut world, ());
+        assert!(world.get_typed_events::<TimerFinishedEvent>().is_empty());
+
+        // Three periods pass between two runs: the timer fires once
+        env.ledger().set_sequence_number(135);
+        env.ledger().set_timestamp(1_059);
+        system.run(&mut world, ());
+        assert_eq!(world.get_typed_events::<TimerFinishedEvent>().len(), 1);
+        let timer = world.get_typed_component::<Timer>(egg).unwrap();
+        assert_eq!(timer.deadline.value, 140);
+        assert_eq!(timer.deadline.remaining(&env), 5);
+        assert!(world.exists(buff));
+
+        env.ledger().set_timestamp(1_060);
+        system.run(&mut world, ());
+        assert!(!world.exists(buff));
+    }
+
+    #[test]
+    fn test_weapon_cooldowns() {
+        let env = Env::default();
+        env.ledger().set_timestamp(500);
+        let mut world = World::with_env(&env);
+        let cannon = world.spawn_empty().id();
+        world.add_typed_component(cannon, &Cooldown::new(TimeSource::Ticks, 2));
+        let laser = world.spawn_empty().id();
+        world.add_typed_component(laser, &Cooldown::new(TimeSource::LedgerTimestamp, 30));
+
+        let mut system = TimerSystem::new();
+        assert!(world.try_use_cooldown(cannon));
+        assert!(!world.try_use_cooldown(cannon));
+        system.run(&mut world, ());
+        assert!(!world.try_use_cooldown(cannon));
+        system.run(&mut world, ());
+        assert!(world.try_use_cooldown(cannon));
+
+        assert!(world.try_use_cooldown(laser));
+        env.ledger().set_timestamp(529);
+        assert!(!world.try_use_cooldown(laser));
+        env.ledger().set_timestamp(530);
+        assert!(world.try_use_cooldown(laser));
+        let rock = world.spawn_empty().id();
+        assert!(!world.try_use_cooldown(rock));
+    }
+}
@@ -0,0 +1,447 @@
+//! Turn-based game flow.
+//!
+//! A [`TurnManager`] resource keeps the ordered participants of a game, whose
+//! turn it is, the phase within that turn and how many rounds were played.
+//! Games describe their moves as [`TurnAction`]s and submit them through
+//! [`World::take_turn`], which rejects actions from participants whose turn it
+//! is not, runs the action's validation, applies it and advances the turn.
+
+use crate::entity::EntityId;
+use crate::resource::ResourceTrait;
+use crate::world::World;
+use alloc::vec::Vec as StdVec;
+use soroban_sdk::{symbol_short, Bytes, Env, Symbol};
+
+/// Errors returned when an action cannot be taken
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TurnError {
+    /// The world has no [`TurnManager`] resource
+    NoTurnManager,
+    /// Nobody is taking part in the game
+    NoParticipants,
+    /// The actor is not a participant
+    NotParticipant,
+    /// The actor is a participant, but it is another participant's turn
+    NotYourTurn,
+    /// The action is not allowed in the current phase
+    WrongPhase,
+    /// The action was rejected by its validation, with a game-defined code
+    InvalidAction(u32),
+}
+
+/// Something a participant can do on their turn
+pub trait TurnAction {
+    /// Phase the action may be taken in, or `None` for any phase
+    fn phase(&self) -> Option<u32> {
+        None
+    }
+
+    /// Check that `actor` may take this action in the current state of `world`
+    fn validate(&self, _world: &World, _actor: EntityId) -> Result<(), TurnError> {
+        Ok(())
+    }
+
+    /// Carry out the action
+    fn apply(&self, world: &mut World, actor: EntityId);
+}
+
+/// Order of play among the participants of a turn-based game
+///
+/// Each turn goes through its phases in order, numbered from 0; games
+/// usually name them with constants. A round ends once every participant has
+/// had a turn.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TurnManager {
+    participants: StdVec<EntityId>,
+    phase_count: u32,
+    current: u32,
+    phase: u32,
+    round: u32,
+    turn: u32,
+    auto_advance: bool,
+}
+
+impl TurnManager {
+    /// Create a turn manager where `participants` play in the given order,
+    /// starting with the first one in round 1
+    pub fn new(participants: StdVec<EntityId>) -> Self {
+        Self {
+            participants,
+            phase_count: 1,
+            current: 0,
+            phase: 0,
+            round: 1,
+            turn: 1,
+            auto_advance: true,
+        }
+    }
+
+    /// Split every turn into `phase_count` phases, 1 by default
+    pub fn with_phases(mut self, phase_count: u32) -> Self {
+        self.phase_count = phase_count.max(1);
+        self.phase = 0;
+        self
+    }
+
+    /// Choose whether accepted actions end the current phase, `true` by default
+    ///
+    /// Without automatic advance, [`TurnManager::end_phase`] and
+    /// [`TurnManager::end_turn`] must be called explicitly.
+    pub fn with_auto_advance(mut self, auto_advance: bool) -> Self {
+        self.auto_advance = auto_advance;
+        self
+    }
+
+    /// Get the participants in order of play
+    pub fn participants(&self) -> &[EntityId] {
+        &self.participants
+    }
+
+    /// Add a participant who plays last in every round
+    ///
+    /// Returns `false` if the entity already takes part.
+    pub fn add_participant(&mut self, entity_id: EntityId) -> bool {
+        if self.participants.contains(&entity_id) {
+            return false;
+        }
+        self.participants.push(entity_id);
+        true
+    }
+
+    /// Remove a participant, e.g. one that was eliminated
+    ///
+    /// If it was their turn, the next participant's turn starts.
+    pub fn remove_participant(&mut self, entity_id: EntityId) -> bool {
+        let Some(index) = self.participants.iter().position(|p| *p == entity_id) else {
+            return false;
+        };
+        self.participants.remove(index);
+        let index = index as u32;
+        if index < self.current {
+            self.current -= 1;
+        } else if index == self.current {
+            self.phase = 0;
+            if self.current as usize >= self.participants.len() {
+                self.current = 0;
+                self.round += 1;
+            }
+            self.turn += 1;
+        }
+        true
+    }
+
+    /// Get the participant whose turn it is
+    pub fn current_actor(&self) -> Option<EntityId> {
+        self.participants.get(self.current as usize).copied()
+    }
+
+    /// Get the current phase
+    pub fn phase(&self) -> u32 {
+        self.phase
+    }
+
+    /// Get the number of phases in a turn
+    pub fn phase_count(&self) -> u32 {
+        self.phase_count
+    }
+
+    /// Get the number of the current round, starting at 1
+    pub fn round(&self) -> u32 {
+        self.round
+    }
+
+    /// Get the number of the current turn across all rounds, starting at 1
+    pub fn turn(&self) -> u32 {
+        self.turn
+    }
+
+    /// Check that it is `actor`'s turn
+    pub fn check_turn(&self, actor: EntityId) -> Result<(), TurnError> {
+        match self.current_actor() {
+            None => Err(TurnError::NoParticipants),
+            Some(current) if current == actor => Ok(()),
+            Some(_) if self.participants.contains(&actor) => Err(TurnError::NotYourTurn),
+            Some(_) => Err(TurnError::NotParticipant),
+        }
+    }
+
+    /// Move on to the next phase, or to the next turn after the last phase
+    pub fn end_phase(&mut self) {
+        if self.phase + 1 < self.phase_count {
+            self.phase += 1;
+        } else {
+            self.end_turn();
+        }
+    }
+
+    /// Pass the turn to the next participant, starting a new round after the last
+    pub fn end_turn(&mut self) {
+        if self.participants.is_empty() {
+            return;
+        }
+        self.phase = 0;
+        self.turn += 1;
+        self.current += 1;
+        if self.current as usize >= self.participants.len() {
+            self.current = 0;
+            self.round += 1;
+        }
+    }
+}
+
+impl ResourceTrait for TurnManager {
+    fn resource_type() -> Symbol {
+        symbol_short!("turns")
+    }
+    fn serialize(&self, env: &Env) -> Bytes {
+        let mut bytes = Bytes::new(env);
+        for value in [
+            self.phase_count,
+            self.current,
+            self.phase,
+            self.round,
+            self.turn,
+        ] {
+            bytes.extend_from_array(&value.to_be_bytes());
+        }
+        bytes.push_back(self.auto_advance as u8);
+        bytes.extend_from_array(&(self.participants.len() as u32).to_be_bytes());
+        for participant in self.participants.iter() {
+            bytes.append(&participant.to_bytes(env));
+        }
+        bytes
+    }
+    fn deserialize(_env: &Env, data: &Bytes) -> Option<Self> {
+        let read_u32 = |offset: u32| -> Option<u32> {
+            let mut buf = [0u8; 4];
+            data.slice(offset..offset.checked_add(4)?)
+                .copy_into_slice(&mut buf);
+            Some(u32::from_be_bytes(buf))
+        };
+        if data.len() < 25 {
+            return None;
+        }
+        let count = read_u32(21)?;
+        if data.len() != count.checked_mul(EntityId::ENCODED_LEN)?.checked_add(25)? {
+            return None;
+        }
+        let participants = (0..count)
+            .map(|i| EntityId::from_bytes(data, 25 + i * EntityId::ENCODED_LEN))
+            .collect::<Option<_>>()?;
+        Some(Self {
+            participants,
+            phase_count: read_u32(0)?,
+            current: read_u32(4)?,
+            phase: read_u32(8)?,
+            round: read_u32(12)?,
+            turn: read_u32(16)?,
+            auto_advance: data.get(20)? != 0,
+        })
+    }
+}
+
+impl World {
+    /// Have `actor` take `action` on their turn
+    ///
+    /// The action is rejected if the world has no [`TurnManager`], if it is
+    /// not `actor`'s turn, if it belongs to another phase or if its
+    /// validation fails. Otherwise it is applied and, with automatic advance,
+    /// the current phase ends. Actions may change the turn manager themselves,
+    /// e.g. to remove an eliminated participant; the turn then only advances
+    /// if `actor` still has it.
+    pub fn take_turn<A: TurnAction>(
+        &mut self,
+        actor: EntityId,
+        action: &A,
+    ) -> Result<(), TurnError> {
+        let turns = self
+            .get_typed_resource::<TurnManager>()
+            .ok_or(TurnError::NoTurnManager)?;
+        turns.check_turn(actor)?;
+        if let Some(phase) = action.phase() {
+            if turns.phase() != phase {
+                return Err(TurnError::WrongPhase);
+            }
+        }
+        action.validate(self, actor)?;
+
+        action.apply(self, actor);
+
+        let mut turns = self
+            .get_typed_resource::<TurnManager>()
+            .ok_or(TurnError::NoTurnManager)?;
+        if turns.auto_advance && turns.current_actor() == Some(actor) {
+            turns.end_phase();
+            self.set_typed_resource(&turns);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::ComponentTrait;
+
+    /// Tic-tac-toe board: 0 for empty, 1 and 2 for the players' marks
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct Board([u8; 9]);
+
+    impl ComponentTrait for Board {
+        fn component_type() -> Symbol {
+            symbol_short!("board")
+        }
+        fn serialize(&self, env: &Env) -> Bytes {
+            Bytes::from_array(env, &self.0)
+        }
+        fn deserialize(_env: &Env, data: &Bytes) -> Option<Self> {
+            let mut cells = [0u8; 9];
+            (data.len() == 9).then(|| {
+                data.copy_into_slice(&mut cells);
+                Self(cells)
+            })
+        }
+    }
+
+    const CELL_TAKEN: u32 = 1;
+
+    struct Mark {
+        board: EntityId,
+        cell: usize,
+        mark: u8,
+    }
+
+    impl TurnAction for Mark {
+        fn validate(&self, world: &World, _actor: EntityId) -> Result<(), TurnError> {
+            let board = world.get_typed_component::<Board>(self.board).unwrap();
+            match board.0.get(self.cell) {
+                Some(0) => Ok(()),
+                _ => Err(TurnError::InvalidAction(CELL_TAKEN)),
+            }
+        }
+
+        fn apply(&self, world: &mut World, _actor: EntityId) {
+            let mut board = world.get_typed_component::<Board>(self.board).unwrap();
+            board.0[self.cell] = self.mark;
+            world.add_typed_component(self.board, &board);
+        }
+    }
+
+    #[test]
+    fn test_tic_tac_toe_turns() {
+        let mut world = World::new();
+        let (x, o) = (world.spawn_empty().id(), world.spawn_empty().id());
+        let spectator = world.spawn_empty().id();
+        let board = world.spawn_empty().id();
+        world.add_typed_component(board, &Board([0; 9]));
+        world.set_typed_resource(&TurnManager::new(alloc::vec![x, o]));
+
+        let mark = |cell, mark| Mark { board, cell, mark };
+        assert_eq!(world.take_turn(x, &mark(4, 1)), Ok(()));
+        assert_eq!(world.take_turn(x, &mark(0, 1)), Err(TurnError::NotYourTurn));
+        assert_eq!(
+            world.take_turn(spectator, &mark(0, 1)),
+            Err(TurnError::NotParticipant)
+        );
+        assert_eq!(
+            world.take_turn(o, &mark(4, 2)),
+            Err(TurnError::InvalidAction(CELL_TAKEN))
+        );
+        assert_eq!(world.take_turn(o, &mark(0, 2)), Ok(()));
+
+        let turns = world.get_typed_resource::<TurnManager>().unwrap();
+        assert_eq!(turns.current_actor(), Some(x));
+        assert_eq!(turns.round(), 2);
+        assert_eq!(turns.turn(), 3);
+        let cells = world.get_typed_component::<Board>(board).unwrap().0;
+        assert_eq!((cells[4], cells[0]), (1, 2));
+    }
+
+    const CHOOSE: u32 = 0;
+    const RESOLVE: u32 = 1;
+
+    struct Choose;
+
+    impl TurnAction for Choose {
+        fn phase(&self) -> Option<u32> {
+            Some(CHOOSE)
+        }
+        fn apply(&self, _world: &mut World, _actor: EntityId) {}
+    }
+
+    struct Resolve;
+
+    impl TurnAction for Resolve {
+        fn phase(&self) -> Option<u32> {
+            Some(RESOLVE)
+        }
+        fn apply(&self, _world: &mut World, _actor: EntityId) {}
+    }
+
+    /// Knocks the actor out of the game
+    struct Forfeit;
+
+    impl TurnAction for Forfeit {
+        fn apply(&self, world: &mut World, actor: EntityId) {
+            let mut turns = world.get_typed_resource::<TurnManager>().unwrap();
+            turns.remove_participant(actor);
+            world.set_typed_resource(&turns);
+        }
+    }
+
+    #[test]
+    fn test_battle_phases() {
+        let mut world = World::new();
+        let (player, rival, wild) = (
+            world.spawn_empty().id(),
+            world.spawn_empty().id(),
+            world.spawn_empty().id(),
+        );
+        let turns = TurnManager::new(alloc::vec![player, rival, wild]).with_phases(2);
+        world.set_typed_resource(&turns);
+
+        assert_eq!(
+            world.take_turn(player, &Resolve),
+            Err(TurnError::WrongPhase)
+        );
+        assert_eq!(world.take_turn(player, &Choose), Ok(()));
+        let turns = world.get_typed_resource::<TurnManager>().unwrap();
+        assert_eq!(turns.current_actor(), Some(player));
+        assert_eq!(turns.phase(), RESOLVE);
+        assert_eq!(world.take_turn(player, &Resolve), Ok(()));
+
+        // The rival gives up; the wild one plays next
+        assert_eq!(world.take_turn(rival, &Forfeit), Ok(()));
+        let turns = world.get_typed_resource::<TurnManager>().unwrap();
+        assert_eq!(turns.participants(), [player, wild]);
+        assert_eq!(turns.current_actor(), Some(wild));
+        assert_eq!(turns.phase(), CHOOSE);
+        assert_eq!(turns.round(), 1);
+    }
+
+    #[test]
+    fn test_manual_advance_and_roundtrip() {
+        let env = Env::default();
+        let (a, b) = (EntityId::new(1, 0), EntityId::new(2, 0));
+        let mut turns = TurnManager::new(alloc::vec![a])
+            .with_phases(2)
+            .with_auto_advance(false);
+        assert!(turns.add_participant(b));
+        assert!(!turns.add_participant(b));
+        turns.end_phase();
+        turns.end_phase();
+        assert_eq!(turns.current_actor(), Some(b));
+        turns.end_turn();
+        assert_eq!((turns.current_actor(), turns.round()), (Some(a), 2));
+
+        let data = turns.serialize(&env);
+        assert_eq!(TurnManager::deserialize(&env, &data), Some(turns));
+        assert_eq!(
+            TurnManager::new(StdVec::new()).check_turn(a),
+            Err(TurnError::NoParticipants)
+        );
+
+        let mut world = World::with_env(&env);
+        assert_eq!(world.take_turn(a, &Forfeit), Err(TurnError::NoTurnManager));
+    }
+}
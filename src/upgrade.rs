@@ -0,0 +1,408 @@
+//! Replacing a game contract's code while keeping its worlds.
+//!
+//! An admin set once with [`Upgrader::initialize`] calls [`Upgrader::upgrade`]
+//! to swap the contract's WASM. The worlds the contract saved stay in storage,
+//! each recorded with the format version of the code that saved it. New code
+//! loads them through [`Upgrader::load`] with its own, higher format version;
+//! the first invocation after the upgrade runs the world's registered
+//! [migrations](crate::migration) and saves the upgraded world. If a migration
+//! fails, the world is left as the old code saved it and every load is
+//! refused until code able to migrate it is deployed.
+
+use crate::migration::MigrationError;
+use crate::persistence::WorldStore;
+use crate::world::World;
+use soroban_sdk::{symbol_short, Address, BytesN, Env, Symbol, Val};
+
+/// Format version of worlds saved without one recorded
+pub const FIRST_FORMAT_VERSION: u32 = 1;
+
+/// Errors returned when upgrading a contract or loading its worlds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UpgradeError {
+    /// An admin has already been set
+    AlreadyInitialized,
+    /// No admin has been set
+    NotInitialized,
+    /// The world was saved by code with a newer format than this code's
+    NewerFormat { saved: u32 },
+    /// A migration could not upgrade the world, which was left as it was
+    MigrationFailed(MigrationError),
+    /// The format was raised but neither the store nor the world declares a
+    /// component schema version, so no component could be found stale and
+    /// migrated
+    UndeclaredVersions,
+}
+
+/// Loads and saves worlds at the format version of the running code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Upgrader {
+    format_version: u32,
+}
+
+impl Upgrader {
+    /// Create an upgrader for code saving worlds at `format_version`
+    ///
+    /// Raise the version along with the schema version of any component type
+    /// saved in the contract's worlds.
+    pub fn new(format_version: u32) -> Self {
+        Self { format_version }
+    }
+
+    /// Get the format version worlds are saved at
+    pub fn format_version(&self) -> u32 {
+        self.format_version
+    }
+
+    fn admin_key() -> (Symbol, Symbol) {
+        (symbol_short!("upgrade"), symbol_short!("admin"))
+    }
+
+    fn format_key(store: &WorldStore) -> Val {
+        store.scoped_key(symbol_short!("wformat"))
+    }
+
+    /// Set the address allowed to upgrade the contract
+    pub fn initialize(env: &Env, admin: &Address) -> Result<(), UpgradeError> {
+        let storage = env.storage().instance();
+        if storage.has(&Self::admin_key()) {
+            return Err(UpgradeError::AlreadyInitialized);
+        }
+        storage.set(&Self::admin_key(), admin);
+        Ok(())
+    }
+
+    /// Get the address allowed to upgrade the contract
+    pub fn admin(env: &Env) -> Option<Address> {
+        env.storage().instance().get(&Self::admin_key())
+    }
+
+    /// Hand the admin role to `new_admin`, with the current admin's
+    /// authorization
+    pub fn transfer_admin(env: &Env, new_admin: &Address) -> Result<(), UpgradeError> {
+        let admin = Self::admin(env).ok_or(UpgradeError::NotInitialized)?;
+        admin.require_auth();
+        env.storage().instance().set(&Self::admin_key(), new_admin);
+        Ok(())
+    }
+
+    /// Replace the contract's code with the uploaded WASM `wasm_hash`, with
+    /// the admin's authorization
+    ///
+    /// The new code runs from the next invocation on.
+    pub fn upgrade(env: &Env, wasm_hash: BytesN<32>) -> Result<(), UpgradeError> {
+        let admin = Self::admin(env).ok_or(UpgradeError::NotInitialized)?;
+        admin.require_auth();
+        env.deployer().update_current_contract_wasm(wasm_hash);
+        Ok(())
+    }
+
+    /// Get the format version the world of `store` was saved at
+    pub fn saved_format(env: &Env, store: &WorldStore) -> Option<u32> {
+        env.storage().instance().get(&Self::format_key(store))
+    }
+
+    /// Forget the format version of a removed world
+    pub(crate) fn clear_format(env: &Env, store: &WorldStore) {
+        env.storage().instance().remove(&Self::format_key(store));
+    }
+
+    /// Load the world saved under `store`'s name into `world`, migrating it
+    /// first if it was saved at an older format
+    ///
+    /// The migrated world is saved right away, so the migrations run once.
+    /// Returns `Ok(false)` if no world is saved under the name, and an error
+    /// if the world was saved at a newer format or could not be migrated. On
+    /// error, `world` and `store` are left as they were and the saved world is
+    /// not changed, though loading it may have extended the TTLs of its
+    /// entries.
+    ///
+    /// Components are only found stale for the types whose versions `world`
+    /// declares, through [`World::register_migration`] or
+    /// [`World::add_typed_component`], or `store` declares with
+    /// [`WorldStore::with_component`], so a raised format is refused when none
+    /// is declared and no migration was applied.
+    pub fn load(&self, store: &mut WorldStore, world: &mut World) -> Result<bool, UpgradeError> {
+        let saved = Self::saved_format(world.env(), store).unwrap_or(FIRST_FORMAT_VERSION);
+        if saved > self.format_version {
+            return Err(UpgradeError::NewerFormat { saved });
+        }
+        // The world is only handed over once it is current
+        let (mut loaded, mut scratch) = (world.clone(), store.clone());
+        if !scratch.load(&mut loaded) {
+            return Ok(false);
+        }
+        if saved < self.format_version {
+            let migrated = loaded
+                .migrate_components()
+                .map_err(UpgradeError::MigrationFailed)?;
+            if migrated == 0 && !scratch.declares_versions(&loaded) {
+                return Err(UpgradeError::UndeclaredVersions);
+            }
+            self.save(&mut scratch, &loaded);
+        }
+        *world = loaded;
+        *store = scratch;
+        Ok(true)
+    }
+
+    /// Save `world` with `store`, recording the format version it was saved at
+    pub fn save(&self, store: &mut WorldStore, world: &World) {
+        store.save(world);
+        world
+            .env()
+            .storage()
+            .instance()
+            .set(&Self::format_key(store), &self.format_version);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::ComponentTrait;
+    use soroban_sdk::testutils::{Address as _, MockAuth, MockAuthInvoke};
+    use soroban_sdk::{contract, contractimpl, Bytes, IntoVal, Vec};
+
+    /// A module holding nothing but the environment interface version, enough
+    /// for the host to accept it as contract code
+    const STUB_WASM: [u8; 40] = [
+        0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // magic and version
+        0x00, 0x1e, 0x11, // custom section of 30 bytes, 17-byte name
+        b'c', b'o', b'n', b't', b'r', b'a', b'c', b't', b'e', b'n', b'v', b'm', b'e', b't', b'a',
+        b'v', b'0', // contractenvmetav0
+        0, 0, 0, 0, 0, 0, 0, 20, 0, 0, 0, 0, // interface version, protocol 20
+    ];
+
+    /// Gold as the first release stores it
+    struct GoldV1(u32);
+
+    impl ComponentTrait for GoldV1 {
+        fn component_type() -> Symbol {
+            symbol_short!("gold")
+        }
+        fn serialize(&self, env: &Env) -> Bytes {
+            Bytes::from_array(env, &self.0.to_be_bytes())
+        }
+        fn deserialize(_env: &Env, data: &Bytes) -> Option<Self> {
+            let mut buf = [0u8; 4];
+            (data.len() == 4).then(|| {
+                data.copy_into_slice(&mut buf);
+                Self(u32::from_be_bytes(buf))
+            })
+        }
+    }
+
+    /// Gold split into a purse and a bank by the second release
+    struct GoldV2 {
+        purse: u32,
+        bank: u32,
+    }
+
+    impl ComponentTrait for GoldV2 {
+        fn component_type() -> Symbol {
+            symbol_short!("gold")
+        }
+        fn serialize(&self, env: &Env) -> Bytes {
+            let mut bytes = Bytes::from_array(env, &self.purse.to_be_bytes());
+            bytes.extend_from_array(&self.bank.to_be_bytes());
+            bytes
+        }
+        fn deserialize(_env: &Env, data: &Bytes) -> Option<Self> {
+            let mut buf = [0u8; 8];
+            (data.len() == 8).then(|| {
+                data.copy_into_slice(&mut buf);
+                Self {
+                    purse: u32::from_be_bytes(buf[..4].try_into().unwrap()),
+                    bank: u32::from_be_bytes(buf[4..].try_into().unwrap()),
+                }
+            })
+        }
+        fn schema_version() -> u32 {
+            2
+        }
+    }
+
+    /// Bank everything over 100 gold; purses over 10 000 are rejected
+    fn open_bank(env: &Env, data: &Bytes) -> Option<Bytes> {
+        let GoldV1(gold) = GoldV1::deserialize(env, data)?;
+        (gold <= 10_000).then(|| {
+            GoldV2 {
+                purse: gold.min(100),
+                bank: gold.saturating_sub(100),
+            }
+            .serialize(env)
+        })
+    }
+
+    fn store(env: &Env) -> WorldStore {
+        WorldStore::new(env, symbol_short!("realm"))
+    }
+
+    #[contract]
+    struct GameV1;
+
+    #[contractimpl]
+    impl GameV1 {
+        pub fn init(env: Env, admin: Address, purses: Vec<u32>) {
+            Upgrader::initialize(&env, &admin).unwrap();
+            let mut world = World::with_env(&env);
+            for gold in purses.iter() {
+                let entity_id = world.spawn_empty().id();
+                world.add_typed_component(entity_id, &GoldV1(gold));
+            }
+            Upgrader::new(1).save(&mut store(&env), &world);
+        }
+
+        pub fn upgrade(env: Env, wasm_hash: BytesN<32>) {
+            Upgrader::upgrade(&env, wasm_hash).unwrap();
+        }
+    }
+
+    #[contract]
+    struct GameV2;
+
+    #[contractimpl]
+    impl GameV2 {
+        /// Get the gold banked across the realm
+        pub fn banked(env: Env) -> u32 {
+            let mut world = World::with_env(&env);
+            world.register_migration::<GoldV2>(1, open_bank);
+            let mut store = store(&env).with_component::<GoldV2>();
+            let upgrader = Upgrader::new(2);
+            if let Err(error) = upgrader.load(&mut store, &mut world) {
+                panic!("cannot load the realm: {error:?}");
+            }
+            world
+                .query_entities(&[GoldV2::component_type()])
+                .iter()
+                .map(|entity_id| world.get_typed_component::<GoldV2>(entity_id).unwrap().bank)
+                .sum()
+        }
+    }
+
+    /// Deploy the first release with `purses` and upgrade it to the second
+    fn deploy_and_upgrade(env: &Env, purses: &[u32]) -> (Address, Address) {
+        let admin = Address::generate(env);
+        let contract_id = env.register(GameV1, ());
+        let game = GameV1Client::new(env, &contract_id);
+        game.init(&admin, &Vec::from_slice(env, purses));
+
+        let wasm_hash = env
+            .deployer()
+            .upload_contract_wasm(Bytes::from_array(env, &STUB_WASM));
+        env.mock_auths(&[MockAuth {
+            address: &admin,
+            invoke: &MockAuthInvoke {
+                contract: &contract_id,
+                fn_name: "upgrade",
+                args: (wasm_hash.clone(),).into_val(env),
+                sub_invokes: &[],
+            },
+        }]);
+        game.upgrade(&wasm_hash);
+        // Tests run native code, so the second release stands in for the
+        // uploaded WASM at the same address
+        env.register_at(&contract_id, GameV2, ());
+        (contract_id, admin)
+    }
+
+    #[test]
+    fn test_upgrade_migrates_the_world_once() {
+        let env = Env::default();
+        let (contract_id, _) = deploy_and_upgrade(&env, &[50, 250, 1_100]);
+        env.as_contract(&contract_id, || {
+            assert_eq!(Upgrader::saved_format(&env, &store(&env)), Some(1));
+        });
+
+        let game = GameV2Client::new(&env, &contract_id);
+        assert_eq!(game.banked(), 1_150);
+        env.as_contract(&contract_id, || {
+            assert_eq!(Upgrader::saved_format(&env, &store(&env)), Some(2));
+            // The saved world is current, so it loads without migrations
+            let mut world = World::with_env(&env);
+            assert!(store(&env).with_component::<GoldV2>().load(&mut world));
+            assert_eq!(world.stale_component_count(), 0);
+        });
+        assert_eq!(game.banked(), 1_150);
+    }
+
+    #[test]
+    fn test_failed_migration_refuses_to_proceed() {
+        let env = Env::default();
+        let (contract_id, _) = deploy_and_upgrade(&env, &[50, 20_000]);
+        let game = GameV2Client::new(&env, &contract_id);
+        assert!(game.try_banked().is_err());
+        assert!(game.try_banked().is_err());
+        env.as_contract(&contract_id, || {
+            assert_eq!(Upgrader::saved_format(&env, &store(&env)), Some(1));
+            let mut world = World::with_env(&env);
+            world.register_migration::<GoldV2>(1, open_bank);
+            assert!(matches!(
+                Upgrader::new(2).load(&mut store(&env).with_component::<GoldV2>(), &mut world),
+                Err(UpgradeError::MigrationFailed(MigrationError::Failed { .. }))
+            ));
+            assert_eq!(world.entity_count(), 0);
+            // Older code refuses worlds saved at a newer format
+            Upgrader::new(3).save(&mut store(&env), &world);
+            assert_eq!(
+                Upgrader::new(2).load(&mut store(&env), &mut world),
+                Err(UpgradeError::NewerFormat { saved: 3 })
+            );
+        });
+    }
+
+    #[test]
+    fn test_store_without_versions_refuses_to_upgrade() {
+        let env = Env::default();
+        let (contract_id, _) = deploy_and_upgrade(&env, &[50, 250, 1_100]);
+        env.as_contract(&contract_id, || {
+            let mut world = World::with_env(&env);
+            assert_eq!(
+                Upgrader::new(2).load(&mut store(&env), &mut world),
+                Err(UpgradeError::UndeclaredVersions)
+            );
+            // The caller's world is not handed the unmigrated data
+            assert_eq!(world.entity_count(), 0);
+            assert_eq!(Upgrader::saved_format(&env, &store(&env)), Some(1));
+        });
+        // The world is left for code that declares its versions
+        assert_eq!(GameV2Client::new(&env, &contract_id).banked(), 1_150);
+    }
+
+    #[test]
+    fn test_only_the_admin_upgrades() {
+        let env = Env::default();
+        let admin = Address::generate(&env);
+        let intruder = Address::generate(&env);
+        let contract_id = env.register(GameV1, ());
+        let game = GameV1Client::new(&env, &contract_id);
+        game.init(&admin, &Vec::new(&env));
+        assert!(game.try_init(&intruder, &Vec::new(&env)).is_err());
+
+        let wasm_hash = env
+            .deployer()
+            .upload_contract_wasm(Bytes::from_array(&env, &STUB_WASM));
+        env.mock_auths(&[MockAuth {
+            address: &intruder,
+            invoke: &MockAuthInvoke {
+                contract: &contract_id,
+                fn_name: "upgrade",
+                args: (wasm_hash.clone(),).into_val(&env),
+                sub_invokes: &[],
+            },
+        }]);
+        assert!(game.try_upgrade(&wasm_hash).is_err());
+
+        env.mock_all_auths();
+        env.as_contract(&contract_id, || {
+            assert_eq!(Upgrader::admin(&env), Some(admin.clone()));
+            assert_eq!(Upgrader::transfer_admin(&env, &intruder), Ok(()));
+            assert_eq!(Upgrader::admin(&env), Some(intruder.clone()));
+            assert_eq!(
+                Upgrader::initialize(&env, &admin),
+                Err(UpgradeError::AlreadyInitialized)
+            );
+        });
+    }
+}
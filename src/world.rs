@@ -1,9 +1,21 @@
-use crate::component::{Component, ComponentRegistry};
-use crate::entity::{Entity, EntityId, EntityIterator, EntityIteratorMut, EntityManager};
-use crate::event::Event;
-use crate::resource::Resource;
+use crate::component::{Component, ComponentRegistry, ComponentTrait, StorageTier};
+use crate::components::Position;
+use crate::entity::{
+    ComponentEntityMapper, Entity, EntityId, EntityIterator, EntityIteratorMut, EntityManager,
+};
+use crate::entity_disabling::DefaultQueryFilters;
+use crate::event::{Event, EventTrait};
+use crate::migration::MigrationRegistry;
+use crate::name::{Name, NameIndex};
+use crate::player::{Player, PlayerIndex};
+use crate::prefab::PrefabRegistry;
+use crate::relationship::RelationshipKind;
+use crate::resource::{Resource, ResourceTrait};
+use crate::spatial::SpatialHash;
 use crate::storage::Storage;
-use soroban_sdk::{contracttype, Symbol, Vec};
+use soroban_sdk::{Env, Map, Symbol, Vec};
+
+mod spawn_batch;
 
 /// The main ECS world that contains all entities, components, and systems
 #[derive(Debug, Clone)]
@@ -18,66 +30,208 @@ pub struct World {
     pub resources: Vec<Resource>,
     /// Event system
     pub events: Vec<Event>,
+    /// Relationship types whose target collections the world maintains
+    pub(crate) relationships: alloc::vec::Vec<RelationshipKind>,
+    /// Disabling components hidden from queries that do not mention them
+    pub(crate) default_query_filters: DefaultQueryFilters,
+    /// Index from entity names to entities
+    pub(crate) names: NameIndex,
+    /// Component types whose entity references are rewritten when copied
+    pub(crate) entity_mappers: alloc::vec::Vec<ComponentEntityMapper>,
+    /// Prefabs that can be spawned by name
+    pub(crate) prefabs: PrefabRegistry,
+    /// Grid index over entity positions, if enabled
+    pub(crate) spatial: Option<SpatialHash>,
+    /// Index from player addresses to the entities they play
+    pub(crate) players: PlayerIndex,
+    /// Migrations upgrading component data saved at older schema versions
+    pub(crate) migrations: MigrationRegistry,
+    /// Schema version of each component stored at an older version than its type's
+    pub(crate) stale: Map<(EntityId, Symbol), u32>,
+    /// Storage tier declared by each component type not kept in persistent storage
+    pub(crate) component_tiers: Map<Symbol, StorageTier>,
+    /// Schema version declared by each component type newer than the first
+    pub(crate) component_versions: Map<Symbol, u32>,
 }
 
 impl World {
     /// Create a new empty world
     pub fn new() -> Self {
-        let env = soroban_sdk::Env::default();
+        Self::with_env(&soroban_sdk::Env::default())
+    }
+
+    /// Create a new empty world whose entities, components and resources live in `env`
+    ///
+    /// Component data added to the world must be created in the same `env`.
+    pub fn with_env(env: &Env) -> Self {
         Self {
-            entities: EntityManager::new(),
+            entities: EntityManager::with_env(env),
             components: ComponentRegistry::new(),
-            storage: Storage::new(),
-            resources: Vec::new(&env),
-            events: Vec::new(&env),
+            storage: Storage::with_env(env),
+            resources: Vec::new(env),
+            events: Vec::new(env),
+            relationships: alloc::vec::Vec::new(),
+            default_query_filters: DefaultQueryFilters::new(env),
+            names: NameIndex::new(env),
+            entity_mappers: alloc::vec::Vec::new(),
+            prefabs: PrefabRegistry::new(env),
+            spatial: None,
+            players: PlayerIndex::new(env),
+            migrations: MigrationRegistry::new(),
+            stale: Map::new(env),
+            component_tiers: Map::new(env),
+            component_versions: Map::new(env),
         }
     }
 
+    /// Get the environment the world's data lives in
+    pub fn env(&self) -> &Env {
+        self.resources.env()
+    }
+
     /// Spawn a new empty entity
     pub fn spawn_empty(&mut self) -> Entity {
         let entity_id = self.entities.spawn();
-        Entity::new(entity_id)
+        self.entities.get_entity(entity_id).unwrap()
     }
 
     /// Spawn a new entity with components
     pub fn spawn(&mut self, components: Vec<Component>) -> Entity {
         let entity_id = self.entities.spawn();
-        let mut entity = Entity::new(entity_id);
 
         // Add components to the entity and storage
         for component in components {
             self.add_component_to_entity(entity_id, component);
         }
 
-        entity
+        self.entities.get_entity(entity_id).unwrap()
     }
 
     /// Add a component to an entity
+    ///
+    /// Components of a registered [`Relationship`](crate::relationship::Relationship)
+    /// type also update the target's collection, and [`Name`] components update
+    /// the name index. A relationship pointing at the entity itself or at a
+    /// missing entity, or a name rejected by unique names, is not stored.
     pub fn add_component_to_entity(&mut self, entity_id: EntityId, component: Component) {
+        if self.index_component(entity_id, &component) {
+            self.add_component_raw(entity_id, component);
+        }
+    }
+
+    /// Update world-maintained indexes before `component` is stored on an entity
+    ///
+    /// Returns `false` if the component must not be stored.
+    fn index_component(&mut self, entity_id: EntityId, component: &Component) -> bool {
+        let component_type = component.component_type();
+        if let Some(kind) = self.relationship_kind(component_type) {
+            return self.link_relationship(&kind, entity_id, component.data());
+        }
+        if *component_type == Name::component_type() {
+            return self.index_name(entity_id, component);
+        }
+        if *component_type == Position::component_type() {
+            return self.index_position(entity_id, component);
+        }
+        if *component_type == Player::component_type() {
+            return self.index_player(entity_id, component);
+        }
+        true
+    }
+
+    /// Check whether storing `component_type` updates a world-maintained index
+    pub(crate) fn is_indexed(&self, component_type: &Symbol) -> bool {
+        self.relationship_kind(component_type).is_some()
+            || *component_type == Name::component_type()
+            || (self.spatial.is_some() && *component_type == Position::component_type())
+            || *component_type == Player::component_type()
+    }
+
+    /// Update world-maintained indexes before a component is removed from an entity
+    fn unindex_component(&mut self, entity_id: EntityId, component_type: &Symbol) {
+        self.unlink_component(entity_id, component_type);
+        if *component_type == Name::component_type() {
+            self.unindex_name(entity_id);
+        }
+        if *component_type == Position::component_type() {
+            self.unindex_position(entity_id);
+        }
+        if *component_type == Player::component_type() {
+            self.unindex_player(entity_id);
+        }
+    }
+
+    /// Add a component to an entity without running relationship bookkeeping
+    pub(crate) fn add_component_raw(&mut self, entity_id: EntityId, component: Component) {
+        self.unmark_stale(entity_id, component.component_type());
         // Register the component type if not already registered
         self.components
             .register_component(component.component_type().clone());
         // Add component type to entity
         if let Some(mut entity) = self.entities.get_entity_mut(entity_id) {
-            entity.add_component_type(component.component_type().clone());
-            // Since we can't modify the entity in place, we need to update it
-            // This is a limitation of the Soroban SDK
+            if !entity.has_component(component.component_type()) {
+                entity.add_component_type(component.component_type().clone());
+                self.entities.set_entity(entity);
+            }
         }
         // Store the component data
         self.storage.add_component(entity_id, component);
     }
 
+    /// Serialize `component` and add it to an entity
+    pub fn add_typed_component<T: ComponentTrait>(&mut self, entity_id: EntityId, component: &T) {
+        self.declare_component::<T>();
+        let component = Component::with_storage(
+            T::component_type(),
+            component.serialize(self.env()),
+            T::default_storage(),
+        );
+        self.add_component_to_entity(entity_id, component);
+    }
+
+    /// Record the storage tier and schema version `T` declares, so worlds are
+    /// saved and loaded accordingly
+    pub(crate) fn declare_component<T: ComponentTrait>(&mut self) {
+        let component_type = T::component_type();
+        if T::storage_tier() != StorageTier::Persistent
+            && self.component_tiers.get(component_type.clone()) != Some(T::storage_tier())
+        {
+            self.component_tiers
+                .set(component_type.clone(), T::storage_tier());
+        }
+        if T::schema_version() > 1
+            && self.component_versions.get(component_type.clone()) != Some(T::schema_version())
+        {
+            self.component_versions
+                .set(component_type, T::schema_version());
+        }
+    }
+
     /// Remove a component from an entity
     pub fn remove_component_from_entity(
         &mut self,
         entity_id: EntityId,
         component_type: &Symbol,
     ) -> bool {
+        if !self.has_component(entity_id, component_type) {
+            return false;
+        }
+        self.unindex_component(entity_id, component_type);
+        self.remove_component_raw(entity_id, component_type)
+    }
+
+    /// Remove a component from an entity without running relationship bookkeeping
+    pub(crate) fn remove_component_raw(
+        &mut self,
+        entity_id: EntityId,
+        component_type: &Symbol,
+    ) -> bool {
+        self.unmark_stale(entity_id, component_type);
         // Remove component type from entity
         if let Some(mut entity) = self.entities.get_entity_mut(entity_id) {
-            entity.remove_component_type(component_type);
-            // Since we can't modify the entity in place, we need to update it
-            // This is a limitation of the Soroban SDK
+            if entity.remove_component_type(component_type) {
+                self.entities.set_entity(entity);
+            }
         }
         // Remove component data from storage
         self.storage
@@ -90,6 +244,16 @@ impl World {
             .get_component(entity_id, component_type.clone())
     }
 
+    /// Get a component from an entity and deserialize it as `T`
+    ///
+    /// Data stored at an older schema version is upgraded first by the
+    /// registered migrations; see [`crate::migration`].
+    pub fn get_typed_component<T: ComponentTrait>(&self, entity_id: EntityId) -> Option<T> {
+        let component = self.get_component(entity_id, &T::component_type())?;
+        let data = self.current_data::<T>(entity_id, component)?;
+        T::deserialize(self.env(), &data)
+    }
+
     /// Get a mutable reference to a component from an entity
     pub fn get_component_mut(
         &mut self,
@@ -111,13 +275,31 @@ impl World {
     }
 
     /// Despawn an entity and remove all its components
+    ///
+    /// Registered relationships are updated according to their
+    /// [`DespawnPolicy`](crate::relationship::DespawnPolicy); returns `false`
+    /// without changing anything if one of them forbids the despawn.
     pub fn despawn(&mut self, entity_id: EntityId) -> bool {
+        if !self.exists(entity_id) || self.despawn_forbidden(entity_id) {
+            return false;
+        }
+        self.despawn_unchecked(entity_id)
+    }
+
+    /// Despawn an entity without checking for forbidding relationships
+    pub(crate) fn despawn_unchecked(&mut self, entity_id: EntityId) -> bool {
+        if !self.exists(entity_id) {
+            return false;
+        }
+        self.despawn_relationships(entity_id);
+        self.unindex_name(entity_id);
+        self.unindex_position(entity_id);
+        self.unindex_player(entity_id);
         if let Some(entity) = self.entities.get_entity(entity_id) {
             // Remove all components from storage
-            let component_types = entity.component_types().clone();
-            for i in 0..component_types.len() {
-                let ctype = component_types.get(i).unwrap();
-                self.storage.remove_component(entity_id, ctype.clone());
+            for ctype in entity.component_types().iter() {
+                self.unmark_stale(entity_id, &ctype);
+                self.storage.remove_component(entity_id, ctype);
             }
         }
         self.entities.despawn(entity_id)
@@ -173,7 +355,7 @@ impl World {
     /// Remove a resource from the world
     pub fn remove_resource(&mut self, resource_type: &Symbol) -> Option<Resource> {
         let mut found = None;
-        let mut new_resources = Vec::new(&soroban_sdk::Env::default());
+        let mut new_resources = Vec::new(self.env());
         for i in 0..self.resources.len() {
             let res = self.resources.get(i).unwrap();
             if res.resource_type() == resource_type {
@@ -188,6 +370,24 @@ impl World {
         found
     }
 
+    /// Insert `resource`, replacing any resource of the same type
+    pub fn set_typed_resource<T: ResourceTrait>(&mut self, resource: &T) {
+        let resource = Resource::new(T::resource_type(), resource.serialize(self.env()));
+        for i in 0..self.resources.len() {
+            if self.resources.get(i).unwrap().resource_type() == resource.resource_type() {
+                self.resources.set(i, resource);
+                return;
+            }
+        }
+        self.resources.push_back(resource);
+    }
+
+    /// Get a resource by type and deserialize it
+    pub fn get_typed_resource<T: ResourceTrait>(&self) -> Option<T> {
+        self.get_resource(&T::resource_type())
+            .and_then(|resource| T::deserialize(self.env(), resource.data()))
+    }
+
     /// Send an event
     pub fn send_event(&mut self, event: Event) {
         self.events.push_back(event);
@@ -195,8 +395,7 @@ impl World {
 
     /// Get all events of a specific type
     pub fn get_events(&self, event_type: &Symbol) -> Vec<Event> {
-        let env = soroban_sdk::Env::default();
-        let mut filtered = Vec::new(&env);
+        let mut filtered = Vec::new(self.env());
         for i in 0..self.events.len() {
             let event = self.events.get(i).unwrap();
             if event.event_type() == event_type {
@@ -206,36 +405,56 @@ impl World {
         filtered
     }
 
+    /// Serialize `event` and send it
+    pub fn send_typed_event<E: EventTrait>(&mut self, event: &E) {
+        let event = Event::new(E::event_type(), event.serialize(self.env()));
+        self.send_event(event);
+    }
+
+    /// Get all events of type `E`, deserialized, in the order they were sent
+    pub fn get_typed_events<E: EventTrait>(&self) -> alloc::vec::Vec<E> {
+        let event_type = E::event_type();
+        self.events
+            .iter()
+            .filter(|event| event.event_type() == &event_type)
+            .filter_map(|event| E::deserialize(self.env(), event.data()))
+            .collect()
+    }
+
     /// Clear all events
     pub fn clear_events(&mut self) {
-        let env = soroban_sdk::Env::default();
-        self.events = Vec::new(&env);
+        self.events = Vec::new(self.env());
     }
 
     /// Iterate over all entities
-    pub fn iter_entities(&self) -> EntityIterator {
+    pub fn iter_entities(&self) -> EntityIterator<'_> {
         self.entities.iter_entities()
     }
 
     /// Iterate over all entities mutably
-    pub fn iter_entities_mut(&mut self) -> EntityIteratorMut {
+    pub fn iter_entities_mut(&mut self) -> EntityIteratorMut<'_> {
         self.entities.iter_entities_mut()
     }
 
     /// Query entities with specific components
+    ///
+    /// Disabled entities are skipped unless every disabling component they
+    /// have is listed in `component_types`.
     pub fn query_entities(&self, component_types: &[Symbol]) -> Vec<EntityId> {
-        let env = soroban_sdk::Env::default();
-        let mut results = Vec::new(&env);
+        let mut results = Vec::new(self.env());
         for entity in self.iter_entities() {
             let mut has_all_components = true;
-            for i in 0..component_types.len() {
-                let ctype = &component_types[i];
+            for ctype in component_types {
                 if !entity.has_component(ctype) {
                     has_all_components = false;
                     break;
                 }
             }
-            if has_all_components {
+            if has_all_components
+                && self
+                    .default_query_filters
+                    .allows(&entity, |ctype| component_types.contains(ctype))
+            {
                 results.push_back(entity.id());
             }
         }
@@ -244,14 +463,21 @@ impl World {
 
     /// Clear all entities and components
     pub fn clear_entities(&mut self) {
-        self.entities = EntityManager::new();
-        self.storage = Storage::new();
+        self.entities = EntityManager::with_env(self.env());
+        self.storage = Storage::with_env(self.env());
+        let unique = self.names.is_unique();
+        self.names = NameIndex::new(self.env());
+        self.set_unique_names(unique);
+        self.players = PlayerIndex::new(self.env());
+        self.stale = Map::new(self.env());
+        if let Some(spatial) = self.spatial.as_mut() {
+            *spatial = SpatialHash::new(spatial.cell_size());
+        }
     }
 
     /// Clear all resources
     pub fn clear_resources(&mut self) {
-        let env = soroban_sdk::Env::default();
-        self.resources = Vec::new(&env);
+        self.resources = Vec::new(self.env());
     }
 
     /// Clear everything in the world
@@ -288,25 +514,28 @@ mod tests {
         assert!(world.exists(entity.id()));
     }
 
-    // TODO: This test requires sharing Env between World and test objects
-    // which is not straightforward with current architecture
-    // #[test]
-    // fn test_component_management() {
-    //     let mut world = World::new();
-    //     let entity_id = world.spawn_empty().id();
-    //     let env = Env::default();
-    //
-    //     let component_type = symbol_short!("test");
-    //     let mut component_data = soroban_sdk::Bytes::new(&env);
-    //     component_data.append(&soroban_sdk::Bytes::from_array(&env, &[1, 2, 3, 4]));
-    //     let component = Component::new(component_type, component_data);
-    //
-    //     world.add_component_to_entity(entity_id, component);
-    //     assert!(world.has_component(entity_id, &symbol_short!("test")));
-    //
-    //     let retrieved_component = world.get_component(entity_id, &symbol_short!("test"));
-    //     assert!(retrieved_component.is_some());
-    // }
+    #[test]
+    fn test_component_management() {
+        let env = Env::default();
+        let mut world = World::with_env(&env);
+        let entity_id = world.spawn_empty().id();
+
+        let component_type = symbol_short!("test");
+        let mut component_data = soroban_sdk::Bytes::new(&env);
+        component_data.append(&soroban_sdk::Bytes::from_array(&env, &[1, 2, 3, 4]));
+        let component = Component::new(component_type, component_data);
+
+        world.add_component_to_entity(entity_id, component);
+        assert!(world.has_component(entity_id, &symbol_short!("test")));
+        assert_eq!(world.query_entities(&[symbol_short!("test")]).len(), 1);
+
+        let retrieved_component = world.get_component(entity_id, &symbol_short!("test"));
+        assert!(retrieved_component.is_some());
+
+        assert!(world.remove_component_from_entity(entity_id, &symbol_short!("test")));
+        assert!(!world.has_component(entity_id, &symbol_short!("test")));
+        assert_eq!(world.query_entities(&[symbol_short!("test")]).len(), 0);
+    }
 
     #[test]
     fn test_entity_despawn() {
@@ -319,42 +548,38 @@ mod tests {
         assert!(!world.exists(entity_id));
     }
 
-    // TODO: This test requires sharing Env between World and test objects
-    // which is not straightforward with current architecture
-    // #[test]
-    // fn test_resource_management() {
-    //     let mut world = World::new();
-    //     let env = Env::default();
-    //     let resource_type = symbol_short!("testres");
-    //     let mut resource_data = soroban_sdk::Bytes::new(&env);
-    //     resource_data.append(&soroban_sdk::Bytes::from_array(&env, &[1, 2, 3, 4]));
-    //     let resource = Resource::new(resource_type, resource_data);
-    //
-    //     world.add_resource(resource);
-    //     assert!(world.get_resource(&symbol_short!("testres")).is_some());
-    //
-    //     let removed_resource = world.remove_resource(&symbol_short!("testres"));
-    //     assert!(removed_resource.is_some());
-    //     assert!(world.get_resource(&symbol_short!("testres")).is_none());
-    // }
-
-    // TODO: This test requires sharing Env between World and test objects
-    // which is not straightforward with current architecture
-    // #[test]
-    // fn test_event_system() {
-    //     let mut world = World::new();
-    //     let env = Env::default();
-    //     let event_type = symbol_short!("testevent");
-    //     let mut event_data = soroban_sdk::Bytes::new(&env);
-    //     event_data.append(&soroban_sdk::Bytes::from_array(&env, &[1, 2, 3, 4]));
-    //     let event = Event::new(event_type, event_data);
-    //
-    //     world.send_event(event);
-    //     let events = world.get_events(&symbol_short!("testevent"));
-    //     assert_eq!(events.len(), 1);
-    //
-    //     world.clear_events();
-    //     let events = world.get_events(&symbol_short!("testevent"));
-    //     assert_eq!(events.len(), 0);
-    // }
+    #[test]
+    fn test_resource_management() {
+        let env = Env::default();
+        let mut world = World::with_env(&env);
+        let resource_type = symbol_short!("testres");
+        let mut resource_data = soroban_sdk::Bytes::new(&env);
+        resource_data.append(&soroban_sdk::Bytes::from_array(&env, &[1, 2, 3, 4]));
+        let resource = Resource::new(resource_type, resource_data);
+
+        world.add_resource(resource);
+        assert!(world.get_resource(&symbol_short!("testres")).is_some());
+
+        let removed_resource = world.remove_resource(&symbol_short!("testres"));
+        assert!(removed_resource.is_some());
+        assert!(world.get_resource(&symbol_short!("testres")).is_none());
+    }
+
+    #[test]
+    fn test_event_system() {
+        let env = Env::default();
+        let mut world = World::with_env(&env);
+        let event_type = symbol_short!("testevent");
+        let mut event_data = soroban_sdk::Bytes::new(&env);
+        event_data.append(&soroban_sdk::Bytes::from_array(&env, &[1, 2, 3, 4]));
+        let event = Event::new(event_type, event_data);
+
+        world.send_event(event);
+        let events = world.get_events(&symbol_short!("testevent"));
+        assert_eq!(events.len(), 1);
+
+        world.clear_events();
+        let events = world.get_events(&symbol_short!("testevent"));
+        assert_eq!(events.len(), 0);
+    }
 }
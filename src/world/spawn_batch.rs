@@ -1,107 +1,165 @@
-use crate::{
-    bundle::{Bundle, BundleSpawner, NoBundleEffect},
-    change_detection::MaybeLocation,
-    entity::{Entity, EntitySetIterator},
-    world::World,
-};
-use core::iter::FusedIterator;
+use crate::component::Component;
+use crate::entity::EntityId;
+use crate::world::World;
+use soroban_sdk::Vec;
 
-/// An iterator that spawns a series of entities and returns the [ID](Entity) of
-/// each spawned entity.
-///
-/// If this iterator is not fully exhausted, any remaining entities will be spawned when this type is dropped.
-pub struct SpawnBatchIter<'w, I>
-where
-    I: Iterator,
-    I::Item: Bundle,
-{
-    inner: I,
-    spawner: BundleSpawner<'w>,
-    caller: MaybeLocation,
-}
+impl World {
+    /// Spawn one entity per bundle of components and return their ids, in order
+    ///
+    /// Equivalent to calling [`World::spawn`] for every bundle, but the ids are
+    /// reserved up front, each entity is written once and component data is
+    /// appended to storage without scanning for existing entries. Components
+    /// that update a world-maintained index, such as relationships or
+    /// [`Name`](crate::name::Name), are added after the entity exists, exactly
+    /// as [`World::add_component_to_entity`] would.
+    pub fn spawn_batch<I>(&mut self, bundles: I) -> Vec<EntityId>
+    where
+        I: IntoIterator<Item = Vec<Component>>,
+    {
+        let env = self.env().clone();
+        let bundles: alloc::vec::Vec<Vec<Component>> = bundles.into_iter().collect();
+        let entity_ids = self.entities.reserve(bundles.len() as u32);
 
-impl<'w, I> SpawnBatchIter<'w, I>
-where
-    I: Iterator,
-    I::Item: Bundle<Effect: NoBundleEffect>,
-{
-    #[inline]
-    #[track_caller]
-    pub(crate) fn new(world: &'w mut World, iter: I, caller: MaybeLocation) -> Self {
-        // Ensure all entity allocations are accounted for so `self.entities` can realloc if
-        // necessary
-        world.flush();
+        for (entity_id, bundle) in entity_ids.iter().zip(bundles) {
+            let mut component_types = Vec::new(&env);
+            let mut indexed = Vec::new(&env);
+            for component in bundle.iter() {
+                let component_type = component.component_type();
+                if self.is_indexed(component_type) {
+                    indexed.push_back(component);
+                } else if component_types.contains(component_type) {
+                    // A later component of the same type replaces the earlier one
+                    self.storage.add_component(entity_id, component);
+                } else {
+                    self.components.register_component(component_type.clone());
+                    component_types.push_back(component_type.clone());
+                    self.storage.append_component(entity_id, component);
+                }
+            }
+            self.entities.insert_reserved(entity_id, component_types);
+            for component in indexed.iter() {
+                self.add_component_to_entity(entity_id, component);
+            }
+        }
 
-        let change_tick = world.change_tick();
+        entity_ids
+    }
+}
 
-        let (lower, upper) = iter.size_hint();
-        let length = upper.unwrap_or(lower);
-        world.entities.reserve(length as u32);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::ComponentTrait;
+    use crate::name::Name;
+    use soroban_sdk::{symbol_short, Bytes, Env};
 
-        let mut spawner = BundleSpawner::new::<I::Item>(world, change_tick);
-        spawner.reserve_storage(length);
+    fn invader(env: &Env, row: u8, column: u8) -> Vec<Component> {
+        let mut components = Vec::new(env);
+        components.push_back(Component::new(
+            symbol_short!("position"),
+            Bytes::from_array(env, &[row, column]),
+        ));
+        components.push_back(Component::new(
+            symbol_short!("invader"),
+            Bytes::from_array(env, &[row]),
+        ));
+        components
+    }
 
-        Self {
-            inner: iter,
-            spawner,
-            caller,
+    fn invader_grid(env: &Env) -> alloc::vec::Vec<Vec<Component>> {
+        let mut bundles = alloc::vec::Vec::new();
+        for row in 0..5 {
+            for column in 0..11 {
+                bundles.push(invader(env, row, column));
+            }
         }
+        bundles
     }
-}
 
-impl<I> Drop for SpawnBatchIter<'_, I>
-where
-    I: Iterator,
-    I::Item: Bundle,
-{
-    fn drop(&mut self) {
-        // Iterate through self in order to spawn remaining bundles.
-        for _ in &mut *self {}
-        // Apply any commands from those operations.
-        // SAFETY: `self.spawner` will be dropped immediately after this call.
-        unsafe { self.spawner.flush_commands() };
+    #[test]
+    fn test_spawn_batch_matches_spawn() {
+        let env = Env::default();
+        let mut world = World::with_env(&env);
+        let ids = world.spawn_batch(invader_grid(&env));
+
+        assert_eq!(ids.len(), 55);
+        assert_eq!(world.entity_count(), 55);
+        assert_eq!(world.storage.len(), 110);
+        let last = ids.get(54).unwrap();
+        assert_eq!(
+            world
+                .get_component(last, &symbol_short!("position"))
+                .unwrap()
+                .data(),
+            &Bytes::from_array(&env, &[4, 10])
+        );
+        assert_eq!(
+            world
+                .query_entities(&[symbol_short!("position"), symbol_short!("invader")])
+                .len(),
+            55
+        );
     }
-}
 
-impl<I> Iterator for SpawnBatchIter<'_, I>
-where
-    I: Iterator,
-    I::Item: Bundle,
-{
-    type Item = Entity;
+    #[test]
+    fn test_spawn_batch_reuses_ids_and_indexes() {
+        let env = Env::default();
+        let mut world = World::with_env(&env);
+        let first = world.spawn_empty().id();
+        world.despawn(first);
 
-    fn next(&mut self) -> Option<Entity> {
-        let bundle = self.inner.next()?;
-        // SAFETY: bundle matches spawner type
-        unsafe { Some(self.spawner.spawn(bundle, self.caller).0) }
-    }
+        let mut bundle = Vec::new(&env);
+        bundle.push_back(Component::new(
+            symbol_short!("hp"),
+            Bytes::from_array(&env, &[1]),
+        ));
+        bundle.push_back(Component::new(
+            symbol_short!("hp"),
+            Bytes::from_array(&env, &[2]),
+        ));
+        let name = symbol_short!("boss");
+        bundle.push_back(Component::new(
+            Name::component_type(),
+            Name::new(name.clone()).serialize(&env),
+        ));
 
-    fn size_hint(&self) -> (usize, Option<usize>) {
-        self.inner.size_hint()
+        let ids = world.spawn_batch([bundle]);
+        let boss = ids.get(0).unwrap();
+        assert_eq!(boss, EntityId::new(first.id(), first.generation() + 1));
+        assert_eq!(world.entity_by_name(&name), Some(boss));
+        assert_eq!(world.storage.get_entity_components(boss).len(), 2);
+        assert_eq!(
+            world
+                .get_component(boss, &symbol_short!("hp"))
+                .unwrap()
+                .data(),
+            &Bytes::from_array(&env, &[2])
+        );
     }
-}
 
-impl<I, T> ExactSizeIterator for SpawnBatchIter<'_, I>
-where
-    I: ExactSizeIterator<Item = T>,
-    T: Bundle,
-{
-    fn len(&self) -> usize {
-        self.inner.len()
-    }
-}
+    #[test]
+    fn test_spawn_batch_uses_less_cpu() {
+        let env = Env::default();
+        env.cost_estimate().budget().reset_unlimited();
+        let mut world = World::with_env(&env);
+        let grid = invader_grid(&env);
+        env.cost_estimate().budget().reset_tracker();
+        for bundle in grid {
+            world.spawn(bundle);
+        }
+        let looped = env.cost_estimate().budget().cpu_instruction_cost();
 
-impl<I, T> FusedIterator for SpawnBatchIter<'_, I>
-where
-    I: FusedIterator<Item = T>,
-    T: Bundle,
-{
-}
+        let env = Env::default();
+        env.cost_estimate().budget().reset_unlimited();
+        let mut world = World::with_env(&env);
+        let grid = invader_grid(&env);
+        env.cost_estimate().budget().reset_tracker();
+        world.spawn_batch(grid);
+        let batched = env.cost_estimate().budget().cpu_instruction_cost();
 
-// SAFETY: Newly spawned entities are unique.
-unsafe impl<I: Iterator, T> EntitySetIterator for SpawnBatchIter<'_, I>
-where
-    I: FusedIterator<Item = T>,
-    T: Bundle,
-{
+        assert!(
+            batched * 5 < looped,
+            "batched spawn used {batched} CPU instructions, looped spawn {looped}"
+        );
+    }
 }